@@ -1,24 +1,199 @@
-use crate::api_v3::response::{ApiV3PoolsPage, ApiV3StandardPool, ApiV3StandardPoolKeys};
+use super::history::{self, PoolEvent, PoolTransaction, PoolTransactionKind};
+use super::routing::{RoutingEdge, RoutingGraph};
+use crate::api_v3::response::{
+    ApiV3ClmmPool, ApiV3PoolsPage, ApiV3StandardPool, ApiV3StandardPoolExtended,
+    ApiV3StandardPoolKeys, ApiV3Token,
+};
+use crate::api_v3::serde_helpers::{field_as_string, option_field_as_string};
 use crate::api_v3::{ApiV3Client, PoolFetchParams, PoolSort, PoolSortOrder, PoolType};
 use crate::builder::SwapInstructionsBuilder;
 use crate::types::{
-    ComputeUnitLimits, PriorityFeeConfig, SwapConfig, SwapConfigOverrides, SwapInput,
+    AmmPoolStatus, BundleStatusEnum, ComputeUnitLimits, CongestionLevel, EntrySignal,
+    FlashloanRisk, LiquidityEventType, LpAction, PoolVerificationPolicy, PriorityFeeConfig,
+    RaydiumAccountType, RouteType, SwapConfig, SwapConfigOverrides, SwapError, SwapExecutionMode,
+    SwapInput, TokenFlagStatus, TradingWindow, Trend, Urgency, VolatilityToSlippageMap,
 };
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant, SystemTime};
 
 use anyhow::{anyhow, Context};
 use arrayref::array_ref;
+use futures_util::StreamExt;
+use raydium_library::amm::utils::SwapDirection;
 use raydium_library::amm::AmmKeys;
 use safe_transmute::{transmute_one_pedantic, transmute_to_bytes};
+use serde::Serialize;
+use solana_account_decoder::{UiAccountData, UiAccountEncoding, UiDataSliceConfig};
+use solana_client::nonblocking::pubsub_client::PubsubClient;
 use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_client::rpc_config::{
+    RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcSimulateTransactionConfig,
+    RpcTransactionConfig,
+};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_client::rpc_request::TokenAccountsFilter;
+use solana_sdk::account::Account;
 use solana_sdk::account_info::IntoAccountInfo;
-use solana_sdk::instruction::Instruction;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::message::{Message, VersionedMessage};
+use solana_sdk::packet::PACKET_DATA_SIZE;
+use solana_sdk::program_option::COption;
 use solana_sdk::program_pack::Pack;
+use solana_sdk::rent::Rent;
+use solana_sdk::signature::Signature;
 use solana_sdk::transaction::VersionedTransaction;
 use solana_sdk::{pubkey, pubkey::Pubkey};
+use solana_transaction_status::{TransactionConfirmationStatus, UiTransactionEncoding};
 
 const RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID: Pubkey =
     pubkey!("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8");
+const USDC_MINT: Pubkey = pubkey!("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+/// The openbook dex program hosting the markets of the amm v4 pools
+const OPENBOOK_PROGRAM_ID: Pubkey = pubkey!("srmqPvymJeFKQ4zGQed1GFppgkRHL9kaELCbyksJtPX");
+/// The raydium liquidity pool programs by amm version. AmmInfo carries no version field, the
+/// version of a pool is determined by the program owning its account
+const RAYDIUM_LIQUIDITY_POOL_PROGRAMS: [(u64, Pubkey); 3] = [
+    (2, pubkey!("RVKd61ztZW9GUwhRbbLoYVRE5Xf1B2tVscKqwwqwmqJ")),
+    (3, pubkey!("27haf8L6oxUeXrHrgEgsexjSY5hbVUWEmvv9Nyxg8vQv")),
+    (4, RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID),
+];
+const LATEST_AMM_VERSION: u64 = 4;
+/// The default Orca api, whose whirlpool list is used to find the whirlpool of a pair
+#[cfg(feature = "orca-comparison")]
+const DEFAULT_ORCA_API_URL: &str = "https://api.mainnet.orca.so/v1";
+/// The Orca Whirlpool program owning the whirlpool accounts
+#[cfg(feature = "orca-comparison")]
+const WHIRLPOOL_PROGRAM_ID: Pubkey = pubkey!("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc");
+/// The taker fee assumed for openbook fills, the base fee tier of the openbook dex program
+#[cfg(feature = "openbook-comparison")]
+const OPENBOOK_TAKER_FEE_BPS: u64 = 4;
+/// Approximate number of slots produced per day, assuming 400ms slots
+const SLOTS_PER_DAY: u64 = 216_000;
+/// Upper bound of transactions loaded when reconstructing pool history
+const MAX_POOL_HISTORY_TRANSACTIONS: usize = 1_000;
+/// Upper bound of signature pages walked back when looking for the pool state at a past slot
+const MAX_SIGNATURE_PAGES: usize = 50;
+/// Vault balances are stale when no transaction moved them for this many slots, about 7 minutes
+const VAULT_STALENESS_SLOTS: u64 = 1_000;
+/// How long the pool price is sampled for when estimating volatility
+const VOLATILITY_SAMPLE_WINDOW: Duration = Duration::from_secs(60);
+/// The interval between two volatility samples
+const VOLATILITY_SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+/// How far back the pool price history is sampled when measuring volatility, about 10 minutes
+const VOLATILITY_LOOKBACK_SLOTS: u64 = 1_500;
+/// Number of historical prices sampled when measuring volatility
+const VOLATILITY_HISTORY_SAMPLES: usize = 60;
+/// How far back trades are loaded when measuring the market microstructure, about 30 minutes
+const MICROSTRUCTURE_LOOKBACK_SLOTS: u64 = 4_500;
+/// Number of recent pool transactions inspected for flash loan activity
+const FLASHLOAN_LOOKBACK_TRANSACTIONS: usize = 100;
+/// A single transaction moving the pool price by more than this is considered suspicious
+const FLASHLOAN_PRICE_MOVE_BPS: f64 = 500.0;
+/// Number of performance samples (one per minute) used to estimate the slot time
+const PERFORMANCE_SAMPLES: usize = 10;
+/// The slot time assumed when the rpc returns no performance samples
+const DEFAULT_SLOT_TIME: Duration = Duration::from_millis(400);
+/// The priority fee suggested per congestion level, in basis points of the swap value, indexed
+/// by `CongestionLevel as usize`
+const PRIORITY_FEE_BPS_BY_CONGESTION: [u64; 4] = [1, 5, 15, 50];
+/// Capacity of the price feed channel, slow receivers lag behind and skip the oldest ticks
+const PRICE_FEED_CAPACITY: usize = 64;
+/// A new price tick is emitted once the bid or ask moved by more than this
+const PRICE_FEED_MIN_CHANGE_BPS: f64 = 1.0;
+/// Weights of the liquidity, fee and volatility components of the pool recommendation score
+const POOL_SCORE_WEIGHTS: (f64, f64, f64) = (0.6, 0.3, 0.1);
+/// A base token making up more than this share of the total lp value is flagged as concentrated
+const CONCENTRATION_THRESHOLD_PCT: f64 = 50.0;
+/// Weights of the age, tvl, lp concentration, verification and volatility components of the
+/// pool risk score, summing to 1
+const RISK_SCORE_WEIGHTS: [f64; 5] = [0.2, 0.25, 0.15, 0.25, 0.15];
+/// Weights of the mintability, top holder, liquidity and pool age components of the token risk
+/// score, summing to 1
+const TOKEN_RISK_SCORE_WEIGHTS: [f64; 4] = [0.3, 0.25, 0.25, 0.2];
+/// Weights of the liquidity, yield and stability components of the pool performance score,
+/// summing to 1
+const PERFORMANCE_SCORE_WEIGHTS: (f64, f64, f64) = (0.4, 0.4, 0.2);
+/// A swap counts as confirmed when it lands within this many slots
+const CONFIRMATION_TARGET_SLOTS: i32 = 2;
+/// How long the recent minimum priority fees are reused before they are fetched again
+const PRIORITY_FEE_DISTRIBUTION_TTL: Duration = Duration::from_secs(30);
+/// Protocol defined: the base fee charged per transaction signature
+const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+/// The slippage of the swaps planned by a rebalance
+const REBALANCING_SLIPPAGE_BPS: u16 = 50;
+/// Extra slippage added on top of the price move a sandwich front-run can cause
+const SANDWICH_SLIPPAGE_BUFFER_BPS: u16 = 50;
+/// The slippage of the swaps planned by a dollar cost average plan
+const DCA_SLIPPAGE_BPS: u16 = 50;
+/// The depth chart covers prices up to this fraction away from the spot price
+const DEPTH_CHART_RANGE: f64 = 0.1;
+/// Number of historical prices sampled for the lp entry signal
+const ENTRY_SIGNAL_SAMPLES: usize = 50;
+/// Number of prices sampled for the momentum, covering three ema periods
+const MOMENTUM_SAMPLES: usize = 60;
+/// A price further than this fraction from its moving average is considered trending
+const MOMENTUM_TREND_THRESHOLD: f64 = 0.01;
+/// Number of vault balances sampled over the last week when scoring vault health
+const VAULT_HEALTH_SAMPLES: usize = 168;
+/// A vault holding less than this fraction of its weekly median balance has a health of zero
+const VAULT_HEALTH_FLOOR: f64 = 0.1;
+/// Number of prices sampled when looking for the recent price high and low
+const PRICE_EXTREMES_SAMPLES: usize = 100;
+/// Number of prices sampled per pool when correlating two pools
+const CORRELATION_SAMPLES: usize = 50;
+/// Pools with less liquidity than this are left out of the consensus price
+const CONSENSUS_MIN_TVL_USD: f64 = 10_000.0;
+/// A buy to sell volume ratio above this indicates strong buying pressure
+const STRONG_BUYING_PRESSURE_RATIO: f64 = 1.5;
+/// An amm price further than this many standard deviations from the reference prices is anomalous
+const ANOMALY_Z_SCORE_THRESHOLD: f64 = 3.0;
+/// A pool is imbalanced when one vault holds more than this share of the total value
+const TOKEN_CONCENTRATION_THRESHOLD_PCT: f64 = 70.0;
+/// Pools whose token symbols are at least this similar to those of a legitimate pool are
+/// reported as possible clones
+const CLONE_SIMILARITY_THRESHOLD: f64 = 0.8;
+/// Upper bound of the slippage recommended to limit the value a sandwich attack can extract
+const MAX_SAFE_SLIPPAGE_BPS: u16 = 200;
+/// A pool can absorb a token unlock when selling all of it moves the price by at most this much
+const UNLOCK_ABSORB_MAX_IMPACT_BPS: u64 = 500;
+/// The output improvement a two hop route needs over the direct route to be recommended at
+/// medium urgency
+const MULTI_HOP_MIN_IMPROVEMENT_BPS: u64 = 10;
+/// The number of recent pool transactions searched for the admin transaction that disabled or
+/// halted a pool
+const STATUS_CHANGE_LOOKBACK_TRANSACTIONS: usize = 100;
+/// The number of recent pool transactions searched for the last successful one
+const LAST_TRADE_LOOKBACK_TRANSACTIONS: usize = 20;
+/// The largest share of the capital a kelly sized position may take
+const MAX_KELLY_POSITION_FRACTION: f64 = 0.25;
+/// Number of prices sampled over the last day when estimating the daily variance
+const DAILY_VARIANCE_SAMPLES: usize = 48;
+/// The longest rebalancing interval considered, 30 days
+const MAX_REBALANCING_INTERVAL_HOURS: f64 = 720.0;
+/// A warning is logged when the amm and oracle prices used for the fair value diverge by more
+/// than this
+const FAIR_VALUE_DIVERGENCE_WARNING_BPS: f64 = 500.0;
+/// A warning is logged when the constant product per lp token shrinks by more than this percentage
+/// between two reads
+const K_DRIFT_ALERT_PCT: f64 = 0.01;
+/// Number of pools, by liquidity, in the routing graph searched for arbitrage cycles
+const ARBITRAGE_GRAPH_POOLS: usize = 500;
+/// Upper bound of arbitrage cycles simulated, the cycles with the deepest weakest pool are kept
+const MAX_ARBITRAGE_CYCLES: usize = 100;
+/// How long a fetched routing graph is reused before the pool list is fetched again
+const ROUTING_GRAPH_TTL: Duration = Duration::from_secs(60);
+/// How long an exchange rate is reused before the pool is read again
+const EXCHANGE_RATE_TTL: Duration = Duration::from_secs(1);
+/// Size of the legacy withdraw queue account: owner (32) + head (8) + count (8) + 64 entries of
+/// withdraw amount, coin amount, pc amount and both destination token accounts (88)
+const WITHDRAW_QUEUE_SIZE: usize = 5_680;
+/// Size of an openbook open orders account, including the 5 byte head and 7 byte tail padding
+const OPEN_ORDERS_SIZE: usize = 3_228;
+const DEFAULT_ORACLE_DIVERGENCE_THRESHOLD_BPS: u64 = 200;
 // // https://api-v3.raydium.io/pools/info/mint?mint1=So11111111111111111111111111111111111111112&mint2=EKpQGSJtjMFqKZ9KQanSqYXRcF8fBopzLHYxdM65zcjm&poolType=standard&poolSortField=liquidity&sortType=desc&pageSize=100&page=1
 
 #[derive(Clone)]
@@ -27,6 +202,21 @@ pub struct RaydiumAmm {
     api: ApiV3Client,
     config: SwapConfig,
     load_keys_by_api: bool,
+    routing_graph: Arc<RwLock<Option<(usize, RoutingGraph)>>>,
+    exchange_rates: Arc<RwLock<HashMap<(Pubkey, Pubkey), (Instant, ExchangeRate)>>>,
+    reserve_invariants: Arc<RwLock<HashMap<Pubkey, (u128, u64)>>>,
+    priority_fee_distribution: Arc<RwLock<Option<(Instant, Vec<u64>)>>>,
+    oracle_divergence_threshold_bps: u64,
+    volatility_slippage_map: VolatilityToSlippageMap,
+    websocket_url: Option<String>,
+    pool_verification_policy: PoolVerificationPolicy,
+    flagged_mints: HashMap<Pubkey, String>,
+    check_api_blacklist: bool,
+    strict_mode: bool,
+    trading_windows: Vec<TradingWindow>,
+    http: reqwest::Client,
+    #[cfg(feature = "orca-comparison")]
+    orca_api_url: String,
 }
 
 // todo: Builder pattern for this
@@ -36,6 +226,15 @@ pub struct RaydiumAmmExecutorOpts {
     pub cu_limits: Option<ComputeUnitLimits>,
     pub wrap_and_unwrap_sol: Option<bool>,
     pub load_keys_by_api: Option<bool>,
+    pub oracle_divergence_threshold_bps: Option<u64>,
+    pub volatility_slippage_map: Option<VolatilityToSlippageMap>,
+    pub websocket_url: Option<String>,
+    pub pool_verification_policy: Option<PoolVerificationPolicy>,
+    pub flagged_mints: Option<HashMap<Pubkey, String>>,
+    pub check_api_blacklist: Option<bool>,
+    pub strict_mode: Option<bool>,
+    pub trading_windows: Option<Vec<TradingWindow>>,
+    pub orca_api_url: Option<String>,
 }
 
 impl RaydiumAmmExecutorOpts {
@@ -45,6 +244,15 @@ impl RaydiumAmmExecutorOpts {
             cu_limits: None,
             wrap_and_unwrap_sol: Some(true),
             load_keys_by_api: Some(true),
+            oracle_divergence_threshold_bps: Some(DEFAULT_ORACLE_DIVERGENCE_THRESHOLD_BPS),
+            volatility_slippage_map: Some(VolatilityToSlippageMap::default()),
+            websocket_url: None,
+            pool_verification_policy: Some(PoolVerificationPolicy::default()),
+            flagged_mints: None,
+            check_api_blacklist: Some(false),
+            strict_mode: Some(false),
+            trading_windows: None,
+            orca_api_url: None,
         }
     }
     
@@ -62,11 +270,38 @@ impl RaydiumAmm {
             cu_limits,
             wrap_and_unwrap_sol,
             load_keys_by_api,
+            oracle_divergence_threshold_bps,
+            volatility_slippage_map,
+            websocket_url,
+            pool_verification_policy,
+            flagged_mints,
+            check_api_blacklist,
+            strict_mode,
+            trading_windows,
+            orca_api_url,
         } = config;
+        #[cfg(not(feature = "orca-comparison"))]
+        let _ = orca_api_url;
         Self {
             client,
             api,
             load_keys_by_api: load_keys_by_api.unwrap_or(true),
+            oracle_divergence_threshold_bps: oracle_divergence_threshold_bps
+                .unwrap_or(DEFAULT_ORACLE_DIVERGENCE_THRESHOLD_BPS),
+            volatility_slippage_map: volatility_slippage_map.unwrap_or_default(),
+            websocket_url,
+            pool_verification_policy: pool_verification_policy.unwrap_or_default(),
+            flagged_mints: flagged_mints.unwrap_or_default(),
+            check_api_blacklist: check_api_blacklist.unwrap_or(false),
+            strict_mode: strict_mode.unwrap_or(false),
+            trading_windows: trading_windows.unwrap_or_default(),
+            http: reqwest::Client::new(),
+            #[cfg(feature = "orca-comparison")]
+            orca_api_url: orca_api_url.unwrap_or_else(|| DEFAULT_ORCA_API_URL.to_string()),
+            routing_graph: Default::default(),
+            exchange_rates: Default::default(),
+            reserve_invariants: Default::default(),
+            priority_fee_distribution: Default::default(),
             config: SwapConfig {
                 priority_fee,
                 cu_limits,
@@ -88,6 +323,14 @@ impl RaydiumAmm {
     // 最后返回一个 RaydiumAmmQuote 结构体,包含交换的详细信息       
 
     pub async fn quote(&self, swap_input: &SwapInput) -> anyhow::Result<RaydiumAmmQuote> {
+        Ok(self.quote_with_state(swap_input).await?.0)
+    }
+
+    // 与 quote 相同,同时返回报价所用的池子状态,便于基于同一份金库数量计算价格影响等指标
+    async fn quote_with_state(
+        &self,
+        swap_input: &SwapInput,
+    ) -> anyhow::Result<(RaydiumAmmQuote, RaydiumAmmPoolState)> {
         // 为什么要检查输入和输出代币是否相同？
         // 因为在交换过程中，输入代币和输出代币必须是不同的，否则没有意义。
         if swap_input.input_token_mint == swap_input.output_token_mint {
@@ -101,84 +344,321 @@ impl RaydiumAmm {
         // 如果 swap_input.market 已经有值，则直接使用它
         let mut pool_id = swap_input.market;
         if pool_id.is_none() {
-            let response: ApiV3PoolsPage<ApiV3StandardPool> = self
-                .api
-                .fetch_pool_by_mints(
-                    &swap_input.input_token_mint,
-                    Some(&swap_input.output_token_mint),
-                    &PoolFetchParams {
-                        pool_type: PoolType::Standard,
-                        pool_sort: PoolSort::Liquidity,
-                        sort_type: PoolSortOrder::Descending,
-                        page_size: 10,
-                        page: 1,
-                    },
+            // exact-out 时 amount 是输出数量,不参与按流动性过滤池子
+            let amount_in = if swap_input.mode.amount_specified_is_input() {
+                swap_input.amount
+            } else {
+                0
+            };
+            let recommended = self
+                .get_recommended_pool(
+                    swap_input.input_token_mint,
+                    swap_input.output_token_mint,
+                    amount_in,
                 )
                 .await?;
-            pool_id = response.pools.into_iter().find_map(|pool| {
-                if pool.mint_a.address == swap_input.input_token_mint
-                    && pool.mint_b.address == swap_input.output_token_mint
-                    || pool.mint_a.address == swap_input.output_token_mint
-                        && pool.mint_b.address == swap_input.input_token_mint
-                        && pool.program_id == RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID
-                {
-                    Some(pool.id)
-                } else {
-                    None
-                }
-            });
+            pool_id = Some(recommended.pool_id);
         }
 
         let Some(pool_id) = pool_id else {
             return Err(anyhow!("Failed to get market for swap"));
         };
+        if self.strict_mode {
+            for mint in [swap_input.input_token_mint, swap_input.output_token_mint] {
+                self.is_token_flagged(mint).await?;
+            }
+        }
 
-        let (amm_keys, market_keys) = if self.load_keys_by_api {
-            let response = self
-                .api
-                .fetch_pool_keys_by_ids::<ApiV3StandardPoolKeys>(
-                    [&pool_id].into_iter().map(|id| id.to_string()).collect(),
-                )
-                .await?;
-            let keys = response.first().context(format!(
-                "Failed to get pool keys for raydium standard pool {}",
-                pool_id
-            ))?;
+        let state = self.fetch_pool_state(pool_id).await?;
+
+        let quote = Self::quote_from_vault_amounts(
+            state.coin_vault_amount,
+            state.pc_vault_amount,
+            state.amm.fees.swap_fee_numerator,
+            state.amm.fees.swap_fee_denominator,
+            state.amm.coin_decimals as u8,
+            state.amm.pc_decimals as u8,
+            swap_input.input_token_mint,
+            swap_input.output_token_mint,
+            state.amm_keys.amm_coin_mint,
+            &SwapInput {
+                market: Some(pool_id),
+                ..*swap_input
+            },
+        )?;
+        let quote = RaydiumAmmQuote {
+            amm_keys: state.amm_keys.clone(),
+            market_keys: state.market_keys,
+            ..quote
+        };
+        Ok((quote, state))
+    }
 
-            (AmmKeys::try_from(keys)?, MarketKeys::try_from(keys)?)
+    // 不发送任何网络请求,直接根据金库数量和手续费率计算报价,用于模拟和测试
+    // coin_mint 用于判断兑换方向,input_mint 和 output_mint 必须一个是 coin,另一个是 pc
+    // 返回的 amm_keys 和 market_keys 中除 coin 和 pc 的 mint 外都是占位的默认地址,
+    // 需要替换为池子真实的地址后才能用于构建交易
+    #[allow(clippy::too_many_arguments)]
+    pub fn quote_from_vault_amounts(
+        coin_vault: u64,
+        pc_vault: u64,
+        fee_numerator: u64,
+        fee_denominator: u64,
+        coin_decimals: u8,
+        pc_decimals: u8,
+        input_mint: Pubkey,
+        output_mint: Pubkey,
+        coin_mint: Pubkey,
+        swap_input: &SwapInput,
+    ) -> anyhow::Result<RaydiumAmmQuote> {
+        if input_mint == output_mint {
+            return Err(anyhow!(
+                "Input token cannot equal output token {}",
+                input_mint
+            ));
+        }
+        let (direction, pc_mint) = if input_mint == coin_mint {
+            (SwapDirection::Coin2PC, output_mint)
+        } else if output_mint == coin_mint {
+            (SwapDirection::PC2Coin, input_mint)
         } else {
-            let amm_keys = raydium_library::amm::utils::load_amm_keys(
+            return Err(anyhow!(
+                "Neither {} nor {} is the coin mint {}",
+                input_mint,
+                output_mint,
+                coin_mint
+            ));
+        };
+        let coin_to_pc = matches!(direction, SwapDirection::Coin2PC);
+
+        let amount_specified_is_input = swap_input.mode.amount_specified_is_input();
+        let (other_amount, other_amount_threshold) = raydium_library::amm::swap_with_slippage(
+            pc_vault,
+            coin_vault,
+            fee_numerator,
+            fee_denominator,
+            direction,
+            swap_input.amount,
+            amount_specified_is_input,
+            swap_input.slippage_bps as u64,
+        )?;
+        log::debug!(
+            "raw quote: {}. raw other_amount_threshold: {}",
+            other_amount,
+            other_amount_threshold
+        );
+
+        let market = swap_input.market.unwrap_or_default();
+        Ok(RaydiumAmmQuote {
+            market,
+            input_mint,
+            output_mint,
+            amount: swap_input.amount,
+            other_amount,
+            other_amount_threshold,
+            amount_specified_is_input,
+            input_mint_decimals: if coin_to_pc {
+                coin_decimals
+            } else {
+                pc_decimals
+            },
+            output_mint_decimals: if coin_to_pc {
+                pc_decimals
+            } else {
+                coin_decimals
+            },
+            swap_fee_numerator: fee_numerator,
+            swap_fee_denominator: fee_denominator,
+            amm_keys: AmmKeys {
+                amm_pool: market,
+                amm_coin_mint: coin_mint,
+                amm_pc_mint: pc_mint,
+                amm_authority: Pubkey::default(),
+                amm_target: Pubkey::default(),
+                amm_coin_vault: Pubkey::default(),
+                amm_pc_vault: Pubkey::default(),
+                amm_lp_mint: Pubkey::default(),
+                amm_open_order: Pubkey::default(),
+                market_program: Pubkey::default(),
+                market: Pubkey::default(),
+                nonce: 0,
+            },
+            market_keys: MarketKeys {
+                event_queue: Pubkey::default(),
+                bids: Pubkey::default(),
+                asks: Pubkey::default(),
+                coin_vault: Pubkey::default(),
+                pc_vault: Pubkey::default(),
+                vault_signer_key: Pubkey::default(),
+            },
+        })
+    }
+
+    // 加载 amm 池和对应 openbook 市场的账户地址
+    // 根据 load_keys_by_api 决定是通过 API 获取还是直接从链上读取
+    // 如果通过 API 获取失败,则回退到从链上读取
+    async fn load_pool_keys(&self, pool_id: Pubkey) -> anyhow::Result<(AmmKeys, MarketKeys)> {
+        if self.load_keys_by_api {
+            match self.load_pool_keys_by_api(pool_id).await {
+                Ok(keys) => return Ok(keys),
+                Err(e) => log::warn!(
+                    "Failed to load keys for pool {} from api, falling back to chain: {}",
+                    pool_id,
+                    e
+                ),
+            }
+        }
+
+        let amm_keys = self.rebuild_amm_keys_from_chain(pool_id).await?;
+        let market_keys = MarketKeys::from(
+            &raydium_library::amm::openbook::get_keys_for_market(
                 &self.client,
-                &RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID,
-                &pool_id,
+                &amm_keys.market_program,
+                &amm_keys.market,
+            )
+            .await?,
+        );
+
+        Ok((amm_keys, market_keys))
+    }
+
+    async fn load_pool_keys_by_api(
+        &self,
+        pool_id: Pubkey,
+    ) -> anyhow::Result<(AmmKeys, MarketKeys)> {
+        let response = self
+            .api
+            .fetch_pool_keys_by_ids::<ApiV3StandardPoolKeys>(
+                [&pool_id].into_iter().map(|id| id.to_string()).collect(),
             )
             .await?;
+        let keys = response.first().context(format!(
+            "Failed to get pool keys for raydium standard pool {}",
+            pool_id
+        ))?;
 
-            let market_keys = MarketKeys::from(
-                &raydium_library::amm::openbook::get_keys_for_market(
-                    &self.client,
-                    &amm_keys.market_program,
-                    &amm_keys.market,
-                )
-                .await?,
-            );
+        Ok((AmmKeys::try_from(keys)?, MarketKeys::try_from(keys)?))
+    }
 
-            (amm_keys, market_keys)
-        };
+    // 不调用 API,直接读取链上的 AmmInfo 账户并根据其中记录的地址重建 AmmKeys
+    // 逻辑与 raydium_library 中的 load_amm_keys 一致,用于缓存的 AmmKeys 失效或 API 不可用时
+    pub async fn rebuild_amm_keys_from_chain(&self, pool_id: Pubkey) -> anyhow::Result<AmmKeys> {
+        let account = self.client.get_account(&pool_id).await?;
+        let amm = decode_amm_info(&account.data)?;
+        let nonce = u8::try_from(amm.nonce)?;
+        let amm_authority = Pubkey::create_program_address(
+            &[raydium_amm::processor::AUTHORITY_AMM, &[nonce]],
+            &RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID,
+        )?;
+        Ok(AmmKeys {
+            amm_pool: pool_id,
+            amm_coin_mint: amm.coin_vault_mint,
+            amm_pc_mint: amm.pc_vault_mint,
+            amm_authority,
+            amm_target: amm.target_orders,
+            amm_coin_vault: amm.coin_vault,
+            amm_pc_vault: amm.pc_vault,
+            amm_lp_mint: amm.lp_mint,
+            amm_open_order: amm.open_orders,
+            market_program: amm.market_program,
+            market: amm.market,
+            nonce,
+        })
+    }
+
+    // 获取 amm 池的当前状态,包括解析后的 AmmInfo 以及扣除待提取 pnl 后的金库数量
+    pub async fn fetch_pool_state(&self, pool_id: Pubkey) -> anyhow::Result<RaydiumAmmPoolState> {
+        let (amm_keys, market_keys) = self.load_pool_keys(pool_id).await?;
 
         // reload accounts data to calculate amm pool vault amount
         // get multiple accounts at the same time to ensure data consistency
-        let load_pubkeys = vec![
-            pool_id,
+        let load_pubkeys = Self::pool_state_pubkeys(&amm_keys, &market_keys);
+        let rsps = crate::utils::get_multiple_account_data(&self.client, &load_pubkeys).await?;
+        Self::pool_state_from_accounts(amm_keys, market_keys, array_ref![rsps, 0, 7])
+    }
+
+    // 批量获取多个池子的状态,按 pool_ids 的顺序返回
+    // 所有池子的 keys 通过一次 API 请求获取,需要的账户合并为分块的 get_multiple_accounts 请求(每块 100 个账户);
+    // API 中找不到 keys 的池子退回到逐个调用 fetch_pool_state
+    pub async fn fetch_pool_states(
+        &self,
+        pool_ids: &[Pubkey],
+    ) -> anyhow::Result<Vec<RaydiumAmmPoolState>> {
+        if pool_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        // api 对不认识的池子 id 返回 null
+        let api_keys = self
+            .api
+            .fetch_pool_keys_by_ids::<Option<ApiV3StandardPoolKeys>>(
+                pool_ids.iter().map(|id| id.to_string()).collect(),
+            )
+            .await
+            .unwrap_or_else(|e| {
+                log::warn!(
+                    "Failed to load pool keys from api, falling back to chain: {}",
+                    e
+                );
+                Vec::new()
+            });
+        let mut keys = Vec::new();
+        for pool_keys in api_keys.iter().flatten() {
+            if !pool_ids.contains(&pool_keys.id) {
+                continue;
+            }
+            match (
+                AmmKeys::try_from(pool_keys),
+                MarketKeys::try_from(pool_keys),
+            ) {
+                (Ok(amm_keys), Ok(market_keys)) => keys.push((amm_keys, market_keys)),
+                (Err(e), _) | (_, Err(e)) => {
+                    log::warn!("Invalid api keys for pool {}: {}", pool_keys.id, e)
+                }
+            }
+        }
+
+        let load_pubkeys = keys
+            .iter()
+            .flat_map(|(amm_keys, market_keys)| Self::pool_state_pubkeys(amm_keys, market_keys))
+            .collect::<Vec<_>>();
+        let rsps = crate::utils::get_multiple_account_data(&self.client, &load_pubkeys).await?;
+        let mut states = HashMap::new();
+        for ((amm_keys, market_keys), accounts) in keys.into_iter().zip(rsps.chunks_exact(7)) {
+            let state =
+                Self::pool_state_from_accounts(amm_keys, market_keys, array_ref![accounts, 0, 7])?;
+            states.insert(state.pool_id, state);
+        }
+
+        let mut ordered = Vec::with_capacity(pool_ids.len());
+        for pool_id in pool_ids {
+            match states.remove(pool_id) {
+                Some(state) => ordered.push(state),
+                None => ordered.push(self.fetch_pool_state(*pool_id).await?),
+            }
+        }
+        Ok(ordered)
+    }
+
+    // fetch_pool_state 需要读取的账户,顺序与 pool_state_from_accounts 的参数一致
+    fn pool_state_pubkeys(amm_keys: &AmmKeys, market_keys: &MarketKeys) -> [Pubkey; 7] {
+        [
+            amm_keys.amm_pool,
             amm_keys.amm_target,
             amm_keys.amm_pc_vault,
             amm_keys.amm_coin_vault,
             amm_keys.amm_open_order,
             amm_keys.market,
             market_keys.event_queue,
-        ];
-        let rsps = crate::utils::get_multiple_account_data(&self.client, &load_pubkeys).await?;
-        let accounts = array_ref![rsps, 0, 7];
+        ]
+    }
+
+    // 根据 pool_state_pubkeys 对应的账户数据计算池子状态,便于与其他账户合并到同一个 get_multiple_accounts 请求中
+    fn pool_state_from_accounts(
+        amm_keys: AmmKeys,
+        market_keys: MarketKeys,
+        accounts: &[Option<Account>; 7],
+    ) -> anyhow::Result<RaydiumAmmPoolState> {
+        let pool_id = amm_keys.amm_pool;
         let [amm_account, amm_target_account, amm_pc_vault_account, amm_coin_vault_account, amm_open_orders_account, market_account, market_event_q_account] =
             accounts;
         let amm_account_unpacked = match amm_account.as_ref() {
@@ -190,11 +670,7 @@ impl RaydiumAmm {
                 ));
             }
         };
-        let amm: raydium_amm::state::AmmInfo = transmute_one_pedantic::<super::amm_info::AmmInfo>(
-            transmute_to_bytes(&amm_account_unpacked.clone().data),
-        )
-        .map_err(|e| e.without_src())?
-        .into();
+        let amm = decode_amm_info(&amm_account_unpacked.data)?;
         let _amm_target: raydium_amm::state::TargetOrders =
             transmute_one_pedantic::<raydium_amm::state::TargetOrders>(transmute_to_bytes(
                 &amm_target_account.as_ref().unwrap().clone().data,
@@ -218,11 +694,7 @@ impl RaydiumAmm {
                 let market_account_info = (&amm.market, market_account).into_account_info();
                 let market_event_queue_info =
                     (&(market_keys.event_queue), market_event_q_account).into_account_info();
-                let amm_authority = Pubkey::find_program_address(
-                    &[raydium_amm::processor::AUTHORITY_AMM],
-                    &RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID,
-                )
-                .0;
+                let amm_authority = Self::get_pool_authority_pda().0;
                 let lamports = &mut 0;
                 let data = &mut [0u8];
                 let owner = Pubkey::default();
@@ -265,54 +737,56 @@ impl RaydiumAmm {
                 (amm_pool_pc_vault_amount, amm_pool_coin_vault_amount)
             };
 
-        let (direction, coin_to_pc) = if swap_input.input_token_mint == amm_keys.amm_coin_mint
-            && swap_input.output_token_mint == amm_keys.amm_pc_mint
-        {
-            (raydium_library::amm::utils::SwapDirection::Coin2PC, true)
-        } else {
-            (raydium_library::amm::utils::SwapDirection::PC2Coin, false)
-        };
-
-        let amount_specified_is_input = swap_input.mode.amount_specified_is_input();
-        let (other_amount, other_amount_threshold) = raydium_library::amm::swap_with_slippage(
-            amm_pool_pc_vault_amount,
-            amm_pool_coin_vault_amount,
-            amm.fees.swap_fee_numerator,
-            amm.fees.swap_fee_denominator,
-            direction,
-            swap_input.amount,
-            amount_specified_is_input,
-            swap_input.slippage_bps as u64,
-        )?;
-        log::debug!(
-            "raw quote: {}. raw other_amount_threshold: {}",
-            other_amount,
-            other_amount_threshold
-        );
-
-        Ok(RaydiumAmmQuote {
-            market: pool_id,
-            input_mint: swap_input.input_token_mint,
-            output_mint: swap_input.output_token_mint,
-            amount: swap_input.amount,
-            other_amount,
-            other_amount_threshold,
-            amount_specified_is_input,
-            input_mint_decimals: if coin_to_pc {
-                amm.coin_decimals
-            } else {
-                amm.pc_decimals
-            } as u8,
-            output_mint_decimals: if coin_to_pc {
-                amm.pc_decimals
-            } else {
-                amm.coin_decimals
-            } as u8,
+        Ok(RaydiumAmmPoolState {
+            pool_id,
+            amm,
             amm_keys,
             market_keys,
+            coin_vault_balance: amm_coin_vault.amount,
+            pc_vault_balance: amm_pc_vault.amount,
+            coin_vault_amount: amm_pool_coin_vault_amount,
+            pc_vault_amount: amm_pool_pc_vault_amount,
         })
     }
 
+    // 计算钱包在某个池子中的净头寸价值(USD)
+    // 包括 LP 份额对应的 coin 和 pc 价值,以及钱包中 coin 和 pc 两种代币 ATA 的余额价值
+    pub async fn get_net_position_value(
+        &self,
+        owner: Pubkey,
+        pool_id: Pubkey,
+        coin_price_usd: f64,
+        pc_price_usd: f64,
+    ) -> anyhow::Result<f64> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        let atas = [
+            state.amm_keys.amm_lp_mint,
+            state.amm_keys.amm_coin_mint,
+            state.amm_keys.amm_pc_mint,
+        ]
+        .map(|mint| spl_associated_token_account::get_associated_token_address(&owner, &mint));
+        // 一次 get_multiple_accounts 请求获取所有 ATA
+        let accounts = self.client.get_multiple_accounts(&atas).await?;
+        let lp_balance = crate::utils::token_account_amount(accounts[0].as_ref())?;
+        let coin_balance = crate::utils::token_account_amount(accounts[1].as_ref())?;
+        let pc_balance = crate::utils::token_account_amount(accounts[2].as_ref())?;
+
+        let coin_decimals = state.amm.coin_decimals;
+        let pc_decimals = state.amm.pc_decimals;
+        let lp_value = if state.amm.lp_amount == 0 {
+            0.0
+        } else {
+            let pool_value = crate::utils::to_ui_amount(state.coin_vault_amount, coin_decimals)
+                * coin_price_usd
+                + crate::utils::to_ui_amount(state.pc_vault_amount, pc_decimals) * pc_price_usd;
+            pool_value * lp_balance as f64 / state.amm.lp_amount as f64
+        };
+
+        Ok(lp_value
+            + crate::utils::to_ui_amount(coin_balance, coin_decimals) * coin_price_usd
+            + crate::utils::to_ui_amount(pc_balance, pc_decimals) * pc_price_usd)
+    }
+
     // 定义一个异步函数swap_instructions，用于生成交换指令
     pub async fn swap_instructions(
         &self,
@@ -323,8 +797,11 @@ impl RaydiumAmm {
         // 交换配置覆盖
         overrides: Option<&SwapConfigOverrides>,
     ) -> anyhow::Result<Vec<solana_sdk::instruction::Instruction>> {
+        self.ensure_swap_allowed(&output).await?;
         // 调用make_swap函数，生成交换指令构建器
-        let builder = self.make_swap(input_pubkey, output, overrides).await?;
+        let builder = self
+            .make_swap(input_pubkey, output, overrides, vec![])
+            .await?;
         // 构建交换指令
         builder.build_instructions()
     }
@@ -340,113 +817,8263 @@ impl RaydiumAmm {
         // 接收一个overrides参数，表示覆盖配置
         overrides: Option<&SwapConfigOverrides>,
     ) -> anyhow::Result<VersionedTransaction> {
+        self.ensure_swap_allowed(&output).await?;
         // 调用make_swap函数，生成交换交易
-        let builder = self.make_swap(input_pubkey, output, overrides).await?;
+        let builder = self
+            .make_swap(input_pubkey, output, overrides, vec![])
+            .await?;
         // 构建交易
         builder.build_transaction(Some(&input_pubkey), None)
     }
 
-    // 更新配置
-    pub fn update_config(&mut self, config: &SwapConfig) {
-        // 将传入的配置赋值给self的config
-        self.config = *config;
+    // 检查交易序列化后的大小是否超过 Solana 的数据包上限(1232 字节)
+    // 如果超出上限,给出缩减交易大小的建议
+    // 如果去掉 SetComputeUnitLimit 指令后仍然放不下,则返回 SwapError::TransactionTooLarge
+    pub async fn verify_transaction_size(
+        &self,
+        tx: &VersionedTransaction,
+    ) -> anyhow::Result<TransactionSizeReport> {
+        let size = bincode::serialize(tx)?.len();
+        if size <= PACKET_DATA_SIZE {
+            return Ok(TransactionSizeReport {
+                size,
+                exceeds_limit: false,
+                suggestions: Vec::new(),
+            });
+        }
+
+        let account_keys = tx.message.static_account_keys();
+        let compute_budget_ixs = tx
+            .message
+            .instructions()
+            .iter()
+            .filter(|ix| {
+                account_keys.get(ix.program_id_index as usize)
+                    == Some(&solana_sdk::compute_budget::id())
+            })
+            .collect::<Vec<_>>();
+        // SetComputeUnitLimit 的指令数据以 2 开头,序列化后占用 8 字节
+        // 如果它是唯一的计算预算指令,还可以省掉计算预算程序的 32 字节公钥
+        let has_cu_limit_ix = compute_budget_ixs
+            .iter()
+            .any(|ix| ix.data.first() == Some(&2));
+        let mut reclaimable = 0;
+        let mut suggestions = Vec::new();
+        if has_cu_limit_ix {
+            reclaimable += 8;
+            if compute_budget_ixs.len() == 1 {
+                reclaimable += 32;
+            }
+            suggestions.push("Remove the SetComputeUnitLimit instruction".to_string());
+        }
+        if tx
+            .message
+            .address_table_lookups()
+            .is_some_and(|lookups| !lookups.is_empty())
+        {
+            suggestions.push("Reduce the number of address lookup tables".to_string());
+        }
+
+        if size - reclaimable > PACKET_DATA_SIZE {
+            return Err(SwapError::TransactionTooLarge {
+                size,
+                limit: PACKET_DATA_SIZE,
+            }
+            .into());
+        }
+
+        Ok(TransactionSizeReport {
+            size,
+            exceeds_limit: true,
+            suggestions,
+        })
     }
 
-    // 异步函数，用于创建交换指令
-    async fn make_swap(
+    // 获取某个 amm 池在 [from_slot, to_slot] 区间内成功执行的交易,按时间从旧到新排列
+    // 通过 getSignaturesForAddress 分页获取签名,再逐笔拉取交易并根据金库余额变化还原交易内容
+    // 为了控制 RPC 请求数量,最多只处理 MAX_POOL_HISTORY_TRANSACTIONS 笔交易
+    pub async fn get_pool_transactions(
         &self,
-        input_pubkey: Pubkey, // 输入公钥
-        output: RaydiumAmmQuote, // 交换输出
-        overrides: Option<&SwapConfigOverrides>, // 交换配置覆盖
-    ) -> anyhow::Result<SwapInstructionsBuilder> { // 返回交换指令构建器
-        // 获取优先费用
-        let priority_fee = overrides
-            .and_then(|o| o.priority_fee)
-            .or(self.config.priority_fee);
-        // 获取计算单元限制
-        let cu_limits = overrides
-            .and_then(|o| o.cu_limits)
-            .or(self.config.cu_limits);
-        // 获取是否需要包装和解包 SOL
-        let wrap_and_unwrap_sol = overrides
-            .and_then(|o| o.wrap_and_unwrap_sol)
-            .or(self.config.wrap_and_unwrap_sol)
-            .unwrap_or(true);
-
-        // 创建交换指令构建器
-        let mut builder = SwapInstructionsBuilder::default();
-        // 处理令牌包装和解包以及账户创建
-        let _associated_accounts = builder.handle_token_wrapping_and_accounts_creation(
-            input_pubkey,
-            wrap_and_unwrap_sol,
-            if output.amount_specified_is_input {
-                output.amount
-            } else {
-                output.other_amount
-            },
-            output.input_mint,
-            output.output_mint,
-            spl_token::ID,
-            spl_token::ID,
-            None,
-        )?;
-        // 创建交换指令
-        let instruction = swap_instruction(
-            &RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID,
-            &output.amm_keys,
-            &output.market_keys,
-            &input_pubkey,
-            &spl_associated_token_account::get_associated_token_address(
-                &input_pubkey,
-                &output.input_mint,
-            ),
-            &spl_associated_token_account::get_associated_token_address(
-                &input_pubkey,
-                &output.output_mint,
-            ),
-            output.amount,
-            output.other_amount_threshold,
-            output.amount_specified_is_input,
-        )?;
-        // 将交换指令添加到构建器中
-        builder.swap_instruction = Some(instruction);
+        pool_id: Pubkey,
+        from_slot: u64,
+        to_slot: Option<u64>,
+    ) -> anyhow::Result<Vec<PoolTransaction>> {
+        let (amm_keys, _) = self.load_pool_keys(pool_id).await?;
+        let signatures = self
+            .get_pool_signatures(pool_id, from_slot, to_slot)
+            .await?;
+        self.fetch_pool_transactions(signatures, &amm_keys).await
+    }
 
-        // 处理计算单元参数
-        let compute_units = builder
-            .handle_compute_units_params(cu_limits, &self.client, input_pubkey)
+    // 拉取一笔交易并解析其对池子金库的影响,未涉及池子金库的交易返回 None
+    async fn fetch_pool_transaction(
+        &self,
+        signature: Signature,
+        amm_keys: &AmmKeys,
+    ) -> anyhow::Result<Option<PoolTransaction>> {
+        let tx = self
+            .client
+            .get_transaction_with_config(
+                &signature,
+                RpcTransactionConfig {
+                    encoding: Some(UiTransactionEncoding::Base64),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    max_supported_transaction_version: Some(0),
+                },
+            )
             .await?;
-        // 处理优先费用参数
-        builder.handle_priority_fee_params(priority_fee, compute_units, input_pubkey)?;
+        Ok(history::parse_pool_transaction(
+            signature,
+            &tx,
+            &amm_keys.amm_coin_vault,
+            &amm_keys.amm_pc_vault,
+            &amm_keys.amm_lp_mint,
+        ))
+    }
 
-        // 返回交换指令构建器
-        Ok(builder)
+    // 按从新到旧的顺序列出 [from_slot, to_slot] 内池子的成功交易签名,最多 MAX_POOL_HISTORY_TRANSACTIONS 个
+    async fn get_pool_signatures(
+        &self,
+        pool_id: Pubkey,
+        from_slot: u64,
+        to_slot: Option<u64>,
+    ) -> anyhow::Result<Vec<Signature>> {
+        let mut signatures = Vec::new();
+        let mut before = None;
+        'pages: loop {
+            let page = self
+                .client
+                .get_signatures_for_address_with_config(
+                    &pool_id,
+                    GetConfirmedSignaturesForAddress2Config {
+                        before,
+                        until: None,
+                        limit: Some(1000),
+                        commitment: Some(CommitmentConfig::confirmed()),
+                    },
+                )
+                .await?;
+            let Some(last) = page.last() else {
+                break;
+            };
+            before = Some(Signature::from_str(&last.signature)?);
+            let is_last_page = page.len() < 1000;
+            for status in page {
+                if status.slot < from_slot {
+                    break 'pages;
+                }
+                if status.err.is_some() || to_slot.is_some_and(|to_slot| status.slot > to_slot) {
+                    continue;
+                }
+                signatures.push(Signature::from_str(&status.signature)?);
+                if signatures.len() >= MAX_POOL_HISTORY_TRANSACTIONS {
+                    break 'pages;
+                }
+            }
+            if is_last_page {
+                break;
+            }
+        }
+        Ok(signatures)
     }
-}
 
-#[derive(Debug)]
-pub struct RaydiumAmmQuote {
-    /// The address of the amm pool
-    pub market: Pubkey,
-    /// The input mint
-    pub input_mint: Pubkey,
-    /// The output mint,
-    pub output_mint: Pubkey,
-    /// The amount specified
-    pub amount: u64,
-    /// The other amount
-    pub other_amount: u64,
-    /// The other amount with slippage
-    pub other_amount_threshold: u64,
-    /// Whether the amount specified is in terms of the input token
-    pub amount_specified_is_input: bool,
-    /// The input mint decimals
-    pub input_mint_decimals: u8,
+    // 并发拉取一组按从新到旧排列的签名对应的池子交易,结果按从旧到新返回
+    async fn fetch_pool_transactions(
+        &self,
+        signatures: Vec<Signature>,
+        amm_keys: &AmmKeys,
+    ) -> anyhow::Result<Vec<PoolTransaction>> {
+        let transactions = futures_util::stream::iter(signatures)
+            .map(|signature| self.fetch_pool_transaction(signature, amm_keys))
+            .buffered(16)
+            .collect::<Vec<_>>()
+            .await;
+
+        let mut transactions = transactions
+            .into_iter()
+            .collect::<anyhow::Result<Vec<_>>>()?
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+        transactions.reverse();
+        Ok(transactions)
+    }
+
+    // 查找 slot 不晚于 target_slot 的最后一笔池子交易,它执行后的金库余额即为 target_slot 时的金库余额
+    // 从最新的签名开始向前翻页,最多翻 MAX_SIGNATURE_PAGES 页
+    pub async fn get_pool_transaction_at_slot(
+        &self,
+        pool_id: Pubkey,
+        target_slot: u64,
+    ) -> anyhow::Result<Option<PoolTransaction>> {
+        let (amm_keys, _) = self.load_pool_keys(pool_id).await?;
+        let mut before = None;
+        for _ in 0..MAX_SIGNATURE_PAGES {
+            let page = self
+                .client
+                .get_signatures_for_address_with_config(
+                    &pool_id,
+                    GetConfirmedSignaturesForAddress2Config {
+                        before,
+                        until: None,
+                        limit: Some(1000),
+                        commitment: Some(CommitmentConfig::confirmed()),
+                    },
+                )
+                .await?;
+            let Some(last) = page.last() else {
+                break;
+            };
+            before = Some(Signature::from_str(&last.signature)?);
+            let is_last_page = page.len() < 1000;
+            for status in page {
+                if status.slot > target_slot || status.err.is_some() {
+                    continue;
+                }
+                let signature = Signature::from_str(&status.signature)?;
+                if let Some(tx) = self.fetch_pool_transaction(signature, &amm_keys).await? {
+                    return Ok(Some(tx));
+                }
+            }
+            if is_last_page {
+                break;
+            }
+        }
+        Ok(None)
+    }
+
+    // 获取最近 lookback_slots 个 slot 内该池子的交易记录
+    pub async fn get_trade_history(
+        &self,
+        pool_id: Pubkey,
+        lookback_slots: u64,
+    ) -> anyhow::Result<Vec<PoolTransaction>> {
+        Ok(self
+            .get_trade_history_window(pool_id, lookback_slots)
+            .await?
+            .transactions)
+    }
+
+    // 与 get_trade_history 相同,同时返回这些交易实际覆盖的 slot 区间
+    // 区间内的交易超过 MAX_POOL_HISTORY_TRANSACTIONS 笔时只保留最新的部分,is_truncated 为 true,
+    // 区间起点为保留的最旧一笔交易的 slot; 按时间换算的指标(成交量、收益率等)应除以实际覆盖的时长
+    pub async fn get_trade_history_window(
+        &self,
+        pool_id: Pubkey,
+        lookback_slots: u64,
+    ) -> anyhow::Result<TradeHistoryWindow> {
+        let current_slot = self.client.get_slot().await?;
+        self.get_pool_transactions_window(
+            pool_id,
+            current_slot.saturating_sub(lookback_slots),
+            current_slot,
+        )
+        .await
+    }
+
+    // 与 get_pool_transactions 相同,同时返回这些交易实际覆盖的 [from_slot, to_slot] 区间,见 get_trade_history_window
+    pub async fn get_pool_transactions_window(
+        &self,
+        pool_id: Pubkey,
+        from_slot: u64,
+        to_slot: u64,
+    ) -> anyhow::Result<TradeHistoryWindow> {
+        let (amm_keys, _) = self.load_pool_keys(pool_id).await?;
+        let signatures = self
+            .get_pool_signatures(pool_id, from_slot, Some(to_slot))
+            .await?;
+        let is_truncated = signatures.len() >= MAX_POOL_HISTORY_TRANSACTIONS;
+        let transactions = self.fetch_pool_transactions(signatures, &amm_keys).await?;
+        let from_slot = match transactions.first() {
+            Some(oldest) if is_truncated => oldest.slot,
+            _ => from_slot,
+        };
+        Ok(TradeHistoryWindow {
+            transactions,
+            from_slot,
+            to_slot,
+            is_truncated,
+        })
+    }
+
+    // 估算 LP 的年化手续费收益率
+    // 通过 get_trade_history_window 汇总回看期内的兑换量,乘以池子的手续费率得到手续费收入,
+    // 再按交易实际覆盖的天数外推为年化; 交易被截断时覆盖的天数小于 lookback_days,is_truncated 为 true
+    pub async fn get_yield_rate(
+        &self,
+        pool_id: Pubkey,
+        lookback_days: u32,
+    ) -> anyhow::Result<YieldRate> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        let window = self
+            .get_trade_history_window(pool_id, lookback_days as u64 * SLOTS_PER_DAY)
+            .await?;
+        let (coin_volume, pc_volume) = window
+            .transactions
+            .iter()
+            .filter_map(PoolTransaction::swap_amounts_in)
+            .fold((0u64, 0u64), |(coin, pc), (coin_in, pc_in)| {
+                (coin.saturating_add(coin_in), pc.saturating_add(pc_in))
+            });
+
+        let (coin_price_usd, pc_price_usd) = self.fetch_pool_token_prices(&state).await?;
+
+        let volume_usd = crate::utils::to_ui_amount(coin_volume, state.amm.coin_decimals)
+            * coin_price_usd
+            + crate::utils::to_ui_amount(pc_volume, state.amm.pc_decimals) * pc_price_usd;
+        let total_fees_collected_usd = volume_usd * state.pool_fee_bps() as f64 / 10_000.0;
+        let days = window.covered_days();
+        let tvl_usd = state.tvl_usd(coin_price_usd, pc_price_usd);
+        let annualized_fee_yield_pct = if tvl_usd > 0.0 {
+            total_fees_collected_usd / days * 365.0 / tvl_usd * 100.0
+        } else {
+            0.0
+        };
+
+        Ok(YieldRate {
+            annualized_fee_yield_pct,
+            total_fees_collected_usd,
+            avg_daily_volume_usd: volume_usd / days,
+            is_truncated: window.is_truncated,
+        })
+    }
+
+    // 获取按流动性排序的前 page_size 个标准池构成的路由图,每个节点是一个 mint
+    // 路由图会被缓存 ROUTING_GRAPH_TTL,期间相同 page_size 的调用直接返回缓存
+    pub async fn get_routing_graph(&self, page_size: usize) -> anyhow::Result<RoutingGraph> {
+        if let Some((cached_page_size, graph)) = self.routing_graph.read().unwrap().as_ref() {
+            if *cached_page_size == page_size && graph.last_updated().elapsed() < ROUTING_GRAPH_TTL
+            {
+                return Ok(graph.clone());
+            }
+        }
+
+        let response: ApiV3PoolsPage<ApiV3StandardPool> = self
+            .api
+            .get_pool_list(&PoolFetchParams {
+                pool_type: PoolType::Standard,
+                pool_sort: PoolSort::Liquidity,
+                sort_type: PoolSortOrder::Descending,
+                page_size: u16::try_from(page_size).unwrap_or(u16::MAX),
+                page: 1,
+            })
+            .await?;
+        let graph = RoutingGraph::from_pools(
+            response
+                .pools
+                .iter()
+                .filter(|pool| pool.program_id == RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID),
+        );
+        *self.routing_graph.write().unwrap() = Some((page_size, graph.clone()));
+        Ok(graph)
+    }
+
+    // 返回各类 Raydium 账户的数据大小(字节),用于估算创建账户所需的租金
+    pub fn account_data_size_for(account_type: RaydiumAccountType) -> usize {
+        match account_type {
+            RaydiumAccountType::AmmInfo => std::mem::size_of::<super::amm_info::AmmInfo>(),
+            RaydiumAccountType::TargetOrders => {
+                std::mem::size_of::<raydium_amm::state::TargetOrders>()
+            }
+            RaydiumAccountType::WithdrawQueue => WITHDRAW_QUEUE_SIZE,
+            RaydiumAccountType::LpMint => spl_token::state::Mint::LEN,
+            RaydiumAccountType::OpenOrders => OPEN_ORDERS_SIZE,
+        }
+    }
+
+    // 计算某类账户达到免租所需的 lamports
+    pub fn rent_for_account_type(account_type: RaydiumAccountType, rent: &Rent) -> u64 {
+        rent.minimum_balance(Self::account_data_size_for(account_type))
+    }
+
+    // 不依赖 API,直接通过 getProgramAccounts 扫描链上的 amm 账户来查找某个交易对的池子
+    // 过滤条件为 AmmInfo 的账户大小,以及 coin mint 和 pc mint 在结构体中的偏移位置
+    pub async fn get_program_accounts_by_mint(
+        &self,
+        coin_mint: Pubkey,
+        pc_mint: Pubkey,
+    ) -> anyhow::Result<Vec<Pubkey>> {
+        let filters = vec![
+            RpcFilterType::DataSize(std::mem::size_of::<super::amm_info::AmmInfo>() as u64),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                std::mem::offset_of!(super::amm_info::AmmInfo, coin_vault_mint),
+                coin_mint.as_ref(),
+            )),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                std::mem::offset_of!(super::amm_info::AmmInfo, pc_vault_mint),
+                pc_mint.as_ref(),
+            )),
+        ];
+        let accounts = self
+            .client
+            .get_program_accounts_with_config(
+                &RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID,
+                RpcProgramAccountsConfig {
+                    filters: Some(filters),
+                    account_config: RpcAccountInfoConfig {
+                        encoding: Some(UiAccountEncoding::Base64),
+                        // only the addresses are needed
+                        data_slice: Some(UiDataSliceConfig {
+                            offset: 0,
+                            length: 0,
+                        }),
+                        commitment: Some(CommitmentConfig::confirmed()),
+                        min_context_slot: None,
+                    },
+                    ..Default::default()
+                },
+            )
+            .await?;
+        Ok(accounts.into_iter().map(|(pubkey, _)| pubkey).collect())
+    }
+
+    // 将 amm 池的现货价格与外部预言机(如 Pyth、Switchboard)价格进行比较
+    // 偏离超过 oracle_divergence_threshold_bps 时标记为异常
+    pub async fn get_price_oracle_comparison(
+        &self,
+        pool_id: Pubkey,
+        oracle_price: f64,
+    ) -> anyhow::Result<OracleComparison> {
+        if oracle_price <= 0.0 {
+            return Err(anyhow!(
+                "Oracle price must be positive, got {}",
+                oracle_price
+            ));
+        }
+        let amm_price = self.fetch_pool_state(pool_id).await?.spot_price();
+        // 金库为空时价格为 NaN 或无穷大,转换为 i64 后会变成 0 而被误判为正常
+        if !amm_price.is_finite() {
+            return Err(anyhow!(
+                "Pool {} has no valid spot price, a vault may be empty",
+                pool_id
+            ));
+        }
+        let divergence_bps = ((amm_price - oracle_price) / oracle_price * 10_000.0).round() as i64;
+        Ok(OracleComparison {
+            amm_price,
+            oracle_price,
+            divergence_bps,
+            is_anomalous: divergence_bps.unsigned_abs() > self.oracle_divergence_threshold_bps,
+        })
+    }
+
+    // 在给定的最大价格影响下,二分查找可以交易的最大输入数量,同时返回该数量实际的价格影响
+    // 搜索范围从 1 到输入代币金库的全部深度,当上下界相差不超过 0.1% 时停止
+    pub async fn get_max_tradeable_amount(
+        &self,
+        pool_id: Pubkey,
+        input_mint: Pubkey,
+        output_mint: Pubkey,
+        max_impact_bps: u64,
+    ) -> anyhow::Result<MaxTradeableAmount> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        let direction = state.direction(&input_mint, &output_mint)?;
+        let amount = state.max_amount_for_impact(direction, max_impact_bps as f64)?;
+        Ok(MaxTradeableAmount {
+            amount,
+            price_impact_bps: state.price_impact_bps(direction, amount)?,
+        })
+    }
+
+    // 获取池子的关键指标快照,便于序列化后发送到监控系统
+    // 池子账户和金库账户通过一次 get_multiple_accounts 请求获取,代币价格来自 API
+    pub async fn get_pool_metrics_snapshot(
+        &self,
+        pool_id: Pubkey,
+    ) -> anyhow::Result<PoolMetricsSnapshot> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        let (coin_price_usd, pc_price_usd) = self.fetch_pool_token_prices(&state).await?;
+        let coin_value_usd =
+            crate::utils::to_ui_amount(state.coin_vault_amount, state.amm.coin_decimals)
+                * coin_price_usd;
+        let pc_value_usd =
+            crate::utils::to_ui_amount(state.pc_vault_amount, state.amm.pc_decimals) * pc_price_usd;
+
+        Ok(PoolMetricsSnapshot {
+            pool_id,
+            spot_price: state.spot_price(),
+            tvl_usd: coin_value_usd + pc_value_usd,
+            fee_rate_bps: state.pool_fee_bps(),
+            vault_imbalance_ratio: if pc_value_usd > 0.0 {
+                coin_value_usd / pc_value_usd
+            } else {
+                0.0
+            },
+            pool_status: AmmPoolStatus::from(state.amm.status),
+            lp_supply: state.amm.lp_amount,
+            coin_vault_amount: state.coin_vault_amount,
+            pc_vault_amount: state.pc_vault_amount,
+        })
+    }
+
+    // 通过 API 获取池子中 coin 和 pc 的 USD 价格,价格未知时为 0
+    async fn fetch_pool_token_prices(
+        &self,
+        state: &RaydiumAmmPoolState,
+    ) -> anyhow::Result<(f64, f64)> {
+        let (coin_mint, pc_mint) = (state.amm_keys.amm_coin_mint, state.amm_keys.amm_pc_mint);
+        let prices = self
+            .api
+            .get_token_price(vec![coin_mint.to_string(), pc_mint.to_string()])
+            .await?;
+        Ok((
+            prices.get(&coin_mint).copied().unwrap_or_default(),
+            prices.get(&pc_mint).copied().unwrap_or_default(),
+        ))
+    }
+
+    // 为多个 mint 批量创建 owner 的关联代币账户(ATA),打包到同一笔交易中
+    // 先通过 get_multiple_accounts 检查哪些 ATA 还不存在,只为不存在的 ATA 生成创建指令
+    pub async fn create_associated_token_accounts_batch(
+        &self,
+        owner: Pubkey,
+        mints: Vec<Pubkey>,
+    ) -> anyhow::Result<VersionedTransaction> {
+        let atas = mints
+            .iter()
+            .map(|mint| spl_associated_token_account::get_associated_token_address(&owner, mint))
+            .collect::<Vec<_>>();
+        let accounts = crate::utils::get_multiple_account_data(&self.client, &atas).await?;
+        let instructions = mints
+            .iter()
+            .zip(accounts)
+            .filter(|(_, account)| account.is_none())
+            .map(|(mint, _)| {
+                spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                    &owner,
+                    &owner,
+                    mint,
+                    &spl_token::ID,
+                )
+            })
+            .collect::<Vec<_>>();
+        if instructions.is_empty() {
+            return Err(anyhow!(
+                "All associated token accounts of {} already exist",
+                owner
+            ));
+        }
+
+        Ok(VersionedTransaction {
+            signatures: vec![Signature::default()],
+            message: VersionedMessage::Legacy(Message::new(&instructions, Some(&owner))),
+        })
+    }
+
+    // 构建带推荐信息的交换交易
+    // 在交换指令之后追加一条 spl_memo 指令记录推荐人和推荐费率
+    // 如果输出代币不是 wSOL,再追加一笔将输出数量的 referral_fee_bps 转给推荐人 ATA 的转账
+    // (wSOL 的输出账户会在清理阶段被关闭,因此不支持转账推荐费)
+    // 推荐人的 ATA 需要事先存在
+    pub async fn swap_transaction_with_referral(
+        &self,
+        owner: Pubkey,
+        quote: RaydiumAmmQuote,
+        referrer: Pubkey,
+        referral_fee_bps: u16,
+        overrides: Option<&SwapConfigOverrides>,
+    ) -> anyhow::Result<VersionedTransaction> {
+        self.ensure_swap_allowed(&quote).await?;
+        let memo = format!(
+            "raydium-swap referral: {} {}bps",
+            referrer, referral_fee_bps
+        );
+        let mut post_swap_instructions = vec![spl_memo::build_memo(memo.as_bytes(), &[])];
+
+        if quote.output_mint != spl_token::native_mint::ID && referral_fee_bps > 0 {
+            // 使用扣除滑点后的最少输出数量,保证转账时余额充足
+            let output_amount = if quote.amount_specified_is_input {
+                quote.other_amount_threshold
+            } else {
+                quote.amount
+            };
+            let referral_amount =
+                (output_amount as u128 * referral_fee_bps as u128 / 10_000) as u64;
+            if referral_amount > 0 {
+                post_swap_instructions.push(spl_token::instruction::transfer(
+                    &spl_token::ID,
+                    &spl_associated_token_account::get_associated_token_address(
+                        &owner,
+                        &quote.output_mint,
+                    ),
+                    &spl_associated_token_account::get_associated_token_address(
+                        &referrer,
+                        &quote.output_mint,
+                    ),
+                    &owner,
+                    &[],
+                    referral_amount,
+                )?);
+            }
+        }
+
+        let builder = self
+            .make_swap(owner, quote, overrides, post_swap_instructions)
+            .await?;
+        builder.build_transaction(Some(&owner), None)
+    }
+
+    // 计算在 deposit_slot 存入的 lp_amount 个 lp 到目前为止的无常损失
+    // deposit_slot 时的价格取该 slot 之前最后一笔池子交易执行后的金库余额
+    pub async fn get_impermanent_loss_since_slot(
+        &self,
+        pool_id: Pubkey,
+        deposit_slot: u64,
+        lp_amount: u64,
+    ) -> anyhow::Result<ImpermanentLossReport> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        let deposit_tx = self
+            .get_pool_transaction_at_slot(pool_id, deposit_slot)
+            .await?
+            .ok_or_else(|| {
+                anyhow!(
+                    "no pool transaction found at or before slot {}",
+                    deposit_slot
+                )
+            })?;
+
+        let (coin_decimals, pc_decimals) = (state.amm.coin_decimals, state.amm.pc_decimals);
+        let price_of = |coin_vault: u64, pc_vault: u64| {
+            crate::utils::to_ui_amount(pc_vault, pc_decimals)
+                / crate::utils::to_ui_amount(coin_vault, coin_decimals)
+        };
+        let price_at_deposit = price_of(deposit_tx.coin_vault_after, deposit_tx.pc_vault_after);
+        let current_price = price_of(state.coin_vault_balance, state.pc_vault_balance);
+        if !price_at_deposit.is_finite() || !current_price.is_finite() {
+            return Err(anyhow!("pool {} has an empty vault", pool_id));
+        }
+        let impermanent_loss =
+            super::math::compute_impermanent_loss(current_price / price_at_deposit);
+
+        let (coin_price_usd, pc_price_usd) = self.fetch_pool_token_prices(&state).await?;
+        let lp_value_usd = if state.amm.lp_amount == 0 {
+            0.0
+        } else {
+            state.tvl_usd(coin_price_usd, pc_price_usd) * lp_amount as f64
+                / state.amm.lp_amount as f64
+        };
+        // lp_value = hodl_value * (1 + il)
+        let hodl_value_usd = lp_value_usd / (1.0 + impermanent_loss);
+
+        Ok(ImpermanentLossReport {
+            deposit_slot: deposit_tx.slot,
+            price_at_deposit,
+            current_price,
+            impermanent_loss_pct: impermanent_loss * 100.0,
+            lp_value_usd,
+            hodl_value_usd,
+            impermanent_loss_usd: lp_value_usd - hodl_value_usd,
+        })
+    }
+
+    // 根据池子最近的波动率推荐滑点
+    // 在 VOLATILITY_SAMPLE_WINDOW 内每隔 VOLATILITY_SAMPLE_INTERVAL 采样一次价格,用价格的变异系数查表
+    pub async fn auto_select_slippage_bps(&self, pool_id: Pubkey) -> anyhow::Result<u16> {
+        let samples = VOLATILITY_SAMPLE_WINDOW.as_secs() / VOLATILITY_SAMPLE_INTERVAL.as_secs();
+        let mut prices = Vec::with_capacity(samples as usize);
+        let mut interval = tokio::time::interval(VOLATILITY_SAMPLE_INTERVAL);
+        for _ in 0..samples {
+            interval.tick().await;
+            let price = self.fetch_pool_state(pool_id).await?.spot_price();
+            if price.is_finite() {
+                prices.push(price);
+            }
+        }
+        if prices.len() < 2 {
+            return Err(anyhow!("not enough price samples for pool {}", pool_id));
+        }
+
+        let coefficient_of_variation = super::math::coefficient_of_variation(&prices);
+        let slippage_bps = self
+            .volatility_slippage_map
+            .slippage_bps(coefficient_of_variation);
+        log::debug!(
+            "pool {} price cv over {:?}: {:.6}, recommended slippage: {} bps",
+            pool_id,
+            VOLATILITY_SAMPLE_WINDOW,
+            coefficient_of_variation,
+            slippage_bps
+        );
+        Ok(slippage_bps)
+    }
+
+    // 检查池子最近 FLASHLOAN_LOOKBACK_TRANSACTIONS 笔交易中是否有单笔交易使价格变动超过 FLASHLOAN_PRICE_MOVE_BPS
+    // 有则说明池子可能刚被用于闪电贷套利,价格可能暂时失真
+    pub async fn get_flashloan_risk(&self, pool_id: Pubkey) -> anyhow::Result<FlashloanRisk> {
+        let (amm_keys, _) = self.load_pool_keys(pool_id).await?;
+        let signatures = self
+            .client
+            .get_signatures_for_address_with_config(
+                &pool_id,
+                GetConfirmedSignaturesForAddress2Config {
+                    before: None,
+                    until: None,
+                    limit: Some(FLASHLOAN_LOOKBACK_TRANSACTIONS),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                },
+            )
+            .await?
+            .into_iter()
+            .filter(|status| status.err.is_none())
+            .map(|status| Signature::from_str(&status.signature))
+            .collect::<Result<Vec<_>, _>>()?;
+        let transactions = self.fetch_pool_transactions(signatures, &amm_keys).await?;
+
+        for tx in &transactions {
+            let coin_before = tx.coin_vault_after as i64 - tx.coin_delta;
+            let pc_before = tx.pc_vault_after as i64 - tx.pc_delta;
+            if coin_before <= 0 || pc_before <= 0 || tx.coin_vault_after == 0 {
+                continue;
+            }
+            let price_before = pc_before as f64 / coin_before as f64;
+            let price_after = tx.pc_vault_after as f64 / tx.coin_vault_after as f64;
+            let move_bps = (price_after / price_before - 1.0).abs() * 10_000.0;
+            if move_bps > FLASHLOAN_PRICE_MOVE_BPS {
+                log::warn!(
+                    "transaction {} moved the price of pool {} by {:.0} bps",
+                    tx.signature,
+                    pool_id,
+                    move_bps
+                );
+                return Ok(FlashloanRisk::High);
+            }
+        }
+        Ok(FlashloanRisk::Low)
+    }
+
+    // 最近 PERFORMANCE_SAMPLES 个性能样本中每个 slot 的平均耗时,没有样本时使用 DEFAULT_SLOT_TIME
+    async fn get_mean_slot_time(&self) -> anyhow::Result<Duration> {
+        let samples = self
+            .client
+            .get_recent_performance_samples(Some(PERFORMANCE_SAMPLES))
+            .await?;
+        let (total_slots, total_secs) = samples.iter().fold((0u64, 0u64), |(slots, secs), s| {
+            (slots + s.num_slots, secs + s.sample_period_secs as u64)
+        });
+        Ok(if total_slots == 0 {
+            DEFAULT_SLOT_TIME
+        } else {
+            Duration::from_secs_f64(total_secs as f64 / total_slots as f64)
+        })
+    }
+
+    // 根据最近的 slot 耗时和优先费估算一笔交易上链所需的时间
+    // priority_fee_lamports_per_cu 与 getRecentPrioritizationFees 的单位相同,即每个 CU 的 micro-lamports
+    // 最近的每个 slot 中,最低优先费不高于给定值的 slot 视为能打包该交易,按几何分布估算需要等待的 slot 数
+    pub async fn get_estimated_block_inclusion_time(
+        &self,
+        priority_fee_lamports_per_cu: u64,
+    ) -> anyhow::Result<Duration> {
+        let slot_time = self.get_mean_slot_time().await?;
+
+        let fees = self.client.get_recent_prioritization_fees(&[]).await?;
+        if fees.is_empty() {
+            return Ok(slot_time);
+        }
+        let included = fees
+            .iter()
+            .filter(|fee| fee.prioritization_fee <= priority_fee_lamports_per_cu)
+            .count();
+        // 没有任何 slot 能打包时,以整个观察窗口作为上限
+        let expected_slots = if included == 0 {
+            fees.len() as f64
+        } else {
+            fees.len() as f64 / included as f64
+        };
+        log::debug!(
+            "slot time: {:?}, {}/{} recent slots accept a cu price of {}",
+            slot_time,
+            included,
+            fees.len(),
+            priority_fee_lamports_per_cu
+        );
+        Ok(slot_time.mul_f64(expected_slots))
+    }
+
+    // 读取池子的运行参数和权限
+    // 注意 AmmInfo 中并没有单独的 config_change_permission / disable_targets 字段,权限完全由 status 决定
+    pub async fn get_pool_config_params(
+        &self,
+        pool_id: Pubkey,
+    ) -> anyhow::Result<PoolConfigParams> {
+        let account = self.client.get_account(&pool_id).await?;
+        let amm = decode_amm_info(&account.data)?;
+        let status = raydium_amm::state::AmmStatus::from_u64(amm.status);
+        Ok(PoolConfigParams {
+            pool_id,
+            status: AmmPoolStatus::from(amm.status),
+            swap_permission: status.swap_permission(),
+            deposit_permission: status.deposit_permission(),
+            withdraw_permission: status.withdraw_permission(),
+            orderbook_permission: status.orderbook_permission(),
+            amm_state: amm.state,
+            reset_flag: amm.reset_flag,
+            order_num: amm.order_num,
+            depth: amm.depth,
+            min_size: amm.min_size,
+            vol_max_cut_ratio: amm.vol_max_cut_ratio,
+            amount_wave: amm.amount_wave,
+            coin_lot_size: amm.coin_lot_size,
+            pc_lot_size: amm.pc_lot_size,
+            min_price_multiplier: amm.min_price_multiplier,
+            max_price_multiplier: amm.max_price_multiplier,
+            sys_decimal_value: amm.sys_decimal_value,
+            pool_open_time: amm.state_data.pool_open_time,
+            orderbook_to_init_time: amm.state_data.orderbook_to_init_time,
+            amm_owner: amm.amm_owner,
+        })
+    }
+
+    // 估算执行 quote 时 fee payer 需要支付的 lamports
+    // 包括交易费(含优先费)、jito 小费,以及需要新建的输出代币账户的租金
+    // 输入为 SOL 时创建的 wSOL 账户会在交易末尾关闭,租金会退回,因此不计入
+    pub async fn get_fee_payer_cost(
+        &self,
+        owner: Pubkey,
+        quote: &RaydiumAmmQuote,
+    ) -> anyhow::Result<u64> {
+        // 只读估算,与 get_gas_optimized_instructions 一样直接使用 make_swap,不做 ensure_swap_allowed 检查
+        let transaction = self
+            .make_swap(owner, quote.clone(), None, vec![])
+            .await?
+            .build_transaction(Some(&owner), None)?;
+        let mut message = transaction.message;
+        message.set_recent_blockhash(self.client.get_latest_blockhash().await?);
+        let transaction_fee = self.client.get_fee_for_message(&message).await?;
+
+        let jito_tip = match self.config.priority_fee {
+            Some(PriorityFeeConfig::JitoTip(tip)) => tip,
+            _ => 0,
+        };
+
+        let output_ata =
+            spl_associated_token_account::get_associated_token_address(&owner, &quote.output_mint);
+        let unwraps_output = self.config.wrap_and_unwrap_sol.unwrap_or(true)
+            && quote.output_mint == spl_token::native_mint::ID;
+        let output_ata_rent = if !unwraps_output
+            && self
+                .client
+                .get_account_with_commitment(&output_ata, CommitmentConfig::confirmed())
+                .await?
+                .value
+                .is_none()
+        {
+            self.client
+                .get_minimum_balance_for_rent_exemption(spl_token::state::Account::LEN)
+                .await?
+        } else {
+            0
+        };
+
+        Ok(transaction_fee + jito_tip + output_ata_rent)
+    }
+
+    // 以美元计算执行 quote 的成本,保留 4 位小数
+    // 小额交易时该成本可能超过交易本身的收益,调用方可据此判断交易是否划算
+    pub async fn estimate_gas_cost_usd(
+        &self,
+        owner: Pubkey,
+        quote: &RaydiumAmmQuote,
+        sol_price_usd: f64,
+    ) -> anyhow::Result<f64> {
+        let lamports = self.get_fee_payer_cost(owner, quote).await?;
+        let cost_usd = crate::utils::to_ui_amount(lamports, 9) * sol_price_usd;
+        Ok((cost_usd * 10_000.0).round() / 10_000.0)
+    }
+
+    // 通过 websocket 订阅池子的两个金库账户,每次金库余额变化时重新计算买卖价
+    // bid/ask 任意一个相对上一次推送的变化超过 PRICE_FEED_MIN_CHANGE_BPS 时推送新的 PriceTick
+    // 所有接收端都被丢弃后后台任务自动退出
+    // 订阅返回的 stream 借用 PubsubClient,因此在后台任务中订阅,任务通过 oneshot 回报订阅结果,订阅失败时返回错误
+    pub async fn subscribe_to_price_feed(
+        &self,
+        pool_id: Pubkey,
+    ) -> anyhow::Result<tokio::sync::broadcast::Receiver<PriceTick>> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        let websocket_url = self.websocket_url.clone().unwrap_or_else(|| {
+            self.client
+                .url()
+                .replacen("https://", "wss://", 1)
+                .replacen("http://", "ws://", 1)
+        });
+        let pubsub = PubsubClient::new(&websocket_url).await?;
+
+        let (coin_vault, pc_vault) = (state.amm_keys.amm_coin_vault, state.amm_keys.amm_pc_vault);
+        // 池子的数量 = 金库余额 + openbook 挂单中的数量 - 尚未提取的 pnl,两者推送期间视为不变,
+        // 挂单较多时池子的数量可能大于金库余额,因此按有符号的差值记录
+        let coin_offset = state.coin_vault_amount as i128 - state.coin_vault_balance as i128;
+        let pc_offset = state.pc_vault_amount as i128 - state.pc_vault_balance as i128;
+        let pool_amount = |balance: u64, offset: i128| (balance as i128 + offset).max(0) as u64;
+        let fee = state.pool_fee_bps() as f64 / 10_000.0;
+        let (coin_decimals, pc_decimals) = (state.amm.coin_decimals, state.amm.pc_decimals);
+        let (sender, receiver) = tokio::sync::broadcast::channel(PRICE_FEED_CAPACITY);
+        let (subscribed_sender, subscribed) = tokio::sync::oneshot::channel();
+
+        tokio::spawn(async move {
+            let config = RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                commitment: Some(CommitmentConfig::confirmed()),
+                ..Default::default()
+            };
+            let subscriptions = futures_util::try_join!(
+                pubsub.account_subscribe(&coin_vault, Some(config.clone())),
+                pubsub.account_subscribe(&pc_vault, Some(config)),
+            );
+            let ((coin_updates, _coin_unsubscribe), (pc_updates, _pc_unsubscribe)) =
+                match subscriptions {
+                    Ok(subscriptions) => {
+                        let _ = subscribed_sender.send(Ok(()));
+                        subscriptions
+                    }
+                    Err(e) => {
+                        let _ = subscribed_sender.send(Err(e));
+                        return;
+                    }
+                };
+            let mut updates = futures_util::stream::select(
+                coin_updates.map(|update| (true, update)),
+                pc_updates.map(|update| (false, update)),
+            );
+
+            let (mut coin_balance, mut pc_balance) =
+                (state.coin_vault_balance, state.pc_vault_balance);
+            let mut last_tick: Option<PriceTick> = None;
+            while let Some((is_coin, update)) = updates.next().await {
+                let Some(account) = update.value.decode::<solana_sdk::account::Account>() else {
+                    continue;
+                };
+                let Ok(balance) = crate::utils::token_account_amount(Some(&account)) else {
+                    continue;
+                };
+                if is_coin {
+                    coin_balance = balance;
+                } else {
+                    pc_balance = balance;
+                }
+
+                let mid =
+                    crate::utils::to_ui_amount(pool_amount(pc_balance, pc_offset), pc_decimals)
+                        / crate::utils::to_ui_amount(
+                            pool_amount(coin_balance, coin_offset),
+                            coin_decimals,
+                        );
+                if !mid.is_finite() {
+                    continue;
+                }
+                let tick = PriceTick {
+                    bid: mid * (1.0 - fee),
+                    ask: mid / (1.0 - fee),
+                    mid,
+                    slot: update.context.slot,
+                };
+                let changed = last_tick.map_or(true, |last| {
+                    (tick.bid / last.bid - 1.0).abs() * 10_000.0 > PRICE_FEED_MIN_CHANGE_BPS
+                        || (tick.ask / last.ask - 1.0).abs() * 10_000.0 > PRICE_FEED_MIN_CHANGE_BPS
+                });
+                if changed {
+                    if sender.send(tick).is_err() {
+                        break;
+                    }
+                    last_tick = Some(tick);
+                }
+            }
+        });
+
+        subscribed
+            .await
+            .context("Price feed task exited before subscribing")??;
+        Ok(receiver)
+    }
+
+    // 在 [from_slot, to_slot] 内均匀取 max_samples 个检查点,返回每个检查点时的金库余额
+    // RPC 节点只提供账户的最新状态(minContextSlot 只能限制最早的上下文 slot,无法读取历史状态),
+    // 因此检查点的余额取该 slot 之前最后一笔池子交易执行后的金库余额
+    pub async fn get_vault_history(
+        &self,
+        pool_id: Pubkey,
+        from_slot: u64,
+        to_slot: u64,
+        max_samples: usize,
+    ) -> anyhow::Result<Vec<VaultSample>> {
+        if to_slot < from_slot || max_samples == 0 {
+            return Ok(vec![]);
+        }
+        let state = self.fetch_pool_state(pool_id).await?;
+        let (coin_decimals, pc_decimals) = (state.amm.coin_decimals, state.amm.pc_decimals);
+        let baseline = self
+            .get_pool_transaction_at_slot(pool_id, from_slot)
+            .await?;
+        let transactions = self
+            .get_pool_transactions(pool_id, from_slot, Some(to_slot))
+            .await?;
+
+        let span = to_slot - from_slot;
+        let samples = max_samples.min(span as usize + 1);
+        let mut history = Vec::with_capacity(samples);
+        for i in 0..samples {
+            let slot = if samples == 1 {
+                to_slot
+            } else {
+                from_slot + span * i as u64 / (samples - 1) as u64
+            };
+            let Some(tx) = transactions
+                .iter()
+                .rev()
+                .find(|tx| tx.slot <= slot)
+                .or(baseline.as_ref())
+            else {
+                continue;
+            };
+            history.push(VaultSample {
+                slot,
+                coin_amount: tx.coin_vault_after,
+                pc_amount: tx.pc_vault_after,
+                implied_price: crate::utils::to_ui_amount(tx.pc_vault_after, pc_decimals)
+                    / crate::utils::to_ui_amount(tx.coin_vault_after, coin_decimals),
+            });
+        }
+        Ok(history)
+    }
+
+    // 为交易对推荐一个池子
+    // 每个池子的得分为 liquidity * 0.6 + (1 / fee_bps) * 0.3 + (1 / volatility) * 0.1,
+    // 三项分别除以所有候选池子中的最大值做归一化,否则 tvl 的数量级会完全盖过另外两项
+    // volatility 取 api 返回的 24 小时价格区间 (price_max - price_min) / price
+    // 输入侧储备不足以吸收 amount 的池子会被排除,amount 为 0 时不过滤
+    pub async fn get_recommended_pool(
+        &self,
+        input_mint: Pubkey,
+        output_mint: Pubkey,
+        amount: u64,
+    ) -> anyhow::Result<RecommendedPool> {
+        let response: ApiV3PoolsPage<ApiV3StandardPool> = self
+            .api
+            .fetch_pool_by_mints(
+                &input_mint,
+                Some(&output_mint),
+                &PoolFetchParams {
+                    pool_type: PoolType::Standard,
+                    pool_sort: PoolSort::Liquidity,
+                    sort_type: PoolSortOrder::Descending,
+                    page_size: 100,
+                    page: 1,
+                },
+            )
+            .await?;
+
+        let candidates = response
+            .pools
+            .into_iter()
+            .filter(|pool| pool.program_id == RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID)
+            .filter_map(|pool| {
+                let (input, input_reserve) = if pool.mint_a.address == input_mint
+                    && pool.mint_b.address == output_mint
+                {
+                    (&pool.mint_a, pool.mint_amount_a)
+                } else if pool.mint_b.address == input_mint && pool.mint_a.address == output_mint {
+                    (&pool.mint_b, pool.mint_amount_b)
+                } else {
+                    return None;
+                };
+                if crate::utils::to_ui_amount(amount, input.decimals as u64) >= input_reserve {
+                    return None;
+                }
+                let fee_bps = (pool.fee_rate * 10_000.0).max(f64::EPSILON);
+                let volatility = if pool.price > 0.0 {
+                    ((pool.day.price_max - pool.day.price_min) / pool.price).max(f64::EPSILON)
+                } else {
+                    f64::MAX
+                };
+                Some((pool.id, pool.tvl, fee_bps, volatility))
+            })
+            .collect::<Vec<_>>();
+
+        let max_of = |values: &mut dyn Iterator<Item = f64>| values.fold(f64::EPSILON, f64::max);
+        let max_tvl = max_of(&mut candidates.iter().map(|(_, tvl, _, _)| *tvl));
+        let max_inverse_fee = max_of(&mut candidates.iter().map(|(_, _, fee, _)| 1.0 / fee));
+        let max_inverse_volatility = max_of(
+            &mut candidates
+                .iter()
+                .map(|(_, _, _, volatility)| 1.0 / volatility),
+        );
+
+        let (liquidity_weight, fee_weight, volatility_weight) = POOL_SCORE_WEIGHTS;
+        candidates
+            .into_iter()
+            .map(|(pool_id, tvl, fee_bps, volatility)| {
+                let liquidity_score = tvl / max_tvl * liquidity_weight;
+                let fee_score = (1.0 / fee_bps) / max_inverse_fee * fee_weight;
+                let volatility_score =
+                    (1.0 / volatility) / max_inverse_volatility * volatility_weight;
+                RecommendedPool {
+                    pool_id,
+                    score: liquidity_score + fee_score + volatility_score,
+                    liquidity_score,
+                    fee_score,
+                    volatility_score,
+                    tvl,
+                    fee_bps,
+                    volatility,
+                }
+            })
+            .max_by(|a, b| a.score.total_cmp(&b.score))
+            .ok_or_else(|| anyhow!("No pool found for {} -> {}", input_mint, output_mint))
+    }
+
+    // 计算 quote 对应的交换交易中不重复的账户数量(含程序账户和 fee payer),不发送任何 RPC 请求
+    // 与 make_swap 构建相同的指令,计算单元限制指令只计入其 ComputeBudget 程序账户
+    pub fn get_transaction_accounts_size(
+        &self,
+        quote: &RaydiumAmmQuote,
+        owner: Pubkey,
+    ) -> anyhow::Result<usize> {
+        let wrap_and_unwrap_sol = self.config.wrap_and_unwrap_sol.unwrap_or(true);
+        let mut builder = SwapInstructionsBuilder::default();
+        builder.handle_token_wrapping_and_accounts_creation(
+            owner,
+            wrap_and_unwrap_sol,
+            if quote.amount_specified_is_input {
+                quote.amount
+            } else {
+                quote.other_amount
+            },
+            quote.input_mint,
+            quote.output_mint,
+            spl_token::ID,
+            spl_token::ID,
+            None,
+        )?;
+        builder.swap_instruction = Some(swap_instruction(
+            &RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID,
+            &quote.amm_keys,
+            &quote.market_keys,
+            &owner,
+            &spl_associated_token_account::get_associated_token_address(&owner, &quote.input_mint),
+            &spl_associated_token_account::get_associated_token_address(&owner, &quote.output_mint),
+            quote.amount,
+            quote.other_amount_threshold,
+            quote.amount_specified_is_input,
+        )?);
+        if self.config.cu_limits.is_some() {
+            builder.compute_budget_instructions.push(
+                solana_sdk::compute_budget::ComputeBudgetInstruction::set_compute_unit_limit(0),
+            );
+        }
+        builder.handle_priority_fee_params(self.config.priority_fee, None, owner)?;
+
+        let instructions = builder.build_instructions()?;
+        Ok(Message::new(&instructions, Some(&owner)).account_keys.len())
+    }
+
+    // 池子是否在 raydium api 的官方池子列表中
+    async fn is_listed_by_api(&self, pool_id: Pubkey) -> anyhow::Result<bool> {
+        // api 对不认识的池子 id 返回 null
+        Ok(self
+            .api
+            .fetch_pools_by_ids::<Option<ApiV3StandardPool>>(vec![pool_id.to_string()])
+            .await?
+            .into_iter()
+            .flatten()
+            .any(|pool| {
+                pool.id == pool_id && pool.program_id == RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID
+            }))
+    }
+
+    // 检查池子是否满足执行器配置的 PoolVerificationPolicy
+    // 非官方或仿冒的池子可能是骗局,构建交换交易前会先做该检查(见 ensure_swap_allowed)
+    // API 请求失败时,StrictApiOnly 返回错误,AllowKnownPrograms 退回到只检查池子账户的 owner
+    pub async fn is_verified_pool(&self, pool_id: Pubkey) -> anyhow::Result<bool> {
+        if self.pool_verification_policy == PoolVerificationPolicy::AllowAll {
+            return Ok(true);
+        }
+        let listed = match self.is_listed_by_api(pool_id).await {
+            Ok(listed) => listed,
+            Err(e)
+                if self.pool_verification_policy == PoolVerificationPolicy::AllowKnownPrograms =>
+            {
+                log::warn!(
+                    "Failed to check pool {} against the api, checking the program owner only: {}",
+                    pool_id,
+                    e
+                );
+                false
+            }
+            Err(e) => return Err(e),
+        };
+        if listed || self.pool_verification_policy == PoolVerificationPolicy::StrictApiOnly {
+            return Ok(listed);
+        }
+        let account = self
+            .client
+            .get_account_with_commitment(&pool_id, CommitmentConfig::confirmed())
+            .await?
+            .value;
+        Ok(self.satisfies_verification_policy(listed, account.map(|account| account.owner)))
+    }
+
+    // 按 PoolVerificationPolicy 判断池子是否可信,listed 为池子是否在 API 的官方列表中,pool_owner 为池子账户的 owner
+    // 供已经取得这两项数据的调用方(如 get_pool_health_dashboard)与 is_verified_pool 使用同一规则
+    fn satisfies_verification_policy(&self, listed: bool, pool_owner: Option<Pubkey>) -> bool {
+        match self.pool_verification_policy {
+            PoolVerificationPolicy::AllowAll => true,
+            PoolVerificationPolicy::StrictApiOnly => listed,
+            PoolVerificationPolicy::AllowKnownPrograms => {
+                listed || pool_owner == Some(RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID)
+            }
+        }
+    }
+
+    // 构建用于提交的交换交易前的检查: 配置了交易时间窗口时当前时间必须在某个窗口内,
+    // 报价使用的池子必须满足 PoolVerificationPolicy
+    // 只读的辅助方法(估算交易大小、模拟指令顺序等)不做这些检查
+    async fn ensure_swap_allowed(&self, quote: &RaydiumAmmQuote) -> anyhow::Result<()> {
+        if !self.trading_windows.is_empty() && !self.is_within_trading_window(&self.trading_windows)
+        {
+            return Err(anyhow!(
+                "Current time is outside of the configured trading windows"
+            ));
+        }
+        if !self.is_verified_pool(quote.market).await? {
+            return Err(anyhow!(
+                "Pool {} is not allowed by the verification policy {:?}",
+                quote.market,
+                self.pool_verification_policy
+            ));
+        }
+        Ok(())
+    }
+
+    // 计算 quote 中以输出代币计的手续费,不需要额外的 RPC 请求
+    // 池子按输入数量收取 swap_fee_numerator / swap_fee_denominator 的手续费(向上取整),
+    // 再按扣费后输入和输出的比例把手续费换算为输出代币,这里用成交均价换算,忽略曲线带来的微小差异
+    pub fn compute_swap_fee(&self, quote: &RaydiumAmmQuote) -> u64 {
+        let (amount_in, amount_out) = if quote.amount_specified_is_input {
+            (quote.amount, quote.other_amount)
+        } else {
+            (quote.other_amount, quote.amount)
+        };
+        if quote.swap_fee_denominator == 0 {
+            return 0;
+        }
+        let fee_in = (amount_in as u128 * quote.swap_fee_numerator as u128)
+            .div_ceil(quote.swap_fee_denominator as u128);
+        let amount_in_after_fee = (amount_in as u128).saturating_sub(fee_in);
+        if amount_in_after_fee == 0 {
+            return 0;
+        }
+        u64::try_from(amount_out as u128 * fee_in / amount_in_after_fee).unwrap_or(u64::MAX)
+    }
+
+    // 列出 owner 持有的所有 raydium amm v4 lp 仓位
+    // lp mint 的 mint authority 都是 amm 程序的 authority,据此从 owner 的代币账户中筛选出 lp 代币,
+    // 再通过一次 API 请求按 lp mint 查找对应的池子(见 lp_positions_from_balances),所有池子的状态通过 fetch_pool_states 批量获取
+    pub async fn get_all_user_lp_positions(
+        &self,
+        owner: Pubkey,
+    ) -> anyhow::Result<Vec<UserLpPosition>> {
+        let balances = self.get_owner_token_balances(owner).await?;
+        self.lp_positions_from_balances(&balances).await
+    }
+
+    // 通过 getTokenAccountsByOwner 获取 owner 持有的所有 spl-token 余额,按 mint 汇总为 (数量, 精度)
+    async fn get_owner_token_balances(
+        &self,
+        owner: Pubkey,
+    ) -> anyhow::Result<HashMap<Pubkey, (u64, u8)>> {
+        let token_accounts = self
+            .client
+            .get_token_accounts_by_owner(&owner, TokenAccountsFilter::ProgramId(spl_token::ID))
+            .await?;
+        let mut balances: HashMap<Pubkey, (u64, u8)> = HashMap::new();
+        for keyed_account in token_accounts {
+            let UiAccountData::Json(parsed) = keyed_account.account.data else {
+                continue;
+            };
+            let info = &parsed.parsed["info"];
+            let (Some(mint), Some(amount), Some(decimals)) = (
+                info["mint"]
+                    .as_str()
+                    .and_then(|mint| Pubkey::from_str(mint).ok()),
+                info["tokenAmount"]["amount"]
+                    .as_str()
+                    .and_then(|amount| amount.parse::<u64>().ok()),
+                info["tokenAmount"]["decimals"].as_u64(),
+            ) else {
+                continue;
+            };
+            if amount > 0 {
+                let balance = balances.entry(mint).or_insert((0, decimals as u8));
+                balance.0 += amount;
+            }
+        }
+        Ok(balances)
+    }
+
+    // 从 owner 的代币余额中筛选出 lp 代币并计算对应的仓位
+    // mint authority 为 amm v4 authority 的代币是 lp 代币,通过一次 API 请求(/pools/info/lps)查找对应的池子,
+    // API 没有收录的池子会被跳过; API 不可用时才回退到逐个 lp mint 的 getProgramAccounts 扫描(get_pool_by_lp_mint)
+    async fn lp_positions_from_balances(
+        &self,
+        balances: &HashMap<Pubkey, (u64, u8)>,
+    ) -> anyhow::Result<Vec<UserLpPosition>> {
+        // 所有 amm v4 池子共用同一个 authority
+        let amm_authority = Self::get_pool_authority_pda().0;
+        let mints = balances.keys().copied().collect::<Vec<_>>();
+        let mint_accounts = crate::utils::get_multiple_account_data(&self.client, &mints).await?;
+        let lp_mints = mints
+            .into_iter()
+            .zip(mint_accounts)
+            .filter(|(_, account)| {
+                account
+                    .as_ref()
+                    .and_then(|account| spl_token::state::Mint::unpack(&account.data).ok())
+                    .is_some_and(|mint| mint.mint_authority == COption::Some(amm_authority))
+            })
+            .map(|(mint, _)| mint)
+            .collect::<Vec<_>>();
+
+        let pools = match self.get_pools_by_lp_mints(&lp_mints).await {
+            Ok(pools) => pools,
+            Err(e) => {
+                log::warn!(
+                    "Failed to look up {} lp mints from api, scanning the amm program: {}",
+                    lp_mints.len(),
+                    e
+                );
+                self.scan_pools_by_lp_mints(lp_mints).await?
+            }
+        };
+        let pool_ids = pools
+            .iter()
+            .map(|(_, pool_id)| *pool_id)
+            .collect::<Vec<_>>();
+        let states = self.fetch_pool_states(&pool_ids).await?;
+
+        Ok(pools
+            .into_iter()
+            .zip(states)
+            .map(|((lp_mint, pool_id), state)| {
+                let lp_balance = balances[&lp_mint].0;
+                let share_of = |vault_amount: u64| {
+                    (vault_amount as u128 * lp_balance as u128)
+                        .checked_div(state.amm.lp_amount as u128)
+                        .unwrap_or_default() as u64
+                };
+                UserLpPosition {
+                    pool_id,
+                    lp_mint,
+                    coin_mint: state.amm_keys.amm_coin_mint,
+                    pc_mint: state.amm_keys.amm_pc_mint,
+                    coin_decimals: state.amm.coin_decimals,
+                    pc_decimals: state.amm.pc_decimals,
+                    lp_balance,
+                    coin_share: share_of(state.coin_vault_amount),
+                    pc_share: share_of(state.pc_vault_amount),
+                }
+            })
+            .collect())
+    }
+
+    // 通过一次 API 请求按 lp mint 批量查找 amm v4 池子,返回 (lp mint, 池子地址),API 没有收录的 lp mint 不在结果中
+    async fn get_pools_by_lp_mints(
+        &self,
+        lp_mints: &[Pubkey],
+    ) -> anyhow::Result<Vec<(Pubkey, Pubkey)>> {
+        if lp_mints.is_empty() {
+            return Ok(vec![]);
+        }
+        // api 对不认识的 lp mint 返回 null
+        let pools = self
+            .api
+            .fetch_pools_by_lps::<Option<ApiV3StandardPool>>(
+                lp_mints.iter().map(Pubkey::to_string).collect(),
+            )
+            .await?;
+        Ok(pools
+            .into_iter()
+            .flatten()
+            .filter(|pool| {
+                pool.program_id == RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID
+                    && lp_mints.contains(&pool.pool.lp_mint.address)
+            })
+            .map(|pool| (pool.pool.lp_mint.address, pool.id))
+            .collect())
+    }
+
+    // 逐个 lp mint 通过 getProgramAccounts 查找池子,只在 API 不可用时使用,
+    // 每个 lp mint 都要扫描整个 amm v4 程序,代价很高,且很多公共 RPC 节点禁用了该接口
+    async fn scan_pools_by_lp_mints(
+        &self,
+        lp_mints: Vec<Pubkey>,
+    ) -> anyhow::Result<Vec<(Pubkey, Pubkey)>> {
+        Ok(futures_util::stream::iter(lp_mints)
+            .map(|lp_mint| async move {
+                Ok::<_, anyhow::Error>(
+                    self.get_pool_by_lp_mint(lp_mint)
+                        .await?
+                        .map(|pool_id| (lp_mint, pool_id)),
+                )
+            })
+            .buffered(8)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<anyhow::Result<Vec<_>>>()?
+            .into_iter()
+            .flatten()
+            .collect())
+    }
+
+    // 按 lp mint 查找 amm v4 池子地址
+    async fn get_pool_by_lp_mint(&self, lp_mint: Pubkey) -> anyhow::Result<Option<Pubkey>> {
+        let filters = vec![
+            RpcFilterType::DataSize(std::mem::size_of::<super::amm_info::AmmInfo>() as u64),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                std::mem::offset_of!(super::amm_info::AmmInfo, lp_mint),
+                lp_mint.as_ref(),
+            )),
+        ];
+        let accounts = self
+            .client
+            .get_program_accounts_with_config(
+                &RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID,
+                RpcProgramAccountsConfig {
+                    filters: Some(filters),
+                    account_config: RpcAccountInfoConfig {
+                        encoding: Some(UiAccountEncoding::Base64),
+                        data_slice: Some(UiDataSliceConfig {
+                            offset: 0,
+                            length: 0,
+                        }),
+                        commitment: Some(CommitmentConfig::confirmed()),
+                        min_context_slot: None,
+                    },
+                    ..Default::default()
+                },
+            )
+            .await?;
+        Ok(accounts.first().map(|(pubkey, _)| *pubkey))
+    }
+
+    // 获取 24 小时交易量最高的 limit 个标准池,用于发现套利机会
+    // fetch_pool_by_mints 必须指定 mint1,不带 mint 过滤时需要使用 /pools/info/list 接口
+    pub async fn get_top_pools_by_volume(
+        &self,
+        limit: usize,
+    ) -> anyhow::Result<Vec<ApiV3StandardPool>> {
+        let response: ApiV3PoolsPage<ApiV3StandardPool> = self
+            .api
+            .get_pool_list(&PoolFetchParams {
+                pool_type: PoolType::Standard,
+                pool_sort: PoolSort::Volume24h,
+                sort_type: PoolSortOrder::Descending,
+                page_size: u16::try_from(limit).unwrap_or(u16::MAX),
+                page: 1,
+            })
+            .await?;
+        let mut pools = response.pools;
+        pools.truncate(limit);
+        Ok(pools)
+    }
+
+    // 获取 24 小时手续费收入最高的 limit 个标准池,用于寻找收益最高的做市池子
+    pub async fn get_top_pools_by_fee_revenue(
+        &self,
+        limit: usize,
+    ) -> anyhow::Result<Vec<ApiV3StandardPoolExtended>> {
+        let response: ApiV3PoolsPage<ApiV3StandardPool> = self
+            .api
+            .get_pool_list(&PoolFetchParams {
+                pool_type: PoolType::Standard,
+                pool_sort: PoolSort::Fee24h,
+                sort_type: PoolSortOrder::Descending,
+                page_size: u16::try_from(limit).unwrap_or(u16::MAX),
+                page: 1,
+            })
+            .await?;
+        Ok(response
+            .pools
+            .into_iter()
+            .take(limit)
+            .map(ApiV3StandardPoolExtended::from)
+            .collect())
+    }
+
+    // 将 amm 池的现货价格与中心化交易所(Binance 兼容的 REST 接口)的价格比较,用于统计套利
+    // cex_symbol 需要与池子的 coin/pc 对应,例如 SOL/USDC 池子对应 SOLUSDC
+    #[cfg(feature = "cex-comparison")]
+    pub async fn get_price_deviation_from_cex(
+        &self,
+        pool_id: Pubkey,
+        cex_symbol: &str,
+        cex_api_url: &str,
+    ) -> anyhow::Result<PriceDeviation> {
+        #[derive(serde::Deserialize)]
+        struct TickerPrice {
+            price: String,
+        }
+
+        let state = self.fetch_pool_state(pool_id).await?;
+        let amm_price = state.spot_price();
+        let url = format!(
+            "{}/api/v3/ticker/price?symbol={}",
+            cex_api_url.trim_end_matches('/'),
+            cex_symbol
+        );
+        let ticker: TickerPrice = self
+            .http
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let cex_price: f64 = ticker
+            .price
+            .parse()
+            .with_context(|| format!("Invalid price for {}: {}", cex_symbol, ticker.price))?;
+        if cex_price <= 0.0 || !amm_price.is_finite() {
+            return Err(anyhow!(
+                "Cannot compare amm price {} with cex price {}",
+                amm_price,
+                cex_price
+            ));
+        }
+
+        Ok(PriceDeviation {
+            amm_price,
+            cex_price,
+            deviation_bps: ((amm_price / cex_price - 1.0) * 10_000.0).round() as i64,
+        })
+    }
+
+    // 不构建交易,预估 make_swap 生成的指令数量
+    // wrap_sol 时:输入为 SOL 需要 transfer、sync_native 和最后的 close_account,输出为 SOL 只需要 close_account
+    pub fn estimate_instruction_count(
+        &self,
+        quote: &RaydiumAmmQuote,
+        wrap_sol: bool,
+        create_ata_input: bool,
+        create_ata_output: bool,
+        priority_fee: bool,
+        cu_limit: bool,
+    ) -> usize {
+        let wrap_instructions = if !wrap_sol {
+            0
+        } else if quote.input_mint == spl_token::native_mint::ID {
+            3
+        } else if quote.output_mint == spl_token::native_mint::ID {
+            1
+        } else {
+            0
+        };
+        // swap 指令本身
+        1 + wrap_instructions
+            + create_ata_input as usize
+            + create_ata_output as usize
+            + priority_fee as usize
+            + cu_limit as usize
+    }
+
+    // 计算池子的流动性利用率:24 小时交易量 / tvl
+    // 交易量取最近 lookback_slots 个 slot 内的 swap 输入数量,按交易实际覆盖的 slot 数折算为 24 小时
+    // (交易超过 MAX_POOL_HISTORY_TRANSACTIONS 笔时只覆盖最新的部分),交易量和 tvl 都按当前现货价格折算为 pc 计价,不依赖外部价格
+    // 大于 0.5 表示池子每天周转超过一半的 tvl,相对 lp 投入的手续费收入较高
+    pub async fn get_liquidity_utilization(
+        &self,
+        pool_id: Pubkey,
+        lookback_slots: u64,
+    ) -> anyhow::Result<f64> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        let window = self
+            .get_trade_history_window(pool_id, lookback_slots)
+            .await?;
+        let (coin_volume, pc_volume) = window
+            .transactions
+            .iter()
+            .filter_map(PoolTransaction::swap_amounts_in)
+            .fold((0u64, 0u64), |(coin, pc), (coin_in, pc_in)| {
+                (coin.saturating_add(coin_in), pc.saturating_add(pc_in))
+            });
+
+        let price = state.spot_price();
+        let (coin_decimals, pc_decimals) = (state.amm.coin_decimals, state.amm.pc_decimals);
+        let volume = crate::utils::to_ui_amount(coin_volume, coin_decimals) * price
+            + crate::utils::to_ui_amount(pc_volume, pc_decimals);
+        let tvl = crate::utils::to_ui_amount(state.coin_vault_amount, coin_decimals) * price
+            + crate::utils::to_ui_amount(state.pc_vault_amount, pc_decimals);
+        if !tvl.is_finite() || tvl <= 0.0 {
+            return Err(anyhow!("pool {} has no liquidity", pool_id));
+        }
+        let daily_volume = volume / window.covered_days();
+        Ok(daily_volume / tvl)
+    }
+
+    // 统计 owner 所有 lp 仓位按 base 代币(coin)分组的美元敞口
+    // 某个 base 代币的敞口超过 lp 总价值的 CONCENTRATION_THRESHOLD_PCT 时标记为集中风险
+    pub async fn get_pool_concentration_risk(
+        &self,
+        owner: Pubkey,
+    ) -> anyhow::Result<ConcentrationRiskReport> {
+        let positions = self.get_all_user_lp_positions(owner).await?;
+        let mut mints = positions
+            .iter()
+            .flat_map(|position| [position.coin_mint.to_string(), position.pc_mint.to_string()])
+            .collect::<Vec<_>>();
+        mints.sort();
+        mints.dedup();
+        let prices = if mints.is_empty() {
+            HashMap::new()
+        } else {
+            self.api.get_token_price(mints).await?
+        };
+        let price_of = |mint: &Pubkey| prices.get(mint).copied().unwrap_or_default();
+
+        let mut exposures: HashMap<Pubkey, f64> = HashMap::new();
+        for position in &positions {
+            let value_usd = crate::utils::to_ui_amount(position.coin_share, position.coin_decimals)
+                * price_of(&position.coin_mint)
+                + crate::utils::to_ui_amount(position.pc_share, position.pc_decimals)
+                    * price_of(&position.pc_mint);
+            *exposures.entry(position.coin_mint).or_default() += value_usd;
+        }
+
+        let total_value_usd = exposures.values().sum::<f64>();
+        let mut exposures = exposures
+            .into_iter()
+            .map(|(base_mint, value_usd)| {
+                let share_pct = if total_value_usd > 0.0 {
+                    value_usd / total_value_usd * 100.0
+                } else {
+                    0.0
+                };
+                BaseTokenExposure {
+                    base_mint,
+                    value_usd,
+                    share_pct,
+                    is_concentrated: share_pct > CONCENTRATION_THRESHOLD_PCT,
+                }
+            })
+            .collect::<Vec<_>>();
+        exposures.sort_by(|a, b| b.value_usd.total_cmp(&a.value_usd));
+
+        Ok(ConcentrationRiskReport {
+            total_value_usd,
+            exposures,
+        })
+    }
+
+    // 从已确认交易的前后代币余额中还原实际成交价格,用于记录交易日志
+    // 数量变化以交易者视角表示:收到为正,付出为负
+    pub async fn replay_price_from_transaction(
+        &self,
+        signature: Signature,
+        pool_id: Pubkey,
+    ) -> anyhow::Result<ExecutedPrice> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        let tx = self
+            .fetch_pool_transaction(signature, &state.amm_keys)
+            .await?
+            .with_context(|| {
+                format!(
+                    "Transaction {} did not trade in pool {}",
+                    signature, pool_id
+                )
+            })?;
+        let PoolTransactionKind::Swap(direction) = tx.kind() else {
+            return Err(anyhow!("Transaction {} is not a swap", signature));
+        };
+
+        let execution_price =
+            crate::utils::to_ui_amount(tx.pc_delta.unsigned_abs(), state.amm.pc_decimals)
+                / crate::utils::to_ui_amount(tx.coin_delta.unsigned_abs(), state.amm.coin_decimals);
+        Ok(ExecutedPrice {
+            base_amount_delta: -tx.coin_delta,
+            quote_amount_delta: -tx.pc_delta,
+            execution_price,
+            direction,
+        })
+    }
+
+    // 在交易对的所有 amm v4 池子上报价同一笔 exact-in 交易,按手续费档位分组
+    // 每个档位取扣除手续费后输出最多的池子,返回净输出最多的档位以及所有档位的对比表
+    pub async fn get_pool_fee_tier_recommendation(
+        &self,
+        input_mint: Pubkey,
+        output_mint: Pubkey,
+        amount: u64,
+    ) -> anyhow::Result<FeeTierRecommendation> {
+        let mut pool_ids = self
+            .get_program_accounts_by_mint(input_mint, output_mint)
+            .await?;
+        pool_ids.extend(
+            self.get_program_accounts_by_mint(output_mint, input_mint)
+                .await?,
+        );
+
+        let states = futures_util::stream::iter(pool_ids)
+            .map(|pool_id| self.fetch_pool_state(pool_id))
+            .buffered(8)
+            .collect::<Vec<_>>()
+            .await;
+
+        let mut tiers: HashMap<u64, FeeTierQuote> = HashMap::new();
+        for state in states {
+            let state = match state {
+                Ok(state) => state,
+                Err(e) => {
+                    log::warn!("skipping pool: {}", e);
+                    continue;
+                }
+            };
+            let direction = state.direction(&input_mint, &output_mint)?;
+            if state.input_vault_amount(direction) == 0 || state.output_vault_amount(direction) == 0
+            {
+                continue;
+            }
+            let quote = FeeTierQuote {
+                pool_id: state.pool_id,
+                fee_bps: state.pool_fee_bps(),
+                net_output: state.swap_output(direction, amount)?,
+                price_impact_bps: state.price_impact_bps(direction, amount)?,
+            };
+            let best = tiers.entry(quote.fee_bps).or_insert(quote);
+            if quote.net_output > best.net_output {
+                *best = quote;
+            }
+        }
+
+        let mut tiers = tiers.into_values().collect::<Vec<_>>();
+        tiers.sort_by(|a, b| b.net_output.cmp(&a.net_output));
+        let best = *tiers
+            .first()
+            .with_context(|| format!("No pool found for {} -> {}", input_mint, output_mint))?;
+        Ok(FeeTierRecommendation { best, tiers })
+    }
+
+    // 计算池子的综合风险评分,0 表示风险最高,10 表示风险最低
+    // 各项指标先归一化到 [0, 1](越大越安全),再按 RISK_SCORE_WEIGHTS 加权:
+    // 池龄 30 天封顶,tvl 按数量级计 100 万美元封顶,最大 lp 持有者占比越低越好,
+    // 是否在官方列表中,最近一小时成交价格的变异系数 5% 以上记为 0
+    pub async fn get_pool_risk_score(&self, pool_id: Pubkey) -> anyhow::Result<PoolRiskScore> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        let (coin_price_usd, pc_price_usd) = self.fetch_pool_token_prices(&state).await?;
+        let tvl_usd = state.tvl_usd(coin_price_usd, pc_price_usd);
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let age_days = now.saturating_sub(state.amm.state_data.pool_open_time) as f64 / 86_400.0;
+
+        let largest_holder = self
+            .client
+            .get_token_largest_accounts(&state.amm_keys.amm_lp_mint)
+            .await?
+            .first()
+            .and_then(|account| account.amount.amount.parse::<u64>().ok())
+            .unwrap_or_default();
+        let lp_concentration_score = if state.amm.lp_amount == 0 {
+            1.0
+        } else {
+            (largest_holder as f64 / state.amm.lp_amount as f64).min(1.0)
+        };
+
+        let is_verified = self.is_listed_by_api(pool_id).await?;
+
+        let prices = self
+            .get_trade_history(pool_id, SLOTS_PER_DAY / 24)
+            .await?
+            .iter()
+            .map(|tx| tx.pc_vault_after as f64 / tx.coin_vault_after as f64)
+            .filter(|price| price.is_finite())
+            .collect::<Vec<_>>();
+        let recent_price_volatility = super::math::coefficient_of_variation(&prices);
+
+        Ok(PoolRiskScore::new(
+            age_days,
+            tvl_usd,
+            lp_concentration_score,
+            is_verified,
+            recent_price_volatility,
+        ))
+    }
+
+    // 生成与 Jupiter QuoteResponse 字段一致的报价,使本 crate 可以作为 Jupiter 兼容的报价源
+    // 价格影响使用报价时读取的同一份池子状态计算,与 out_amount 一致
+    pub async fn get_jupiter_compatible_quote(
+        &self,
+        swap_input: &SwapInput,
+    ) -> anyhow::Result<JupiterCompatibleQuote> {
+        let (quote, state) = self.quote_with_state(swap_input).await?;
+        let direction = state.direction(&quote.input_mint, &quote.output_mint)?;
+        let (in_amount, out_amount) = if quote.amount_specified_is_input {
+            (quote.amount, quote.other_amount)
+        } else {
+            (quote.other_amount, quote.amount)
+        };
+        let price_impact_pct = state.price_impact_bps(direction, in_amount)? / 10_000.0;
+        let fee_amount = self.compute_swap_fee(&quote);
+
+        Ok(JupiterCompatibleQuote {
+            input_mint: quote.input_mint,
+            in_amount,
+            output_mint: quote.output_mint,
+            out_amount,
+            other_amount_threshold: quote.other_amount_threshold,
+            swap_mode: if quote.amount_specified_is_input {
+                "ExactIn"
+            } else {
+                "ExactOut"
+            }
+            .to_string(),
+            slippage_bps: swap_input.slippage_bps,
+            price_impact_pct,
+            route_plan: vec![JupiterRoutePlanStep {
+                swap_info: JupiterSwapInfo {
+                    amm_key: quote.market,
+                    label: "Raydium".to_string(),
+                    input_mint: quote.input_mint,
+                    output_mint: quote.output_mint,
+                    in_amount,
+                    out_amount,
+                    fee_amount,
+                    fee_mint: quote.output_mint,
+                },
+                percent: 100,
+            }],
+        })
+    }
+
+    // 按执行器的优先费配置估算一笔交换交易的手续费(lamports),不需要构建交易
+    // 包括一个签名的基础费用,FixedCuPrice 按默认的 200_000 计算单元计算优先费
+    fn expected_transaction_fee_lamports(&self) -> u64 {
+        let priority_fee = match self.config.priority_fee {
+            Some(PriorityFeeConfig::FixedCuPrice(cu_price)) => {
+                cu_price.saturating_mul(200_000) / 1_000_000
+            }
+            Some(PriorityFeeConfig::DynamicMultiplier(multiplier)) => {
+                multiplier.saturating_mul(100_000)
+            }
+            Some(PriorityFeeConfig::JitoTip(tip)) => tip,
+            None => 0,
+        };
+        LAMPORTS_PER_SIGNATURE + priority_fee
+    }
+
+    // 二分查找使 输出价值 - 输入价值 > 交易成本 的最小输入数量(原始单位)
+    // 只有池子价格相对外部美元价格有利时才可能盈利,搜索范围为输入侧储备的 1%,
+    // 在该范围内利润随数量单调增加;范围上限仍不盈利时返回错误
+    pub async fn get_minimum_viable_trade(
+        &self,
+        pool_id: Pubkey,
+        input_mint: Pubkey,
+        output_mint: Pubkey,
+        sol_price_usd: f64,
+    ) -> anyhow::Result<u64> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        let direction = state.direction(&input_mint, &output_mint)?;
+        let (coin_price_usd, pc_price_usd) = self.fetch_pool_token_prices(&state).await?;
+        let ((input_price, input_decimals), (output_price, output_decimals)) = match direction {
+            SwapDirection::Coin2PC => (
+                (coin_price_usd, state.amm.coin_decimals),
+                (pc_price_usd, state.amm.pc_decimals),
+            ),
+            SwapDirection::PC2Coin => (
+                (pc_price_usd, state.amm.pc_decimals),
+                (coin_price_usd, state.amm.coin_decimals),
+            ),
+        };
+        let cost_usd =
+            crate::utils::to_ui_amount(self.expected_transaction_fee_lamports(), 9) * sol_price_usd;
+        let is_viable = |amount_in: u64| -> anyhow::Result<bool> {
+            let amount_out = state.swap_output(direction, amount_in)?;
+            let profit_usd = crate::utils::to_ui_amount(amount_out, output_decimals) * output_price
+                - crate::utils::to_ui_amount(amount_in, input_decimals) * input_price;
+            Ok(profit_usd > cost_usd)
+        };
+
+        let mut high = state.input_vault_amount(direction) / 100;
+        if high == 0 || !is_viable(high)? {
+            return Err(anyhow!(
+                "No profitable trade size in pool {} for {} -> {}",
+                pool_id,
+                input_mint,
+                output_mint
+            ));
+        }
+        let mut low = 1;
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if is_viable(mid)? {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+        Ok(low)
+    }
+
+    // 计算 owner 在池子中的 lp 仓位自 last_sync_slot 以来的变化
+    // 从 last_sync_slot 之后的池子交易倒推当时的金库余额、lp 总量以及 owner 的 lp 数量(owner 作为 fee payer 的加减流动性交易),
+    // 再正向遍历这些交易,按 owner 当时的 lp 占比累计其分得的 swap 手续费
+    // last_sync_slot 之后的交易超过 MAX_POOL_HISTORY_TRANSACTIONS 笔时无法还原当时的状态,返回错误
+    pub async fn get_position_delta(
+        &self,
+        pool_id: Pubkey,
+        owner: Pubkey,
+        last_sync_slot: u64,
+    ) -> anyhow::Result<PositionDelta> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        let lp_ata = spl_associated_token_account::get_associated_token_address(
+            &owner,
+            &state.amm_keys.amm_lp_mint,
+        );
+        let lp_account = self
+            .client
+            .get_account_with_commitment(&lp_ata, CommitmentConfig::confirmed())
+            .await?
+            .value;
+        let lp_now = crate::utils::token_account_amount(lp_account.as_ref())?;
+        let current_slot = self.client.get_slot().await?;
+        let window = self
+            .get_pool_transactions_window(pool_id, last_sync_slot + 1, current_slot)
+            .await?;
+        if window.is_truncated {
+            return Err(anyhow!(
+                "Pool {} has more than {} transactions since slot {}, sync more often",
+                pool_id,
+                MAX_POOL_HISTORY_TRANSACTIONS,
+                last_sync_slot
+            ));
+        }
+        let transactions = window.transactions;
+
+        let (coin_vault_then, pc_vault_then) = match transactions.first() {
+            Some(tx) => (
+                (tx.coin_vault_after as i64 - tx.coin_delta) as u64,
+                (tx.pc_vault_after as i64 - tx.pc_delta) as u64,
+            ),
+            None => (state.coin_vault_balance, state.pc_vault_balance),
+        };
+        let minted_since = transactions.iter().map(|tx| tx.lp_delta).sum::<i64>();
+        let owner_minted_since = transactions
+            .iter()
+            .filter(|tx| tx.wallet == owner)
+            .map(|tx| tx.lp_delta)
+            .sum::<i64>();
+        let supply_then = (state.amm.lp_amount as i64 - minted_since).max(0) as u64;
+        let lp_then = (lp_now as i64 - owner_minted_since).max(0) as u64;
+
+        let share_of = |amount: u64, lp: u64, supply: u64| -> u64 {
+            (amount as u128 * lp as u128)
+                .checked_div(supply as u128)
+                .unwrap_or_default() as u64
+        };
+        let (fee_numerator, fee_denominator) = (
+            state.amm.fees.swap_fee_numerator,
+            state.amm.fees.swap_fee_denominator,
+        );
+        let (mut fee_earnings_coin, mut fee_earnings_pc) = (0u64, 0u64);
+        let (mut owner_lp, mut supply) = (lp_then, supply_then);
+        for tx in &transactions {
+            if let Some((coin_in, pc_in)) = tx.swap_amounts_in() {
+                let fee_of = |amount_in: u64| {
+                    (amount_in as u128 * fee_numerator as u128)
+                        .checked_div(fee_denominator as u128)
+                        .unwrap_or_default() as u64
+                };
+                fee_earnings_coin += share_of(fee_of(coin_in), owner_lp, supply);
+                fee_earnings_pc += share_of(fee_of(pc_in), owner_lp, supply);
+            }
+            supply = (supply as i64 + tx.lp_delta).max(0) as u64;
+            if tx.wallet == owner {
+                owner_lp = (owner_lp as i64 + tx.lp_delta).max(0) as u64;
+            }
+        }
+
+        let coin_delta = share_of(state.coin_vault_balance, lp_now, state.amm.lp_amount) as i64
+            - share_of(coin_vault_then, lp_then, supply_then) as i64;
+        let pc_delta = share_of(state.pc_vault_balance, lp_now, state.amm.lp_amount) as i64
+            - share_of(pc_vault_then, lp_then, supply_then) as i64;
+        let (coin_price_usd, pc_price_usd) = self.fetch_pool_token_prices(&state).await?;
+        let net_pnl_usd = coin_delta as f64 / 10f64.powi(state.amm.coin_decimals as i32)
+            * coin_price_usd
+            + pc_delta as f64 / 10f64.powi(state.amm.pc_decimals as i32) * pc_price_usd;
+
+        Ok(PositionDelta {
+            coin_delta,
+            pc_delta,
+            fee_earnings_coin,
+            fee_earnings_pc,
+            net_pnl_usd,
+        })
+    }
+
+    // 列出池子在 [from_slot, to_slot] 内的主要状态变化,用于审计
+    // swap 和加减流动性从 amm 程序的 ray_log 日志中按事件类型解码,状态和手续费的变化从 SetParams 指令中解析
+    pub async fn get_pool_event_log(
+        &self,
+        pool_id: Pubkey,
+        from_slot: u64,
+        to_slot: u64,
+    ) -> anyhow::Result<Vec<PoolEvent>> {
+        let mut signatures = self
+            .get_pool_signatures(pool_id, from_slot, Some(to_slot))
+            .await?;
+        signatures.reverse();
+        let transactions = futures_util::stream::iter(signatures)
+            .map(|signature| async move {
+                self.client
+                    .get_transaction_with_config(
+                        &signature,
+                        RpcTransactionConfig {
+                            encoding: Some(UiTransactionEncoding::Base64),
+                            commitment: Some(CommitmentConfig::confirmed()),
+                            max_supported_transaction_version: Some(0),
+                        },
+                    )
+                    .await
+            })
+            .buffered(16)
+            .collect::<Vec<_>>()
+            .await;
+
+        let mut events = Vec::new();
+        for tx in transactions {
+            events.extend(history::parse_pool_events(
+                &tx?,
+                &pool_id,
+                &RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID,
+            ));
+        }
+        Ok(events)
+    }
+
+    // 读取并解析池子的 TargetOrders 账户,用于调试 amm 的 orderbook 挂单和 pnl 计算
+    pub async fn get_target_orders_state(
+        &self,
+        pool_id: Pubkey,
+    ) -> anyhow::Result<TargetOrdersState> {
+        let (amm_keys, _) = self.load_pool_keys(pool_id).await?;
+        let target = self.load_target_orders(&amm_keys.amm_target).await?;
+
+        let orders = |orders: &[raydium_amm::state::TargetOrder], count: u64| {
+            orders
+                .iter()
+                .take(count as usize)
+                .map(|order| (order.price, order.vol))
+                .collect::<Vec<_>>()
+        };
+        Ok(TargetOrdersState {
+            target_orders: amm_keys.amm_target,
+            target_coin_amount: target.target_x,
+            target_pc_amount: target.target_y,
+            pnl_coin_baseline: target.calc_pnl_x,
+            pnl_pc_baseline: target.calc_pnl_y,
+            placed_coin_amount: target.placed_x,
+            placed_pc_amount: target.placed_y,
+            buy_orders: orders(&target.buy_orders, target.valid_buy_order_num),
+            sell_orders: orders(&target.sell_orders, target.valid_sell_order_num),
+            last_order_numerator: target.last_order_numerator,
+            last_order_denominator: target.last_order_denominator,
+        })
+    }
+
+    // 读取并解码 TargetOrders 账户
+    async fn load_target_orders(
+        &self,
+        target_orders: &Pubkey,
+    ) -> anyhow::Result<raydium_amm::state::TargetOrders> {
+        let account = self.client.get_account(target_orders).await?;
+        Ok(
+            transmute_one_pedantic::<raydium_amm::state::TargetOrders>(transmute_to_bytes(
+                &account.data,
+            ))
+            .map_err(|e| e.without_src())?,
+        )
+    }
+
+    // 估算把 current_balances 调整到 target_weights 所需的成本(手续费 + 价格冲击)
+    // 权重高于目标的代币按差额卖出,依次换成权重低于目标的代币,每一笔兑换都通过 quote 报价
+    // 价格冲击成本 = 输入价值 - 输出价值 - 手续费
+    pub async fn estimate_rebalancing_cost(
+        &self,
+        current_balances: HashMap<Pubkey, u64>,
+        target_weights: HashMap<Pubkey, f64>,
+        total_value_usd: f64,
+    ) -> anyhow::Result<RebalancingCost> {
+        let mut mints = current_balances
+            .keys()
+            .chain(target_weights.keys())
+            .copied()
+            .collect::<Vec<_>>();
+        mints.sort();
+        mints.dedup();
+        let prices = self
+            .api
+            .get_token_price(mints.iter().map(Pubkey::to_string).collect())
+            .await?;
+        let mint_accounts = crate::utils::get_multiple_account_data(&self.client, &mints).await?;
+        let mut decimals = HashMap::new();
+        for (mint, account) in mints.iter().zip(mint_accounts) {
+            let account = account.with_context(|| format!("Mint {} not found", mint))?;
+            decimals.insert(
+                *mint,
+                spl_token::state::Mint::unpack(&account.data)?.decimals,
+            );
+        }
+        let price_of = |mint: &Pubkey| -> anyhow::Result<f64> {
+            prices
+                .get(mint)
+                .copied()
+                .filter(|price| *price > 0.0)
+                .with_context(|| format!("No price for {}", mint))
+        };
+
+        // 正数为需要卖出的美元价值,负数为需要买入的美元价值
+        let mut surpluses = Vec::new();
+        let mut deficits = Vec::new();
+        for mint in &mints {
+            let balance = current_balances.get(mint).copied().unwrap_or_default();
+            let current_usd =
+                crate::utils::to_ui_amount(balance, decimals[mint] as u64) * price_of(mint)?;
+            let target_usd =
+                target_weights.get(mint).copied().unwrap_or_default() * total_value_usd;
+            let diff = current_usd - target_usd;
+            if diff > 0.0 {
+                surpluses.push((*mint, diff));
+            } else if diff < 0.0 {
+                deficits.push((*mint, -diff));
+            }
+        }
+
+        let mut cost = RebalancingCost {
+            total_fees_usd: 0.0,
+            total_slippage_usd: 0.0,
+            net_cost_usd: 0.0,
+            instructions_plan: vec![],
+        };
+        let (mut i, mut j) = (0, 0);
+        while i < surpluses.len() && j < deficits.len() {
+            let (input_mint, surplus_usd) = surpluses[i];
+            let (output_mint, deficit_usd) = deficits[j];
+            let trade_usd = surplus_usd.min(deficit_usd);
+            surpluses[i].1 -= trade_usd;
+            deficits[j].1 -= trade_usd;
+            if surpluses[i].1 <= f64::EPSILON {
+                i += 1;
+            }
+            if deficits[j].1 <= f64::EPSILON {
+                j += 1;
+            }
+
+            let input_price = price_of(&input_mint)?;
+            let amount =
+                (trade_usd / input_price * 10f64.powi(decimals[&input_mint] as i32)) as u64;
+            if amount == 0 {
+                continue;
+            }
+            let swap_input = SwapInput {
+                input_token_mint: input_mint,
+                output_token_mint: output_mint,
+                slippage_bps: REBALANCING_SLIPPAGE_BPS,
+                amount,
+                mode: SwapExecutionMode::ExactIn,
+                market: None,
+            };
+            let quote = self.quote(&swap_input).await?;
+            let output_price = price_of(&output_mint)?;
+            let fee_usd = crate::utils::to_ui_amount(
+                self.compute_swap_fee(&quote),
+                quote.output_mint_decimals as u64,
+            ) * output_price;
+            let output_usd =
+                crate::utils::to_ui_amount(quote.other_amount, quote.output_mint_decimals as u64)
+                    * output_price;
+            let input_usd =
+                crate::utils::to_ui_amount(amount, quote.input_mint_decimals as u64) * input_price;
+            cost.total_fees_usd += fee_usd;
+            cost.total_slippage_usd += (input_usd - output_usd - fee_usd).max(0.0);
+            cost.instructions_plan.push(swap_input);
+        }
+        cost.net_cost_usd = cost.total_fees_usd + cost.total_slippage_usd;
+        Ok(cost)
+    }
+
+    // 根据恒定乘积公式生成深度图数据,价格档位在现货价格上下 DEPTH_CHART_RANGE 内均匀分布
+    // 两侧的数量都以 coin 计: bids 为把价格压到该档位需要卖出的 coin 数量(含以 coin 支付的手续费),
+    // asks 为把价格推到该档位时从池子买出的 coin 数量(手续费以 pc 支付,不影响 coin 数量)
+    // 价格为 coin 以 pc 计价,数量为 coin 的 ui 数量
+    pub async fn get_pool_depth_chart_data(
+        &self,
+        pool_id: Pubkey,
+        num_price_levels: usize,
+    ) -> anyhow::Result<DepthChartData> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        let (x, y) = (state.coin_vault_amount as f64, state.pc_vault_amount as f64);
+        if x == 0.0 || y == 0.0 {
+            return Err(anyhow!("pool {} has an empty vault", pool_id));
+        }
+        let k = x * y;
+        let fee_rate = state.amm.fees.swap_fee_numerator as f64
+            / state.amm.fees.swap_fee_denominator.max(1) as f64;
+        let spot_price = state.spot_price();
+        let coin_decimals = state.amm.coin_decimals;
+        let step = DEPTH_CHART_RANGE / num_price_levels.max(1) as f64;
+
+        let mut bids = Vec::with_capacity(num_price_levels);
+        let mut asks = Vec::with_capacity(num_price_levels);
+        for level in 1..=num_price_levels {
+            let bid_ratio = 1.0 - step * level as f64;
+            let ask_ratio = 1.0 + step * level as f64;
+
+            // 价格比例对原始单位和 ui 单位的价格相同,档位价格下 coin 金库的数量为 sqrt(k / price)
+            let coin_in = ((k / (y / x * bid_ratio)).sqrt() - x) / (1.0 - fee_rate);
+            bids.push((
+                spot_price * bid_ratio,
+                crate::utils::to_ui_amount(coin_in as u64, coin_decimals),
+            ));
+
+            let coin_out = x - (k / (y / x * ask_ratio)).sqrt();
+            asks.push((
+                spot_price * ask_ratio,
+                crate::utils::to_ui_amount(coin_out as u64, coin_decimals),
+            ));
+        }
+        Ok(DepthChartData { bids, asks })
+    }
+
+    // 估算池子的年化收益,包括手续费收益、代币升值以及无常损失的拖累
+    // 手续费收益取 get_yield_rate;回看起点的价格取该 slot 之前最后一笔池子交易后的金库余额,
+    // 起点的 coin 美元价格按当前 pc 美元价格换算(假设 pc 的美元价格不变)
+    // 代币升值按起点时等值持有 coin 和 pc 计算,无常损失按起点到现在的价格变化计算
+    // 三项都与 get_yield_rate 一样按单利线性折算为年化,因此可以直接相加得到总收益
+    pub async fn get_pool_apy_estimate(
+        &self,
+        pool_id: Pubkey,
+        coin_price_usd: f64,
+        pc_price_usd: f64,
+        lookback_days: u32,
+    ) -> anyhow::Result<ApyEstimate> {
+        let lookback_days = lookback_days.max(1);
+        let state = self.fetch_pool_state(pool_id).await?;
+        let fee_apy_pct = self
+            .get_yield_rate(pool_id, lookback_days)
+            .await?
+            .annualized_fee_yield_pct;
+
+        let current_slot = self.client.get_slot().await?;
+        let start_slot = current_slot.saturating_sub(lookback_days as u64 * SLOTS_PER_DAY);
+        let start_tx = self
+            .get_pool_transaction_at_slot(pool_id, start_slot)
+            .await?
+            .with_context(|| format!("No pool transaction found before slot {}", start_slot))?;
+        let price_then = crate::utils::to_ui_amount(start_tx.pc_vault_after, state.amm.pc_decimals)
+            / crate::utils::to_ui_amount(start_tx.coin_vault_after, state.amm.coin_decimals);
+        let price_now = state.spot_price();
+        if !price_then.is_finite() || !price_now.is_finite() || price_then <= 0.0 {
+            return Err(anyhow!("pool {} has an empty vault", pool_id));
+        }
+
+        let coin_price_then_usd = price_then * pc_price_usd;
+        let hodl_return = 0.5 * coin_price_usd / coin_price_then_usd + 0.5 - 1.0;
+        let years = lookback_days as f64 / 365.0;
+        let price_appreciation_apy_pct = hodl_return / years * 100.0;
+        let impermanent_loss_drag_pct =
+            super::math::compute_impermanent_loss(price_now / price_then) / years * 100.0;
+
+        Ok(ApyEstimate {
+            fee_apy_pct,
+            price_appreciation_apy_pct,
+            total_apy_pct: fee_apy_pct + price_appreciation_apy_pct + impermanent_loss_drag_pct,
+            impermanent_loss_drag_pct,
+        })
+    }
+
+    // 基于均值回归判断当前是否适合提供流动性:价格接近历史均值时入场,后续的无常损失期望更小
+    // 在最近 lookback_slots 内均匀采样 ENTRY_SIGNAL_SAMPLES 个价格,偏离均值超过一个标准差时视为过度偏离
+    pub async fn get_optimal_lp_entry_price(
+        &self,
+        pool_id: Pubkey,
+        lookback_slots: u64,
+    ) -> anyhow::Result<LpEntrySignal> {
+        let current_price = self.fetch_pool_state(pool_id).await?.spot_price();
+        let current_slot = self.client.get_slot().await?;
+        let prices = self
+            .get_vault_history(
+                pool_id,
+                current_slot.saturating_sub(lookback_slots),
+                current_slot,
+                ENTRY_SIGNAL_SAMPLES,
+            )
+            .await?
+            .into_iter()
+            .map(|sample| sample.implied_price)
+            .filter(|price| price.is_finite())
+            .collect::<Vec<_>>();
+        if prices.is_empty() {
+            return Err(anyhow!("No price history for pool {}", pool_id));
+        }
+
+        let historical_mean = prices.iter().sum::<f64>() / prices.len() as f64;
+        let deviation_from_mean_pct = (current_price / historical_mean - 1.0) * 100.0;
+        let band_pct = super::math::coefficient_of_variation(&prices) * 100.0;
+        let signal = if deviation_from_mean_pct > band_pct {
+            EntrySignal::OverextendedUp
+        } else if deviation_from_mean_pct < -band_pct {
+            EntrySignal::OverextendedDown
+        } else {
+            EntrySignal::GoodEntry
+        };
+
+        Ok(LpEntrySignal {
+            current_price,
+            historical_mean,
+            deviation_from_mean_pct,
+            signal,
+        })
+    }
+
+    // 返回 amm v4 池子的 authority PDA 及其 bump,用于自行构建需要 authority 的指令
+    // authority 的种子只有 AUTHORITY_AMM,所有池子共用同一个 authority
+    pub fn get_pool_authority_pda() -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[raydium_amm::processor::AUTHORITY_AMM],
+            &RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID,
+        )
+    }
+
+    // 使用池子记录的 nonce 重新推导 authority PDA 并与给定的 authority 比较,不一致说明池子状态被篡改
+    // 与 get_pool_authority_pda 相同,authority 对整个程序只有一个,与池子无关; nonce 不是有效的 bump 时推导失败,同样返回 false
+    pub fn verify_authority_nonce(authority: &Pubkey, nonce: u8, program_id: &Pubkey) -> bool {
+        Pubkey::create_program_address(
+            &[raydium_amm::processor::AUTHORITY_AMM, &[nonce]],
+            program_id,
+        )
+        .is_ok_and(|derived| derived == *authority)
+    }
+
+    // 汇总 owner 钱包的资产: lp 仓位按池子中可赎回的代币计价, 其余代币按 api 价格计价,
+    // 原生 sol 与 wsol 按传入的 sol_price_usd 计价
+    pub async fn get_all_user_positions_summary(
+        &self,
+        owner: Pubkey,
+        sol_price_usd: f64,
+    ) -> anyhow::Result<UserPortfolioSummary> {
+        let mut balances = self.get_owner_token_balances(owner).await?;
+        let lp_positions = self.lp_positions_from_balances(&balances).await?;
+        for position in &lp_positions {
+            balances.remove(&position.lp_mint);
+        }
+        let native_balance = self.client.get_balance(&owner).await?;
+
+        let mut mints = balances
+            .keys()
+            .chain(
+                lp_positions
+                    .iter()
+                    .flat_map(|position| [&position.coin_mint, &position.pc_mint]),
+            )
+            .filter(|mint| **mint != spl_token::native_mint::ID)
+            .map(Pubkey::to_string)
+            .collect::<Vec<_>>();
+        mints.sort();
+        mints.dedup();
+        let prices = if mints.is_empty() {
+            HashMap::new()
+        } else {
+            self.api.get_token_price(mints).await?
+        };
+        let value_of = |mint: &Pubkey, amount: u64, decimals: u64| {
+            let price = if *mint == spl_token::native_mint::ID {
+                sol_price_usd
+            } else {
+                prices.get(mint).copied().unwrap_or_default()
+            };
+            crate::utils::to_ui_amount(amount, decimals) * price
+        };
+
+        let mut raw_token_balances = balances
+            .into_iter()
+            .map(|(mint, (amount, decimals))| TokenBalance {
+                mint,
+                amount,
+                decimals,
+                value_usd: value_of(&mint, amount, decimals as u64),
+            })
+            .collect::<Vec<_>>();
+        if native_balance > 0 {
+            raw_token_balances.push(TokenBalance {
+                mint: solana_sdk::system_program::ID,
+                amount: native_balance,
+                decimals: spl_token::native_mint::DECIMALS,
+                value_usd: crate::utils::to_ui_amount(
+                    native_balance,
+                    spl_token::native_mint::DECIMALS as u64,
+                ) * sol_price_usd,
+            });
+        }
+        raw_token_balances.sort_by(|a, b| b.value_usd.total_cmp(&a.value_usd));
+
+        let lp_value_usd = lp_positions
+            .iter()
+            .map(|position| {
+                value_of(
+                    &position.coin_mint,
+                    position.coin_share,
+                    position.coin_decimals,
+                ) + value_of(&position.pc_mint, position.pc_share, position.pc_decimals)
+            })
+            .sum::<f64>();
+        let total_value_usd = lp_value_usd
+            + raw_token_balances
+                .iter()
+                .map(|balance| balance.value_usd)
+                .sum::<f64>();
+
+        Ok(UserPortfolioSummary {
+            total_value_usd,
+            lp_positions,
+            raw_token_balances,
+            // api 只提供当前价格, 没有 24 小时前的价格可供比较
+            pnl_24h_usd: None,
+        })
+    }
+
+    // 按权重混合 amm 现货价格与外部预言机价格得到公允价格:
+    // amm_price * (1 - oracle_weight) + oracle_price * oracle_weight
+    pub async fn compute_fair_value(
+        &self,
+        pool_id: Pubkey,
+        oracle_price: f64,
+        oracle_weight: f64,
+    ) -> anyhow::Result<f64> {
+        if !(0.0..=1.0).contains(&oracle_weight) {
+            return Err(anyhow!(
+                "Oracle weight must be between 0 and 1, got {}",
+                oracle_weight
+            ));
+        }
+        if oracle_price <= 0.0 {
+            return Err(anyhow!(
+                "Oracle price must be positive, got {}",
+                oracle_price
+            ));
+        }
+        let amm_price = self.fetch_pool_state(pool_id).await?.spot_price();
+        let divergence_bps = (amm_price - oracle_price) / oracle_price * 10_000.0;
+        if divergence_bps.abs() > FAIR_VALUE_DIVERGENCE_WARNING_BPS {
+            log::warn!(
+                "pool {} price {} diverges from oracle price {} by {:.0} bps",
+                pool_id,
+                amm_price,
+                oracle_price,
+                divergence_bps
+            );
+        }
+        Ok(amm_price * (1.0 - oracle_weight) + oracle_price * oracle_weight)
+    }
+
+    // 估算创建一个新 amm 池子所需的 sol: amm 状态、两个金库、lp mint、open orders 和 target orders
+    // 账户的免租金额,租金参数从链上 rent sysvar 读取
+    pub async fn get_pool_creation_cost(&self) -> anyhow::Result<PoolCreationCost> {
+        let rent_account = self
+            .client
+            .get_account(&solana_sdk::sysvar::rent::ID)
+            .await?;
+        let rent: Rent = bincode::deserialize(&rent_account.data)?;
+
+        let amm_info_rent = Self::rent_for_account_type(RaydiumAccountType::AmmInfo, &rent);
+        let vaults_rent = 2 * rent.minimum_balance(spl_token::state::Account::LEN);
+        let lp_mint_rent = Self::rent_for_account_type(RaydiumAccountType::LpMint, &rent);
+        let open_orders_rent = Self::rent_for_account_type(RaydiumAccountType::OpenOrders, &rent);
+        let target_orders_rent =
+            Self::rent_for_account_type(RaydiumAccountType::TargetOrders, &rent);
+        Ok(PoolCreationCost {
+            total_lamports_required: amm_info_rent
+                + vaults_rent
+                + lp_mint_rent
+                + open_orders_rent
+                + target_orders_rent,
+            amm_info_rent,
+            vaults_rent,
+            lp_mint_rent,
+            open_orders_rent,
+            target_orders_rent,
+        })
+    }
+
+    // 根据池子账户所属的程序判断 amm 版本,并与最新版本比较
+    pub async fn get_pool_upgrade_status(
+        &self,
+        pool_id: Pubkey,
+    ) -> anyhow::Result<PoolUpgradeStatus> {
+        let program_id = self.client.get_account(&pool_id).await?.owner;
+        let current_version = RAYDIUM_LIQUIDITY_POOL_PROGRAMS
+            .iter()
+            .find(|(_, program)| *program == program_id)
+            .map(|(version, _)| *version)
+            .with_context(|| {
+                format!(
+                    "Pool {} is owned by {}, which is not a raydium liquidity pool program",
+                    pool_id, program_id
+                )
+            })?;
+        Ok(PoolUpgradeStatus {
+            current_version,
+            is_latest: current_version == LATEST_AMM_VERSION,
+            program_id,
+        })
+    }
+
+    // 按 swap 指令中的顺序返回其所需的全部账户,调用方可据此预先检查账户是否存在或批量获取账户数据
+    // 用户的输入输出账户为对应 mint 的 ATA,与 make_swap 一致
+    pub fn get_swap_instruction_accounts_list(
+        quote: &RaydiumAmmQuote,
+        owner: Pubkey,
+    ) -> anyhow::Result<Vec<AccountMeta>> {
+        Ok(swap_instruction(
+            &RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID,
+            &quote.amm_keys,
+            &quote.market_keys,
+            &owner,
+            &spl_associated_token_account::get_associated_token_address(&owner, &quote.input_mint),
+            &spl_associated_token_account::get_associated_token_address(&owner, &quote.output_mint),
+            quote.amount,
+            quote.other_amount_threshold,
+            quote.amount_specified_is_input,
+        )?
+        .accounts)
+    }
+
+    // 返回池子中 coin 与 pc 的数量比例(按精度换算),以及与 1:1 的偏离程度
+    // 对于稳定币交易对,比例明显偏离 1 意味着存在套利机会
+    pub async fn get_coin_to_pc_ratio(&self, pool_id: Pubkey) -> anyhow::Result<CoinToPcRatio> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        if state.coin_vault_amount == 0 || state.pc_vault_amount == 0 {
+            return Err(anyhow!("pool {} has an empty vault", pool_id));
+        }
+        let ratio = crate::utils::to_ui_amount(state.coin_vault_amount, state.amm.coin_decimals)
+            / crate::utils::to_ui_amount(state.pc_vault_amount, state.amm.pc_decimals);
+        Ok(CoinToPcRatio {
+            coin_amount: state.coin_vault_amount,
+            pc_amount: state.pc_vault_amount,
+            ratio,
+            deviation_from_parity_bps: ((ratio - 1.0) * 10_000.0).round() as i64,
+        })
+    }
+
+    // 校验池子两个金库代币账户的 owner 是否为 amm authority PDA
+    // 金库地址直接从链上的 AmmInfo 读取,不依赖 API 返回的 keys
+    pub async fn get_vault_token_accounts(
+        &self,
+        pool_id: Pubkey,
+    ) -> anyhow::Result<VaultVerification> {
+        let amm_keys = self.rebuild_amm_keys_from_chain(pool_id).await?;
+        let amm_authority = Self::get_pool_authority_pda().0;
+        let vaults = crate::utils::get_multiple_account_data(
+            &self.client,
+            &[amm_keys.amm_coin_vault, amm_keys.amm_pc_vault],
+        )
+        .await?;
+        let owner_valid = |vault: &Option<solana_sdk::account::Account>| {
+            vault
+                .as_ref()
+                .filter(|account| account.owner == spl_token::ID)
+                .and_then(|account| spl_token::state::Account::unpack(&account.data).ok())
+                .is_some_and(|vault| vault.owner == amm_authority)
+        };
+        let coin_vault_owner_valid = owner_valid(&vaults[0]);
+        let pc_vault_owner_valid = owner_valid(&vaults[1]);
+        Ok(VaultVerification {
+            coin_vault_owner_valid,
+            pc_vault_owner_valid,
+            is_tampered: !coin_vault_owner_valid || !pc_vault_owner_valid,
+        })
+    }
+
+    // 比较池子在 slot_a 和 slot_b 两个时刻的状态,用于监控异常的流动性变化
+    // 汇总 (slot_a, slot_b] 之间所有池子交易的金库与 lp 变化;slot_a 时的金库余额由 slot_b 时的余额减去变化量得到
+    // 区间内交易超过 MAX_POOL_HISTORY_TRANSACTIONS 笔时只统计最新的部分
+    pub async fn get_pool_state_diff(
+        &self,
+        pool_id: Pubkey,
+        slot_a: u64,
+        slot_b: u64,
+    ) -> anyhow::Result<PoolStateDiff> {
+        if slot_a > slot_b {
+            return Err(anyhow!(
+                "slot_a ({}) must not be after slot_b ({})",
+                slot_a,
+                slot_b
+            ));
+        }
+        let transactions = self
+            .get_pool_transactions(pool_id, slot_a + 1, Some(slot_b))
+            .await?;
+        let coin_delta = transactions.iter().map(|tx| tx.coin_delta).sum::<i64>();
+        let pc_delta = transactions.iter().map(|tx| tx.pc_delta).sum::<i64>();
+        let lp_supply_delta = transactions.iter().map(|tx| tx.lp_delta).sum::<i64>();
+
+        let implied_price_delta_bps = match transactions.last() {
+            Some(last) => {
+                let (coin_b, pc_b) = (last.coin_vault_after as f64, last.pc_vault_after as f64);
+                let (coin_a, pc_a) = (coin_b - coin_delta as f64, pc_b - pc_delta as f64);
+                if coin_a > 0.0 && pc_a > 0.0 && coin_b > 0.0 {
+                    let (price_a, price_b) = (pc_a / coin_a, pc_b / coin_b);
+                    ((price_b - price_a) / price_a * 10_000.0).round() as i64
+                } else {
+                    0
+                }
+            }
+            None => 0,
+        };
+
+        Ok(PoolStateDiff {
+            coin_delta,
+            pc_delta,
+            lp_supply_delta,
+            implied_price_delta_bps,
+            num_transactions_between: transactions.len() as u64,
+        })
+    }
+
+    // 在同一笔交易中先由 funder 向 recipient 转入 transfer_lamports 个 lamports,再以 recipient 的身份执行兑换
+    // 转账指令放在所有准备指令之前,兑换时包装 sol 可以直接使用刚转入的 lamports
+    // funder 支付交易费,funder 与 recipient 不同时交易需要两者共同签名
+    pub async fn build_transfer_and_swap_transaction(
+        &self,
+        funder: Pubkey,
+        recipient: Pubkey,
+        transfer_lamports: u64,
+        quote: RaydiumAmmQuote,
+        overrides: Option<&SwapConfigOverrides>,
+    ) -> anyhow::Result<VersionedTransaction> {
+        self.ensure_swap_allowed(&quote).await?;
+        let mut builder = self.make_swap(recipient, quote, overrides, vec![]).await?;
+        builder.setup_instructions.insert(
+            0,
+            solana_sdk::system_instruction::transfer(&funder, &recipient, transfer_lamports),
+        );
+        let message =
+            VersionedMessage::Legacy(Message::new(&builder.build_instructions()?, Some(&funder)));
+        Ok(VersionedTransaction {
+            signatures: vec![
+                Signature::default();
+                message.header().num_required_signatures as usize
+            ],
+            message,
+        })
+    }
+
+    // 不构建交易,只返回 from_mint 兑换为 to_mint 的汇率
+    // 使用 get_recommended_pool 选出的池子,按金库数量计算无价格影响(交易量趋近于 0)且不含手续费的价格
+    // 结果缓存 EXCHANGE_RATE_TTL
+    pub async fn get_exchange_rate(
+        &self,
+        from_mint: Pubkey,
+        to_mint: Pubkey,
+    ) -> anyhow::Result<ExchangeRate> {
+        if let Some((fetched_at, rate)) = self
+            .exchange_rates
+            .read()
+            .unwrap()
+            .get(&(from_mint, to_mint))
+        {
+            if fetched_at.elapsed() < EXCHANGE_RATE_TTL {
+                return Ok(*rate);
+            }
+        }
+
+        let pool_id = self
+            .get_recommended_pool(from_mint, to_mint, 0)
+            .await?
+            .pool_id;
+        let state = self.fetch_pool_state(pool_id).await?;
+        if state.coin_vault_amount == 0 || state.pc_vault_amount == 0 {
+            return Err(anyhow!("pool {} has an empty vault", pool_id));
+        }
+        let spot_price = state.spot_price();
+        let rate = if from_mint == state.amm_keys.amm_coin_mint {
+            spot_price
+        } else {
+            1.0 / spot_price
+        };
+        let exchange_rate = ExchangeRate {
+            rate,
+            inverse_rate: 1.0 / rate,
+            pool_id,
+        };
+        self.exchange_rates
+            .write()
+            .unwrap()
+            .insert((from_mint, to_mint), (Instant::now(), exchange_rate));
+        Ok(exchange_rate)
+    }
+
+    // 对给定的输入数量,比较直接兑换与经由 SOL 或 USDC 的两跳兑换,返回预期输出最多的路径及 owner 执行该路径的指令
+    // 所有候选路径并发报价,两跳路径的报价见 quote_two_hop_routes;只为选中的路径构建指令,见 route_instructions
+    pub async fn get_best_route_for_amount(
+        &self,
+        owner: Pubkey,
+        input_mint: Pubkey,
+        output_mint: Pubkey,
+        amount: u64,
+        slippage_bps: u16,
+    ) -> anyhow::Result<BestRoute> {
+        let direct = async {
+            self.quote(&SwapInput {
+                input_token_mint: input_mint,
+                output_token_mint: output_mint,
+                slippage_bps,
+                amount,
+                mode: SwapExecutionMode::ExactIn,
+                market: None,
+            })
+            .await
+        };
+        let (direct, two_hops) = futures_util::join!(
+            direct,
+            self.quote_two_hop_routes(input_mint, output_mint, amount, slippage_bps)
+        );
+
+        let direct = direct
+            .map_err(|e| log::debug!("Route candidate failed: {}", e))
+            .ok()
+            .map(|quote| (RouteType::Direct, quote.other_amount, vec![quote]));
+        let (route_type, expected_output, quotes) = std::iter::once(direct)
+            .flatten()
+            .chain(
+                two_hops
+                    .into_iter()
+                    .map(|(intermediate_mint, first, second)| {
+                        (
+                            RouteType::TwoHop { intermediate_mint },
+                            second.other_amount,
+                            vec![first, second],
+                        )
+                    }),
+            )
+            .max_by_key(|(_, expected_output, _)| *expected_output)
+            .ok_or_else(|| anyhow!("No route found for {} -> {}", input_mint, output_mint))?;
+        Ok(BestRoute {
+            route_type,
+            expected_output,
+            instructions: self.route_instructions(owner, quotes).await?,
+        })
+    }
+
+    // 经由 SOL 或 USDC 的两跳 exact-in 报价,返回每个报价成功的中间代币及两跳的报价
+    // 第二跳以第一跳不计滑点的输出作为输入,使两跳路径的 other_amount 与直接兑换可比
+    async fn quote_two_hop_routes(
+        &self,
+        input_mint: Pubkey,
+        output_mint: Pubkey,
+        amount: u64,
+        slippage_bps: u16,
+    ) -> Vec<(Pubkey, RaydiumAmmQuote, RaydiumAmmQuote)> {
+        let swap_input = |input_token_mint, output_token_mint, amount| SwapInput {
+            input_token_mint,
+            output_token_mint,
+            slippage_bps,
+            amount,
+            mode: SwapExecutionMode::ExactIn,
+            market: None,
+        };
+        let two_hops = [spl_token::native_mint::ID, USDC_MINT]
+            .into_iter()
+            .filter(|mint| *mint != input_mint && *mint != output_mint)
+            .map(|intermediate_mint| async move {
+                let first = self
+                    .quote(&swap_input(input_mint, intermediate_mint, amount))
+                    .await?;
+                let second = self
+                    .quote(&swap_input(
+                        intermediate_mint,
+                        output_mint,
+                        first.other_amount,
+                    ))
+                    .await?;
+                Ok::<_, anyhow::Error>((intermediate_mint, first, second))
+            });
+        futures_util::future::join_all(two_hops)
+            .await
+            .into_iter()
+            .filter_map(|candidate| {
+                candidate
+                    .map_err(|e| log::debug!("Route candidate failed: {}", e))
+                    .ok()
+            })
+            .collect()
+    }
+
+    // 构建执行 get_best_route_for_amount 所选路径的指令,quotes 为各跳的报价,owner 为执行兑换的钱包
+    // 两跳路径的第二跳只能使用第一跳保证的最少输出 other_amount_threshold 作为输入,
+    // 其最少输出按同样比例缩小;恒定乘积曲线下输出随输入凹增,缩小后的最少输出不会高于实际可得的输出
+    async fn route_instructions(
+        &self,
+        owner: Pubkey,
+        quotes: Vec<RaydiumAmmQuote>,
+    ) -> anyhow::Result<Vec<Instruction>> {
+        let (first, second) = match <[RaydiumAmmQuote; 2]>::try_from(quotes) {
+            Ok([first, second]) => (first, second),
+            Err(mut quotes) if quotes.len() == 1 => {
+                return self.swap_instructions(owner, quotes.remove(0), None).await
+            }
+            Err(quotes) => return Err(anyhow!("Invalid route with {} legs", quotes.len())),
+        };
+        self.ensure_swap_allowed(&first).await?;
+        self.ensure_swap_allowed(&second).await?;
+
+        let second_amount_in = first.other_amount_threshold;
+        let second_minimum_out = (second.other_amount_threshold as u128 * second_amount_in as u128
+            / second.amount.max(1) as u128) as u64;
+        let output_mint = second.output_mint;
+        let mut second_leg = vec![
+            spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                &owner,
+                &owner,
+                &output_mint,
+                &spl_token::ID,
+            ),
+            swap_instruction(
+                &RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID,
+                &second.amm_keys,
+                &second.market_keys,
+                &owner,
+                &spl_associated_token_account::get_associated_token_address(
+                    &owner,
+                    &second.input_mint,
+                ),
+                &spl_associated_token_account::get_associated_token_address(&owner, &output_mint),
+                second_amount_in,
+                second_minimum_out,
+                true,
+            )?,
+        ];
+        // 第一跳的 builder 只处理了中间代币的 wsol 账户,输出为 sol 时需要自行关闭输出的 wsol 账户
+        if output_mint == spl_token::native_mint::ID
+            && self.config.wrap_and_unwrap_sol.unwrap_or(true)
+        {
+            second_leg.push(spl_token::instruction::close_account(
+                &spl_token::ID,
+                &spl_associated_token_account::get_associated_token_address(&owner, &output_mint),
+                &owner,
+                &owner,
+                &[],
+            )?);
+        }
+        let builder = self.make_swap(owner, first, None, second_leg).await?;
+        builder.build_instructions()
+    }
+
+    // 读取 TargetOrders 中记录的锚定价格并与当前价格比较
+    // TargetOrders 中没有 buy_x3/sell_x3 字段,amm 以 calc_pnl_x/calc_pnl_y 作为计算 pnl 的基准储备,
+    // 两者都按 sys_decimal_value 归一化,因此 calc_pnl_y / calc_pnl_x 即为按精度换算后的锚定价格
+    pub async fn get_pool_anchor_price(&self, pool_id: Pubkey) -> anyhow::Result<AnchorPrice> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        let target = self.load_target_orders(&state.amm_keys.amm_target).await?;
+        if target.calc_pnl_x == 0 || target.calc_pnl_y == 0 {
+            return Err(anyhow!("pool {} has no anchor price", pool_id));
+        }
+        let target_price = target.calc_pnl_y as f64 / target.calc_pnl_x as f64;
+        let current_price = state.spot_price();
+        let delta_bps = ((current_price - target_price) / target_price * 10_000.0).round() as i64;
+        Ok(AnchorPrice {
+            target_price,
+            current_price,
+            delta_bps,
+            is_above_target: current_price > target_price,
+        })
+    }
+
+    // 统计 protocol_lp_holders 持有的 lp 代币(协议自有流动性)与其余 lp 持有者的份额
+    // 每个地址的所有 lp 代币账户都会计入
+    pub async fn get_protocol_owned_liquidity(
+        &self,
+        pool_id: Pubkey,
+        protocol_lp_holders: &[Pubkey],
+    ) -> anyhow::Result<ProtocolLiquidityInfo> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        let lp_mint = state.amm_keys.amm_lp_mint;
+        let balances = futures_util::stream::iter(protocol_lp_holders)
+            .map(|holder| self.get_owner_token_balances(*holder))
+            .buffered(8)
+            .collect::<Vec<_>>()
+            .await;
+        let mut protocol_lp_tokens = 0;
+        for balances in balances {
+            protocol_lp_tokens += balances?.get(&lp_mint).map_or(0, |(amount, _)| *amount);
+        }
+
+        let lp_supply = state.amm.lp_amount;
+        Ok(ProtocolLiquidityInfo {
+            protocol_lp_tokens,
+            community_lp_tokens: lp_supply.saturating_sub(protocol_lp_tokens),
+            protocol_share_bps: (protocol_lp_tokens as u128 * 10_000)
+                .checked_div(lp_supply as u128)
+                .unwrap_or_default() as u64,
+        })
+    }
+
+    // 计算池子的 coin 有多少挂在 openbook 的 open orders 中
+    // coin_vault_amount = 金库余额 + open orders 中的数量 - 待提取的 pnl,据此反推 open orders 中的数量
+    // 池子没有 orderbook 权限时 coin_vault_amount 不包含 open orders,结果为 0
+    pub async fn get_coin_vault_utilization(
+        &self,
+        pool_id: Pubkey,
+    ) -> anyhow::Result<VaultUtilization> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        let total_coin_vault = state.coin_vault_amount;
+        let coin_in_open_orders = (total_coin_vault + state.amm.state_data.need_take_pnl_coin)
+            .saturating_sub(state.coin_vault_balance);
+        Ok(VaultUtilization {
+            total_coin_vault,
+            coin_in_open_orders,
+            coin_available: total_coin_vault.saturating_sub(coin_in_open_orders),
+            utilization_pct: if total_coin_vault == 0 {
+                0.0
+            } else {
+                coin_in_open_orders as f64 / total_coin_vault as f64 * 100.0
+            },
+        })
+    }
+
+    // 计算池子的综合表现评分,各项均归一化到 [0, 1],越大越好:
+    // liquidity_score = log10(tvl_usd) / 6,100 万美元封顶
+    // yield_score = 最近一天的年化手续费收益率 / 100%,封顶为 1;收益率已经包含了手续费率与成交量的影响
+    // stability_score = 1 - 最近一小时成交价格的变异系数 / 5%,最低为 0
+    // composite 为三项按 PERFORMANCE_SCORE_WEIGHTS 的加权和,调用方也可以用各分项自行加权
+    pub async fn get_pool_performance_score(
+        &self,
+        pool_id: Pubkey,
+    ) -> anyhow::Result<PerformanceScore> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        let (coin_price_usd, pc_price_usd) = self.fetch_pool_token_prices(&state).await?;
+        let tvl_usd = state.tvl_usd(coin_price_usd, pc_price_usd);
+        let fee_yield_pct = self
+            .get_yield_rate(pool_id, 1)
+            .await?
+            .annualized_fee_yield_pct;
+        let prices = self
+            .get_trade_history(pool_id, SLOTS_PER_DAY / 24)
+            .await?
+            .iter()
+            .map(|tx| tx.pc_vault_after as f64 / tx.coin_vault_after as f64)
+            .filter(|price| price.is_finite())
+            .collect::<Vec<_>>();
+        let volatility = super::math::coefficient_of_variation(&prices);
+
+        let liquidity_score = (tvl_usd.max(1.0).log10() / 6.0).min(1.0);
+        let yield_score = (fee_yield_pct / 100.0).clamp(0.0, 1.0);
+        let stability_score = 1.0 - (volatility / 0.05).min(1.0);
+        let (liquidity_weight, yield_weight, stability_weight) = PERFORMANCE_SCORE_WEIGHTS;
+        Ok(PerformanceScore {
+            liquidity_score,
+            yield_score,
+            stability_score,
+            composite: liquidity_score * liquidity_weight
+                + yield_score * yield_weight
+                + stability_score * stability_weight,
+        })
+    }
+
+    // 生成定投计划:把 total_amount 平均拆成 num_orders 笔 exact-in 兑换,每隔 interval 执行一笔
+    // 无法整除的余数分摊到前几笔;池子在执行时再通过 quote 选择
+    pub fn build_dollar_cost_average_plan(
+        &self,
+        input_mint: Pubkey,
+        output_mint: Pubkey,
+        total_amount: u64,
+        num_orders: usize,
+        interval: Duration,
+    ) -> DcaPlan {
+        let num_orders = num_orders as u64;
+        let orders = (0..num_orders)
+            .map(|i| SwapInput {
+                input_token_mint: input_mint,
+                output_token_mint: output_mint,
+                slippage_bps: DCA_SLIPPAGE_BPS,
+                amount: total_amount / num_orders + u64::from(i < total_amount % num_orders),
+                mode: SwapExecutionMode::ExactIn,
+                market: None,
+            })
+            .filter(|order| order.amount > 0)
+            .collect();
+        DcaPlan {
+            orders,
+            start: Instant::now(),
+            interval,
+        }
+    }
+
+    // 返回 now 时刻应当执行的那一笔定投,第 i 笔在 start + i * interval 到期,计划结束或尚未开始时返回 None
+    // 计划本身不记录执行状态,调用方需要自行避免在同一个时间段内重复执行
+    pub fn next_order<'a>(&self, plan: &'a DcaPlan, now: Instant) -> Option<&'a SwapInput> {
+        let elapsed = now.checked_duration_since(plan.start)?;
+        let index = if plan.interval.is_zero() {
+            0
+        } else {
+            (elapsed.as_nanos() / plan.interval.as_nanos()) as usize
+        };
+        plan.orders.get(index)
+    }
+
+    // 根据最近 PERFORMANCE_SAMPLES 个性能样本的平均 tps 判断网络拥堵程度
+    // tps 越低说明出块中能打包的交易越少,拥堵越严重
+    pub async fn get_network_congestion_level(&self) -> anyhow::Result<CongestionLevel> {
+        let samples = self
+            .client
+            .get_recent_performance_samples(Some(PERFORMANCE_SAMPLES))
+            .await?;
+        let (total_transactions, total_secs) =
+            samples
+                .iter()
+                .fold((0u64, 0u64), |(transactions, secs), s| {
+                    (
+                        transactions + s.num_transactions,
+                        secs + s.sample_period_secs as u64,
+                    )
+                });
+        if total_secs == 0 {
+            return Err(anyhow!("No performance samples available"));
+        }
+        let mean_tps = total_transactions as f64 / total_secs as f64;
+        Ok(if mean_tps > 3_000.0 {
+            CongestionLevel::Low
+        } else if mean_tps >= 1_500.0 {
+            CongestionLevel::Medium
+        } else if mean_tps >= 500.0 {
+            CongestionLevel::High
+        } else {
+            CongestionLevel::Extreme
+        })
+    }
+
+    // 不同拥堵程度下建议的优先费,以兑换价值的基点表示
+    pub fn suggested_priority_fee_bps(&self, level: CongestionLevel) -> u64 {
+        PRIORITY_FEE_BPS_BY_CONGESTION[level as usize]
+    }
+
+    // 统计最近 lookback_slots 内的买卖压力,买入指用 pc 换 coin(PC2Coin),卖出指用 coin 换 pc(Coin2PC)
+    // 买卖量都以 pc 计:买入为付给池子的 pc,卖出为从池子收到的 pc
+    pub async fn get_token_flow(
+        &self,
+        pool_id: Pubkey,
+        lookback_slots: u64,
+    ) -> anyhow::Result<TokenFlow> {
+        let trades = self.get_trade_history(pool_id, lookback_slots).await?;
+        let (mut net_coin_bought, mut net_pc_bought) = (0i64, 0i64);
+        let (mut buy_volume, mut sell_volume) = (0u64, 0u64);
+        for tx in &trades {
+            match tx.kind() {
+                PoolTransactionKind::Swap(SwapDirection::PC2Coin) => {
+                    buy_volume = buy_volume.saturating_add(tx.pc_delta.unsigned_abs());
+                }
+                PoolTransactionKind::Swap(SwapDirection::Coin2PC) => {
+                    sell_volume = sell_volume.saturating_add(tx.pc_delta.unsigned_abs());
+                }
+                _ => continue,
+            }
+            // 池子金库减少的数量即为交易者买走的数量
+            net_coin_bought = net_coin_bought.saturating_sub(tx.coin_delta);
+            net_pc_bought = net_pc_bought.saturating_sub(tx.pc_delta);
+        }
+
+        let buy_sell_ratio = match (buy_volume, sell_volume) {
+            (0, 0) => 1.0,
+            (_, 0) => f64::INFINITY,
+            (buy, sell) => buy as f64 / sell as f64,
+        };
+        Ok(TokenFlow {
+            net_coin_bought,
+            net_pc_bought,
+            buy_volume,
+            sell_volume,
+            buy_sell_ratio,
+        })
+    }
+
+    // 计算两个池子在最近 lookback_slots 内价格对数收益率的皮尔逊相关系数,例如与 SOL/USDC 池比较
+    // 两个池子在相同的 slot 检查点上采样 CORRELATION_SAMPLES 个价格,只使用两边都有价格的检查点
+    pub async fn get_correlation_with_market(
+        &self,
+        pool_id: Pubkey,
+        market_pool_id: Pubkey,
+        lookback_slots: u64,
+    ) -> anyhow::Result<f64> {
+        let current_slot = self.client.get_slot().await?;
+        let from_slot = current_slot.saturating_sub(lookback_slots);
+        let (pool_history, market_history) = futures_util::try_join!(
+            self.get_vault_history(pool_id, from_slot, current_slot, CORRELATION_SAMPLES),
+            self.get_vault_history(market_pool_id, from_slot, current_slot, CORRELATION_SAMPLES),
+        )?;
+        let market_prices = market_history
+            .into_iter()
+            .map(|sample| (sample.slot, sample.implied_price))
+            .collect::<HashMap<_, _>>();
+        let (pool_prices, market_prices): (Vec<f64>, Vec<f64>) = pool_history
+            .into_iter()
+            .filter_map(|sample| Some((sample.implied_price, *market_prices.get(&sample.slot)?)))
+            .filter(|(pool, market)| pool.is_finite() && market.is_finite())
+            .unzip();
+        if pool_prices.len() < 3 {
+            return Err(anyhow!(
+                "Not enough price history to correlate pool {} with {}",
+                pool_id,
+                market_pool_id
+            ));
+        }
+        Ok(super::math::pearson_correlation(
+            &super::math::log_returns(&pool_prices),
+            &super::math::log_returns(&market_prices),
+        ))
+    }
+
+    // 检测最近 lookback_slots 内的刷量交易
+    // 同一个钱包(交易的 fee payer)在同一个 slot 内既买又卖,视为一次往返,相关的兑换交易都计入 round_trip_txn_count
+    // same_wallet_both_sides_count 为回看期内任意时间既买过又卖过的钱包数量
+    // suspicion_level 为往返交易占全部兑换交易的比例
+    pub async fn get_wash_trading_score(
+        &self,
+        pool_id: Pubkey,
+        lookback_slots: u64,
+    ) -> anyhow::Result<WashTradingScore> {
+        let trades = self.get_trade_history(pool_id, lookback_slots).await?;
+        // (买入次数, 卖出次数)
+        let mut by_wallet_and_slot: HashMap<(Pubkey, u64), (u64, u64)> = HashMap::new();
+        let mut by_wallet: HashMap<Pubkey, (u64, u64)> = HashMap::new();
+        for tx in &trades {
+            let (buys, sells) = match tx.kind() {
+                PoolTransactionKind::Swap(SwapDirection::PC2Coin) => (1, 0),
+                PoolTransactionKind::Swap(SwapDirection::Coin2PC) => (0, 1),
+                _ => continue,
+            };
+            for sides in [
+                by_wallet_and_slot.entry((tx.wallet, tx.slot)).or_default(),
+                by_wallet.entry(tx.wallet).or_default(),
+            ] {
+                sides.0 += buys;
+                sides.1 += sells;
+            }
+        }
+
+        let swap_count = by_wallet
+            .values()
+            .map(|(buys, sells)| buys + sells)
+            .sum::<u64>();
+        let round_trip_txn_count = by_wallet_and_slot
+            .values()
+            .filter(|(buys, sells)| *buys > 0 && *sells > 0)
+            .map(|(buys, sells)| buys + sells)
+            .sum::<u64>();
+        let same_wallet_both_sides_count = by_wallet
+            .values()
+            .filter(|(buys, sells)| *buys > 0 && *sells > 0)
+            .count() as u64;
+        Ok(WashTradingScore {
+            suspicion_level: if swap_count == 0 {
+                0.0
+            } else {
+                round_trip_txn_count as f64 / swap_count as f64
+            },
+            round_trip_txn_count,
+            same_wallet_both_sides_count,
+        })
+    }
+
+    // 比较池子中 coin 的深度与 coin 的总供应量,占比越低,大户抛售时越容易把池子抽干
+    pub async fn get_token_supply_ratio(&self, pool_id: Pubkey) -> anyhow::Result<SupplyRatio> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        let coin_total_supply = self
+            .client
+            .get_token_supply(&state.amm_keys.amm_coin_mint)
+            .await?
+            .amount
+            .parse::<u64>()?;
+        let coin_pool_depth = state.coin_vault_amount;
+        Ok(SupplyRatio {
+            coin_pool_depth,
+            coin_total_supply,
+            pool_depth_pct: if coin_total_supply == 0 {
+                0.0
+            } else {
+                coin_pool_depth as f64 / coin_total_supply as f64 * 100.0
+            },
+        })
+    }
+
+    // 列出包含 mint 的所有 amm v4 标准池,即路由图中 mint 节点的邻接表
+    // 只请求一页,按流动性从高到低最多返回 100 个池子
+    pub async fn get_adjacent_pools(&self, mint: Pubkey) -> anyhow::Result<Vec<ApiV3StandardPool>> {
+        let response: ApiV3PoolsPage<ApiV3StandardPool> = self
+            .api
+            .fetch_pool_by_mints(
+                &mint,
+                None,
+                &PoolFetchParams {
+                    pool_type: PoolType::Standard,
+                    pool_sort: PoolSort::Liquidity,
+                    sort_type: PoolSortOrder::Descending,
+                    page_size: 100,
+                    page: 1,
+                },
+            )
+            .await?;
+        Ok(response
+            .pools
+            .into_iter()
+            .filter(|pool| pool.program_id == RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID)
+            .collect())
+    }
+
+    // 还原池子在 target_slot 时的金库余额
+    // getAccountInfo 的 minContextSlot 只能要求节点的状态不早于该 slot,无法读取过去的账户状态,
+    // 因此与 get_vault_history 一样取 target_slot 之前最后一笔池子交易执行后的金库余额
+    // 较早的 slot 需要节点保留完整的交易历史(archive 节点),普通节点只能查询最近的交易
+    // 返回的是金库的原始余额,包含尚未提取的 pnl
+    pub async fn get_pool_state_at_slot(
+        &self,
+        pool_id: Pubkey,
+        target_slot: u64,
+    ) -> anyhow::Result<HistoricalPoolState> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        let tx = self
+            .get_pool_transaction_at_slot(pool_id, target_slot)
+            .await?
+            .with_context(|| format!("No pool transaction found before slot {}", target_slot))?;
+        Ok(HistoricalPoolState {
+            coin_vault_amount: tx.coin_vault_after,
+            pc_vault_amount: tx.pc_vault_after,
+            implied_price: crate::utils::to_ui_amount(tx.pc_vault_after, state.amm.pc_decimals)
+                / crate::utils::to_ui_amount(tx.coin_vault_after, state.amm.coin_decimals),
+        })
+    }
+
+    // 汇总同一交易对所有池子的现货价格(coin 以 pc 计价),返回中位数、均值和范围
+    // tvl 低于 CONSENSUS_MIN_TVL_USD 的池子容易被操纵,不参与统计
+    pub async fn get_token_price_consensus(
+        &self,
+        coin_mint: Pubkey,
+        pc_mint: Pubkey,
+    ) -> anyhow::Result<ConsensusPrice> {
+        let response: ApiV3PoolsPage<ApiV3StandardPool> = self
+            .api
+            .fetch_pool_by_mints(
+                &coin_mint,
+                Some(&pc_mint),
+                &PoolFetchParams {
+                    pool_type: PoolType::Standard,
+                    pool_sort: PoolSort::Liquidity,
+                    sort_type: PoolSortOrder::Descending,
+                    page_size: 100,
+                    page: 1,
+                },
+            )
+            .await?;
+        let pool_ids = response
+            .pools
+            .iter()
+            .filter(|pool| pool.program_id == RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID)
+            .filter(|pool| pool.tvl >= CONSENSUS_MIN_TVL_USD)
+            .map(|pool| pool.id)
+            .collect::<Vec<_>>();
+
+        let states = futures_util::stream::iter(pool_ids)
+            .map(|pool_id| self.fetch_pool_state(pool_id))
+            .buffered(8)
+            .collect::<Vec<_>>()
+            .await;
+        let mut prices = Vec::with_capacity(states.len());
+        for state in states {
+            let state = state?;
+            let spot_price = state.spot_price();
+            // 池子的 coin 可能是请求中的 pc,此时取倒数
+            let price = if state.amm_keys.amm_coin_mint == coin_mint {
+                spot_price
+            } else {
+                1.0 / spot_price
+            };
+            if price.is_finite() && price > 0.0 {
+                prices.push(price);
+            }
+        }
+        if prices.is_empty() {
+            return Err(anyhow!(
+                "No pool with at least {} usd of liquidity found for {} / {}",
+                CONSENSUS_MIN_TVL_USD,
+                coin_mint,
+                pc_mint
+            ));
+        }
+
+        prices.sort_by(f64::total_cmp);
+        let pool_count = prices.len();
+        Ok(ConsensusPrice {
+            median_price: super::math::median(&prices),
+            mean_price: prices.iter().sum::<f64>() / pool_count as f64,
+            min_price: prices[0],
+            max_price: prices[pool_count - 1],
+            pool_count,
+        })
+    }
+
+    // 计算能够容纳一次 amount 大小的三明治抢跑的滑点,再加上 SANDWICH_SLIPPAGE_BUFFER_BPS
+    // 抢跑交易与我们的交易同向,使输出代币相对输入代币的现货价格下降,下降的幅度即为我们至少需要容忍的滑点
+    // amount 以 sol 计(lamports),池子中没有 sol 时以 coin 计;抢跑方向为用该代币买入另一种代币
+    pub async fn get_sandwich_protection_slippage(
+        &self,
+        pool_id: Pubkey,
+        amount: u64,
+    ) -> anyhow::Result<u16> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        let direction = if state.amm_keys.amm_pc_mint == spl_token::native_mint::ID {
+            SwapDirection::PC2Coin
+        } else {
+            SwapDirection::Coin2PC
+        };
+        let input_reserve = state.input_vault_amount(direction) as f64;
+        let output_reserve = state.output_vault_amount(direction) as f64;
+        if input_reserve == 0.0 || output_reserve == 0.0 {
+            return Err(anyhow!("pool {} has an empty vault", pool_id));
+        }
+        let front_run_output = state.swap_output(direction, amount)? as f64;
+        let price_before = output_reserve / input_reserve;
+        let price_after = (output_reserve - front_run_output) / (input_reserve + amount as f64);
+        let price_move_bps = ((1.0 - price_after / price_before) * 10_000.0)
+            .ceil()
+            .max(0.0);
+        Ok((price_move_bps as u16).saturating_add(SANDWICH_SLIPPAGE_BUFFER_BPS))
+    }
+
+    // 估算池子被销毁的 lp,这是一个启发式的判断,并不查询任何锁仓程序
+    // amm v4 没有官方的 lp 锁仓程序,常见的锁定方式是直接销毁 lp 代币;销毁只会减少 lp mint 的供应量,
+    // 不会改变 AmmInfo 中记录的 lp_amount,两者之差即为被销毁的 lp
+    // 转入第三方锁仓程序的 lp 仍计入供应量,不会被识别;没有被销毁的 lp 时返回 None
+    pub async fn get_pool_burned_lp(&self, pool_id: Pubkey) -> anyhow::Result<Option<BurnedLp>> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        let lp_supply = self
+            .client
+            .get_token_supply(&state.amm_keys.amm_lp_mint)
+            .await?
+            .amount
+            .parse::<u64>()?;
+        let burned_lp_tokens = state.amm.lp_amount.saturating_sub(lp_supply);
+        if burned_lp_tokens == 0 {
+            return Ok(None);
+        }
+        Ok(Some(BurnedLp {
+            burned_lp_tokens,
+            percentage_burned: burned_lp_tokens as f64 / state.amm.lp_amount as f64 * 100.0,
+        }))
+    }
+
+    // 读取 mint 账户的 mint authority 和 freeze authority
+    // 设置了 mint authority 的代币可以随时增发,稀释池子中的代币价值
+    // token-2022 的 mint 账户前 82 字节与 spl-token 布局相同,只解析这一部分
+    pub async fn get_token_mint_authority(
+        &self,
+        mint: Pubkey,
+    ) -> anyhow::Result<MintAuthorityInfo> {
+        let account = self.client.get_account(&mint).await?;
+        let data = account
+            .data
+            .get(..spl_token::state::Mint::LEN)
+            .with_context(|| format!("Account {} is not a mint", mint))?;
+        let mint_state = spl_token::state::Mint::unpack(data)?;
+        let authority = Option::<Pubkey>::from(mint_state.mint_authority);
+        Ok(MintAuthorityInfo {
+            authority,
+            is_mintable: authority.is_some(),
+            is_freeze_authority_set: mint_state.freeze_authority.is_some(),
+        })
+    }
+
+    // 监控池子的恒定乘积 k = coin_vault * pc_vault,与上一次调用时记录的 k 比较
+    // 增减流动性会按 lp 数量的平方改变 k,因此 k_drift_pct 比较的是每单位 lp 对应的 k(k / lp_amount^2)
+    // 手续费只会让它增大,减小超过 K_DRIFT_ALERT_PCT 说明不变量被破坏(bug 或攻击),此时记录警告
+    // 第一次调用时没有可比较的 k,previous_k 为 None,k_drift_pct 为 0
+    pub async fn get_pool_reserve_ratio(&self, pool_id: Pubkey) -> anyhow::Result<ReserveRatio> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        let k_invariant = state.coin_vault_amount as u128 * state.pc_vault_amount as u128;
+        let lp_amount = state.amm.lp_amount;
+        let previous = self
+            .reserve_invariants
+            .write()
+            .unwrap()
+            .insert(pool_id, (k_invariant, lp_amount));
+
+        let k_per_lp = |k: u128, lp_amount: u64| k as f64 / (lp_amount as f64).powi(2);
+        let k_drift_pct = match previous {
+            Some((previous_k, previous_lp_amount)) if previous_k > 0 && previous_lp_amount > 0 => {
+                let previous_k_per_lp = k_per_lp(previous_k, previous_lp_amount);
+                (k_per_lp(k_invariant, lp_amount) - previous_k_per_lp) / previous_k_per_lp * 100.0
+            }
+            _ => 0.0,
+        };
+        if k_drift_pct < -K_DRIFT_ALERT_PCT {
+            log::warn!(
+                "constant product per lp of pool {} dropped by {:.4}%",
+                pool_id,
+                -k_drift_pct
+            );
+        }
+        Ok(ReserveRatio {
+            k_invariant,
+            previous_k: previous.map(|(previous_k, _)| previous_k),
+            k_drift_pct,
+        })
+    }
+
+    // 统计 from_slot 以来池子收取的兑换手续费,按兑换方向分别计入 coin 和 pc
+    // 每笔兑换的手续费 = 付给池子的数量 * 当前手续费率,兑换事件来自 get_pool_event_log,
+    // 只包含该池子的 amm 指令输出的事件,经由多个池子路由的交易不会把其他池子的兑换计入
+    pub async fn get_accumulated_fees_since_slot(
+        &self,
+        pool_id: Pubkey,
+        from_slot: u64,
+    ) -> anyhow::Result<AccumulatedFees> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        let current_slot = self.client.get_slot().await?;
+        let events = self
+            .get_pool_event_log(pool_id, from_slot, current_slot)
+            .await?;
+        Ok(AccumulatedFees::from_events(
+            &events,
+            state.amm.fees.swap_fee_numerator,
+            state.amm.fees.swap_fee_denominator,
+        ))
+    }
+
+    // 计算做市(提供流动性)策略自 entry_slot 以来的盈亏
+    // owner 当前的仓位按 lp ATA 余额占 lp 总量的比例分摊金库;手续费收益为池子自 entry_slot 以来的手续费按当前 lp 占比分摊
+    // 入场时的 coin 美元价格按 entry_slot 时池子的价格和当前 pc 美元价格换算(假设 pc 的美元价格不变),
+    // net_pnl_usd = 当前仓位价值 - 入场时 initial_coin 和 initial_pc 的价值
+    pub async fn get_market_maker_pnl(
+        &self,
+        pool_id: Pubkey,
+        owner: Pubkey,
+        entry_slot: u64,
+        initial_coin: u64,
+        initial_pc: u64,
+    ) -> anyhow::Result<MarketMakerPnl> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        let lp_ata = spl_associated_token_account::get_associated_token_address(
+            &owner,
+            &state.amm_keys.amm_lp_mint,
+        );
+        let lp_account = self.client.get_multiple_accounts(&[lp_ata]).await?;
+        let lp_balance = crate::utils::token_account_amount(lp_account[0].as_ref())?;
+        let share_of = |amount: u64| {
+            (amount as u128 * lp_balance as u128)
+                .checked_div(state.amm.lp_amount as u128)
+                .unwrap_or_default() as u64
+        };
+        let current_coin = share_of(state.coin_vault_amount);
+        let current_pc = share_of(state.pc_vault_amount);
+
+        let pool_fees = self
+            .get_accumulated_fees_since_slot(pool_id, entry_slot)
+            .await?;
+        let fee_earnings = AccumulatedFees {
+            coin_fees: share_of(pool_fees.coin_fees),
+            pc_fees: share_of(pool_fees.pc_fees),
+            num_swap_events: pool_fees.num_swap_events,
+        };
+
+        let entry_tx = self
+            .get_pool_transaction_at_slot(pool_id, entry_slot)
+            .await?
+            .with_context(|| format!("No pool transaction found before slot {}", entry_slot))?;
+        let (coin_decimals, pc_decimals) = (state.amm.coin_decimals, state.amm.pc_decimals);
+        let price_then = crate::utils::to_ui_amount(entry_tx.pc_vault_after, pc_decimals)
+            / crate::utils::to_ui_amount(entry_tx.coin_vault_after, coin_decimals);
+        let price_now = state.spot_price();
+        if !price_then.is_finite() || !price_now.is_finite() || price_then <= 0.0 {
+            return Err(anyhow!("pool {} has an empty vault", pool_id));
+        }
+        let impermanent_loss_pct =
+            super::math::compute_impermanent_loss(price_now / price_then) * 100.0;
+
+        let (coin_price_usd, pc_price_usd) = self.fetch_pool_token_prices(&state).await?;
+        let current_value_usd = crate::utils::to_ui_amount(current_coin, coin_decimals)
+            * coin_price_usd
+            + crate::utils::to_ui_amount(current_pc, pc_decimals) * pc_price_usd;
+        let initial_value_usd =
+            crate::utils::to_ui_amount(initial_coin, coin_decimals) * price_then * pc_price_usd
+                + crate::utils::to_ui_amount(initial_pc, pc_decimals) * pc_price_usd;
+
+        Ok(MarketMakerPnl {
+            current_coin,
+            current_pc,
+            fee_earnings,
+            impermanent_loss_pct,
+            net_pnl_usd: current_value_usd - initial_value_usd,
+        })
+    }
+
+    // 比较同一交易对两个池子的价格(均为池子 a 的 coin 以 pc 计价),衡量跨池套利空间
+    // 在较便宜的池子买入 coin、在较贵的池子卖出,扣除两个池子的手续费后仍为正才视为有利可图
+    // profitable_direction 为在池子 a 上的交易方向:PC2Coin 表示在 a 买入 coin 再到 b 卖出,Coin2PC 相反
+    pub async fn get_arbitrage_spread(
+        &self,
+        pool_id_a: Pubkey,
+        pool_id_b: Pubkey,
+    ) -> anyhow::Result<ArbitrageSpread> {
+        let (state_a, state_b) = futures_util::try_join!(
+            self.fetch_pool_state(pool_id_a),
+            self.fetch_pool_state(pool_id_b),
+        )?;
+        let (coin_mint, pc_mint) = (state_a.amm_keys.amm_coin_mint, state_a.amm_keys.amm_pc_mint);
+        let pool_a_price = state_a.spot_price();
+        let pool_b_price = if state_b.amm_keys.amm_coin_mint == coin_mint
+            && state_b.amm_keys.amm_pc_mint == pc_mint
+        {
+            state_b.spot_price()
+        } else if state_b.amm_keys.amm_coin_mint == pc_mint
+            && state_b.amm_keys.amm_pc_mint == coin_mint
+        {
+            1.0 / state_b.spot_price()
+        } else {
+            return Err(anyhow!(
+                "Pools {} and {} do not trade the same pair",
+                pool_id_a,
+                pool_id_b
+            ));
+        };
+        if !pool_a_price.is_finite() || !pool_b_price.is_finite() || pool_a_price <= 0.0 {
+            return Err(anyhow!(
+                "pool {} or {} has an empty vault",
+                pool_id_a,
+                pool_id_b
+            ));
+        }
+
+        let spread_bps = ((pool_b_price - pool_a_price) / pool_a_price * 10_000.0).round() as i64;
+        let fees_bps = (state_a.pool_fee_bps() + state_b.pool_fee_bps()) as i64;
+        let estimated_profit_bps_after_fees = spread_bps.abs() - fees_bps;
+        let profitable_direction = if estimated_profit_bps_after_fees <= 0 {
+            None
+        } else if spread_bps > 0 {
+            Some(SwapDirection::PC2Coin)
+        } else {
+            Some(SwapDirection::Coin2PC)
+        };
+        Ok(ArbitrageSpread {
+            pool_a_price,
+            pool_b_price,
+            spread_bps,
+            profitable_direction,
+            estimated_profit_bps_after_fees,
+        })
+    }
+
+    // 检查代币是否在已知的诈骗代币列表中
+    // 先查配置的 flagged_mints,check_api_blacklist 开启时再查 raydium api 的代币黑名单和代币列表
+    // strict_mode 下代币被标记时直接返回错误
+    pub async fn is_token_flagged(&self, mint: Pubkey) -> anyhow::Result<TokenFlagStatus> {
+        let status = if let Some(reason) = self.flagged_mints.get(&mint) {
+            TokenFlagStatus::Flagged {
+                reason: reason.clone(),
+            }
+        } else if self.check_api_blacklist {
+            let token_list = self.api.get_token_list().await?;
+            let mint_str = mint.to_string();
+            if token_list
+                .blacklist
+                .iter()
+                .any(|token| token.address == mint)
+            {
+                TokenFlagStatus::Flagged {
+                    reason: "blacklisted by the raydium api".to_string(),
+                }
+            } else if token_list
+                .mint_list
+                .iter()
+                .any(|token| token.address == mint)
+                || token_list.whitelist.contains(&mint_str)
+            {
+                TokenFlagStatus::Safe
+            } else {
+                TokenFlagStatus::Unknown
+            }
+        } else {
+            TokenFlagStatus::Unknown
+        };
+
+        if let (true, TokenFlagStatus::Flagged { reason }) = (self.strict_mode, &status) {
+            return Err(anyhow!("Token {} is flagged: {}", mint, reason));
+        }
+        Ok(status)
+    }
+
+    // 判断 amm v4 池子是否已迁移到 clmm:池子状态为 Disabled,且同一交易对存在 clmm 池子
+    // new_pool_id 取该交易对流动性最高的 clmm 池子
+    // 链上没有记录迁移时间,migration_slot 取池子最近一笔成功交易的 slot,池子被禁用后通常不再有交易
+    pub async fn get_pool_migration_status(
+        &self,
+        pool_id: Pubkey,
+    ) -> anyhow::Result<MigrationStatus> {
+        let account = self.client.get_account(&pool_id).await?;
+        let amm = decode_amm_info(&account.data)?;
+        if AmmPoolStatus::from(amm.status) != AmmPoolStatus::Disabled {
+            return Ok(MigrationStatus {
+                is_migrated: false,
+                new_pool_id: None,
+                migration_slot: None,
+            });
+        }
+
+        let response: ApiV3PoolsPage<ApiV3ClmmPool> = self
+            .api
+            .fetch_pool_by_mints(
+                &amm.coin_vault_mint,
+                Some(&amm.pc_vault_mint),
+                &PoolFetchParams {
+                    pool_type: PoolType::Concentrated,
+                    pool_sort: PoolSort::Liquidity,
+                    sort_type: PoolSortOrder::Descending,
+                    page_size: 1,
+                    page: 1,
+                },
+            )
+            .await?;
+        let Some(new_pool_id) = response.pools.first().map(|pool| pool.id) else {
+            return Ok(MigrationStatus {
+                is_migrated: false,
+                new_pool_id: None,
+                migration_slot: None,
+            });
+        };
+
+        let migration_slot = self
+            .get_last_successful_slot(&pool_id, STATUS_CHANGE_LOOKBACK_TRANSACTIONS)
+            .await?;
+        Ok(MigrationStatus {
+            is_migrated: true,
+            new_pool_id: Some(new_pool_id),
+            migration_slot,
+        })
+    }
+
+    // 在 address 最近 limit 笔交易中查找最后一笔成功交易所在的 slot,没有成功交易时返回 None
+    // 池子被禁用后通常不再有交易,最后一笔成功交易一般就是修改池子状态的管理员交易
+    async fn get_last_successful_slot(
+        &self,
+        address: &Pubkey,
+        limit: usize,
+    ) -> anyhow::Result<Option<u64>> {
+        Ok(self
+            .client
+            .get_signatures_for_address_with_config(
+                address,
+                GetConfirmedSignaturesForAddress2Config {
+                    before: None,
+                    until: None,
+                    limit: Some(limit),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                },
+            )
+            .await?
+            .into_iter()
+            .find(|status| status.err.is_none())
+            .map(|status| status.slot))
+    }
+
+    // 把 swap_input.amount 按 split_weights 拆分到多个池子分别报价,返回汇总后的数量和平均成交价
+    // 权重会被归一化,取整后剩余的数量计入最后一个池子;权重为 0 的池子不参与报价
+    // effective_price 为每单位输入代币换得的输出代币数量(按精度换算)
+    pub async fn get_effective_price_after_aggregation(
+        &self,
+        swap_input: &SwapInput,
+        pool_ids: Vec<Pubkey>,
+        split_weights: Vec<f64>,
+    ) -> anyhow::Result<AggregatedQuote> {
+        if pool_ids.is_empty() || pool_ids.len() != split_weights.len() {
+            return Err(anyhow!(
+                "Expected one split weight per pool, got {} pools and {} weights",
+                pool_ids.len(),
+                split_weights.len()
+            ));
+        }
+        if split_weights
+            .iter()
+            .any(|weight| !weight.is_finite() || *weight < 0.0)
+        {
+            return Err(anyhow!("Split weights must be non-negative"));
+        }
+        let total_weight = split_weights.iter().sum::<f64>();
+        if total_weight <= 0.0 {
+            return Err(anyhow!("Split weights must not all be zero"));
+        }
+
+        let pool_count = pool_ids.len();
+        let mut remaining = swap_input.amount;
+        let mut portions = Vec::with_capacity(pool_count);
+        for (i, (pool_id, weight)) in pool_ids.into_iter().zip(split_weights).enumerate() {
+            let amount = if i == pool_count - 1 {
+                remaining
+            } else {
+                ((swap_input.amount as f64 * weight / total_weight) as u64).min(remaining)
+            };
+            remaining -= amount;
+            if amount > 0 {
+                portions.push(SwapInput {
+                    amount,
+                    market: Some(pool_id),
+                    ..*swap_input
+                });
+            }
+        }
+        let quotes =
+            futures_util::future::try_join_all(portions.iter().map(|portion| self.quote(portion)))
+                .await?;
+
+        let other_amount = quotes.iter().map(|quote| quote.other_amount).sum::<u64>();
+        let (total_input, total_output) = if swap_input.mode.amount_specified_is_input() {
+            (swap_input.amount, other_amount)
+        } else {
+            (other_amount, swap_input.amount)
+        };
+        let effective_price = match quotes.first() {
+            Some(quote) if total_input > 0 => {
+                crate::utils::to_ui_amount(total_output, quote.output_mint_decimals as u64)
+                    / crate::utils::to_ui_amount(total_input, quote.input_mint_decimals as u64)
+            }
+            _ => 0.0,
+        };
+        Ok(AggregatedQuote {
+            quotes,
+            total_input,
+            total_output,
+            effective_price,
+        })
+    }
+
+    // 根据最近的性能样本估算平均出块时间和下一个区块的出块时刻
+    pub async fn get_block_time_estimate(&self) -> anyhow::Result<BlockTimeEstimate> {
+        let slot_time = self.get_mean_slot_time().await?;
+        let current_slot = self.client.get_slot().await?;
+        Ok(BlockTimeEstimate {
+            mean_slot_duration_ms: slot_time.as_secs_f64() * 1000.0,
+            current_slot,
+            estimated_next_block_time: SystemTime::now() + slot_time,
+        })
+    }
+
+    // 衡量池子抵抗价格操纵的能力:分别计算使价格影响达到 1%、5%、10% 所需投入的美元价值
+    // 仓库中没有 get_price_impact_table,这里对两个方向分别二分查找达到目标价格影响的输入数量,取成本较低的方向
+    // 成本越高说明池子越稳定
+    pub async fn get_pool_stability_index(
+        &self,
+        pool_id: Pubkey,
+    ) -> anyhow::Result<StabilityIndex> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        let (coin_price_usd, pc_price_usd) = self.fetch_pool_token_prices(&state).await?;
+
+        state.stability_index(coin_price_usd, pc_price_usd)
+    }
+
+    // 判断当前 UTC 时间是否落在任意一个交易时间窗口内,不在所有窗口内时返回 false
+    // 仓库中没有 swap_with_keypair,配置了 trading_windows 时由 ensure_swap_allowed 在构建用于提交的交换交易前检查
+    pub fn is_within_trading_window(&self, windows: &[TradingWindow]) -> bool {
+        let now = chrono::Utc::now();
+        windows.iter().any(|window| window.contains(now))
+    }
+
+    // 计算池子两个代币价格序列的皮尔逊相关系数,接近 1 表示两个代币同涨同跌(例如两个稳定币),无常损失风险低
+    // 单个池子只能给出两个代币的相对价格,因此两个代币分别使用与同一个计价代币之间流动性最高的 amm v4 池子,
+    // 在相同的 slot 检查点上得到以计价代币表示的价格,再比较两者的对数收益率
+    // 计价代币为 USDC,池子本身包含 USDC 时改用 SOL
+    pub async fn get_pool_token_pair_correlation(
+        &self,
+        pool_id: Pubkey,
+        lookback_slots: u64,
+    ) -> anyhow::Result<f64> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        let (coin_mint, pc_mint) = (state.amm_keys.amm_coin_mint, state.amm_keys.amm_pc_mint);
+        let quote_mint = [USDC_MINT, spl_token::native_mint::ID]
+            .into_iter()
+            .find(|mint| *mint != coin_mint && *mint != pc_mint)
+            .with_context(|| format!("No independent quote token to price pool {}", pool_id))?;
+
+        let current_slot = self.client.get_slot().await?;
+        let from_slot = current_slot.saturating_sub(lookback_slots);
+        let (coin_history, pc_history) = futures_util::try_join!(
+            self.get_price_history_in(coin_mint, quote_mint, from_slot, current_slot),
+            self.get_price_history_in(pc_mint, quote_mint, from_slot, current_slot),
+        )?;
+        let pc_prices = pc_history.into_iter().collect::<HashMap<_, _>>();
+        let (coin_prices, pc_prices): (Vec<f64>, Vec<f64>) = coin_history
+            .into_iter()
+            .filter_map(|(slot, coin_price)| Some((coin_price, *pc_prices.get(&slot)?)))
+            .filter(|(coin, pc)| coin.is_finite() && pc.is_finite())
+            .unzip();
+        if coin_prices.len() < 3 {
+            return Err(anyhow!(
+                "Not enough price history to correlate the tokens of pool {}",
+                pool_id
+            ));
+        }
+        Ok(super::math::pearson_correlation(
+            &super::math::log_returns(&coin_prices),
+            &super::math::log_returns(&pc_prices),
+        ))
+    }
+
+    // mint 以 quote_mint 计价的价格历史,来自两者之间流动性最高的 amm v4 池子,返回 (slot, 价格)
+    async fn get_price_history_in(
+        &self,
+        mint: Pubkey,
+        quote_mint: Pubkey,
+        from_slot: u64,
+        to_slot: u64,
+    ) -> anyhow::Result<Vec<(u64, f64)>> {
+        let response: ApiV3PoolsPage<ApiV3StandardPool> = self
+            .api
+            .fetch_pool_by_mints(
+                &mint,
+                Some(&quote_mint),
+                &PoolFetchParams {
+                    pool_type: PoolType::Standard,
+                    pool_sort: PoolSort::Liquidity,
+                    sort_type: PoolSortOrder::Descending,
+                    page_size: 100,
+                    page: 1,
+                },
+            )
+            .await?;
+        let pool_id = response
+            .pools
+            .iter()
+            .find(|pool| pool.program_id == RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID)
+            .map(|pool| pool.id)
+            .with_context(|| format!("No amm v4 pool found for {} and {}", mint, quote_mint))?;
+        let (state, history) = futures_util::try_join!(
+            self.fetch_pool_state(pool_id),
+            self.get_vault_history(pool_id, from_slot, to_slot, CORRELATION_SAMPLES),
+        )?;
+        // implied_price 是以 pc 表示的 coin 价格,mint 为 pc 时取倒数
+        let is_coin = state.amm_keys.amm_coin_mint == mint;
+        Ok(history
+            .into_iter()
+            .map(|sample| {
+                let price = if is_coin {
+                    sample.implied_price
+                } else {
+                    sample.implied_price.recip()
+                };
+                (sample.slot, price)
+            })
+            .collect())
+    }
+
+    // 根据最近 slot 的最低优先费,估算给定优先费的交易在 2 个 slot 内确认的概率
+    // getRecentPrioritizationFees 返回最近(最多 150 个)slot 中已上链交易的最低优先费,一次请求即可获得,
+    // 以给定优先费不低于最低优先费的 slot 比例作为在某个 slot 被打包的概率 p,2 个 slot 内确认的概率为 1 - (1 - p)^2
+    // 最低优先费序列被缓存 PRIORITY_FEE_DISTRIBUTION_TTL,过期后在下一次调用时更新
+    pub async fn get_swap_confirmation_probability(
+        &self,
+        priority_fee_lamports_per_cu: u64,
+    ) -> anyhow::Result<f64> {
+        let distribution = self.get_priority_fee_distribution().await?;
+        if distribution.is_empty() {
+            return Ok(1.0);
+        }
+        let per_slot = distribution.partition_point(|fee| *fee <= priority_fee_lamports_per_cu)
+            as f64
+            / distribution.len() as f64;
+        Ok(1.0 - (1.0 - per_slot).powi(CONFIRMATION_TARGET_SLOTS))
+    }
+
+    // 最近 slot 的最低 compute unit price(每个 CU 的 micro-lamports),每个 slot 一个,升序排列
+    async fn get_priority_fee_distribution(&self) -> anyhow::Result<Vec<u64>> {
+        if let Some((updated, distribution)) =
+            self.priority_fee_distribution.read().unwrap().as_ref()
+        {
+            if updated.elapsed() < PRIORITY_FEE_DISTRIBUTION_TTL {
+                return Ok(distribution.clone());
+            }
+        }
+
+        let mut distribution = self
+            .client
+            .get_recent_prioritization_fees(&[])
+            .await?
+            .into_iter()
+            .map(|fee| fee.prioritization_fee)
+            .collect::<Vec<_>>();
+        distribution.sort_unstable();
+        *self.priority_fee_distribution.write().unwrap() =
+            Some((Instant::now(), distribution.clone()));
+        Ok(distribution)
+    }
+
+    // 衡量 owner 有多少资金处于占用状态: lp 仓位和 openbook 挂单中的资金占钱包总资产的比例
+    // solana 上交易不会长时间处于 pending 状态,in_pending_transactions_usd 统计的是 owner 在 openbook
+    // open orders 账户中的资金,包括挂单冻结的和已成交但尚未结算的代币
+    // sol 和 wsol 按 api 返回的 wsol 价格计价
+    pub async fn get_user_token_utilization(
+        &self,
+        owner: Pubkey,
+    ) -> anyhow::Result<UserTokenUtilization> {
+        let sol_price_usd = self
+            .api
+            .get_token_price(vec![spl_token::native_mint::ID.to_string()])
+            .await?
+            .get(&spl_token::native_mint::ID)
+            .copied()
+            .unwrap_or_default();
+        let (summary, in_pending_transactions_usd) = futures_util::try_join!(
+            self.get_all_user_positions_summary(owner, sol_price_usd),
+            self.get_open_orders_value_usd(owner, sol_price_usd),
+        )?;
+
+        let idle_usd = summary
+            .raw_token_balances
+            .iter()
+            .map(|balance| balance.value_usd)
+            .sum::<f64>();
+        let in_lp_positions_usd = summary.total_value_usd - idle_usd;
+        let total_balance_usd = summary.total_value_usd + in_pending_transactions_usd;
+        Ok(UserTokenUtilization {
+            total_balance_usd,
+            in_lp_positions_usd,
+            in_pending_transactions_usd,
+            idle_usd,
+            utilization_pct: if total_balance_usd > 0.0 {
+                (in_lp_positions_usd + in_pending_transactions_usd) / total_balance_usd * 100.0
+            } else {
+                0.0
+            },
+        })
+    }
+
+    // owner 所有 openbook open orders 账户中代币的美元价值
+    // open orders 账户布局: 5 字节填充, account_flags(8), market(32), owner(32), native_coin_free(8),
+    // native_coin_total(8), native_pc_free(8), native_pc_total(8), ...
+    // market 账户布局: 5 字节填充, account_flags(8), own_address(32), vault_signer_nonce(8), coin_mint(32), pc_mint(32), ...
+    async fn get_open_orders_value_usd(
+        &self,
+        owner: Pubkey,
+        sol_price_usd: f64,
+    ) -> anyhow::Result<f64> {
+        let accounts = self
+            .client
+            .get_program_accounts_with_config(
+                &OPENBOOK_PROGRAM_ID,
+                RpcProgramAccountsConfig {
+                    filters: Some(vec![
+                        RpcFilterType::DataSize(OPEN_ORDERS_SIZE as u64),
+                        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(45, owner.as_ref())),
+                    ]),
+                    account_config: RpcAccountInfoConfig {
+                        encoding: Some(UiAccountEncoding::Base64),
+                        data_slice: None,
+                        commitment: Some(CommitmentConfig::confirmed()),
+                        min_context_slot: None,
+                    },
+                    ..Default::default()
+                },
+            )
+            .await?;
+        if accounts.is_empty() {
+            return Ok(0.0);
+        }
+
+        let open_orders = accounts
+            .iter()
+            .map(|(_, account)| {
+                let data = &account.data;
+                (
+                    Pubkey::new_from_array(*array_ref![data, 13, 32]),
+                    u64::from_le_bytes(*array_ref![data, 85, 8]),
+                    u64::from_le_bytes(*array_ref![data, 101, 8]),
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut markets = open_orders
+            .iter()
+            .map(|(market, _, _)| *market)
+            .collect::<Vec<_>>();
+        markets.sort();
+        markets.dedup();
+        let market_accounts =
+            crate::utils::get_multiple_account_data(&self.client, &markets).await?;
+        let market_mints = markets
+            .into_iter()
+            .zip(market_accounts)
+            .filter_map(|(market, account)| {
+                let data = account?.data;
+                (data.len() >= 117).then(|| {
+                    (
+                        market,
+                        (
+                            Pubkey::new_from_array(*array_ref![data, 53, 32]),
+                            Pubkey::new_from_array(*array_ref![data, 85, 32]),
+                        ),
+                    )
+                })
+            })
+            .collect::<HashMap<_, _>>();
+
+        let mut amounts: HashMap<Pubkey, u64> = HashMap::new();
+        for (market, coin_total, pc_total) in open_orders {
+            let Some((coin_mint, pc_mint)) = market_mints.get(&market) else {
+                continue;
+            };
+            *amounts.entry(*coin_mint).or_default() += coin_total;
+            *amounts.entry(*pc_mint).or_default() += pc_total;
+        }
+        amounts.retain(|_, amount| *amount > 0);
+        if amounts.is_empty() {
+            return Ok(0.0);
+        }
+
+        let mints = amounts.keys().copied().collect::<Vec<_>>();
+        let mint_accounts = crate::utils::get_multiple_account_data(&self.client, &mints).await?;
+        let prices = self
+            .api
+            .get_token_price(mints.iter().map(Pubkey::to_string).collect())
+            .await?;
+        Ok(mints
+            .iter()
+            .zip(mint_accounts)
+            .filter_map(|(mint, account)| {
+                let decimals = spl_token::state::Mint::unpack(&account?.data)
+                    .ok()?
+                    .decimals;
+                let price = if *mint == spl_token::native_mint::ID {
+                    sol_price_usd
+                } else {
+                    prices.get(mint).copied().unwrap_or_default()
+                };
+                Some(crate::utils::to_ui_amount(amounts[mint], decimals as u64) * price)
+            })
+            .sum())
+    }
+
+    // 用指数移动平均衡量池子价格的短期动量: momentum = current / ema - 1
+    // 在最近 3 个 ema 周期内均匀采样 MOMENTUM_SAMPLES 个价格,按 alpha = 2 / (N + 1) 计算 ema,
+    // N 为一个 ema 周期内的采样点数,当前现货价格作为最后一个采样点
+    pub async fn get_price_momentum(
+        &self,
+        pool_id: Pubkey,
+        ema_period_slots: u64,
+    ) -> anyhow::Result<PriceMomentum> {
+        let current_price = self.fetch_pool_state(pool_id).await?.spot_price();
+        let current_slot = self.client.get_slot().await?;
+        let lookback_slots = ema_period_slots.saturating_mul(3);
+        let mut prices = self
+            .get_vault_history(
+                pool_id,
+                current_slot.saturating_sub(lookback_slots),
+                current_slot,
+                MOMENTUM_SAMPLES,
+            )
+            .await?
+            .into_iter()
+            .map(|sample| sample.implied_price)
+            .filter(|price| price.is_finite())
+            .collect::<Vec<_>>();
+        prices.push(current_price);
+
+        let slots_per_sample = (lookback_slots as f64 / (MOMENTUM_SAMPLES - 1) as f64).max(1.0);
+        let samples_per_period = ema_period_slots as f64 / slots_per_sample;
+        let ema =
+            super::math::exponential_moving_average(&prices, 2.0 / (samples_per_period + 1.0));
+        let momentum = current_price / ema - 1.0;
+        let trend = if momentum > MOMENTUM_TREND_THRESHOLD {
+            Trend::Bullish
+        } else if momentum < -MOMENTUM_TREND_THRESHOLD {
+            Trend::Bearish
+        } else {
+            Trend::Neutral
+        };
+
+        Ok(PriceMomentum {
+            current_price,
+            ema,
+            momentum,
+            trend,
+        })
+    }
+
+    // 计算价格到达 target_price(以 pc 表示的 coin 价格)之前池子可以买入或卖出的 coin 数量
+    // 恒定乘积 k = x * y 不变时,价格为 p 的储备量为 x = sqrt(k / p), y = sqrt(k * p)
+    // 目标价格高于现货价格时只能通过买入 coin 到达,sell_depth 为 0;低于现货价格时相反
+    // 手续费留在金库中,这里按扣除前的储备变化计算,买入深度为可买出的 coin 数量,卖出深度为需要卖入的 coin 数量
+    pub async fn get_pool_depth_at_price(
+        &self,
+        pool_id: Pubkey,
+        target_price: f64,
+    ) -> anyhow::Result<PoolDepth> {
+        if !(target_price.is_finite() && target_price > 0.0) {
+            return Err(anyhow!("Invalid target price {}", target_price));
+        }
+        let state = self.fetch_pool_state(pool_id).await?;
+        let (coin_price_usd, _) = self.fetch_pool_token_prices(&state).await?;
+
+        let coin_reserve = state.coin_vault_amount as f64;
+        let k = coin_reserve * state.pc_vault_amount as f64;
+        // 将以 ui 数量表示的价格换算为原始数量之比
+        let raw_target_price = target_price
+            * 10f64.powi(state.amm.pc_decimals as i32 - state.amm.coin_decimals as i32);
+        let target_coin_reserve = (k / raw_target_price).sqrt();
+        let (buy_depth_tokens, sell_depth_tokens) = if target_coin_reserve < coin_reserve {
+            ((coin_reserve - target_coin_reserve) as u64, 0)
+        } else {
+            (0, (target_coin_reserve - coin_reserve) as u64)
+        };
+
+        let to_usd = |amount: u64| {
+            crate::utils::to_ui_amount(amount, state.amm.coin_decimals) * coin_price_usd
+        };
+        Ok(PoolDepth {
+            buy_depth_tokens,
+            sell_depth_tokens,
+            buy_depth_usd: to_usd(buy_depth_tokens),
+            sell_depth_usd: to_usd(sell_depth_tokens),
+        })
+    }
+
+    // 在最近 lookback_slots 内均匀采样 PRICE_EXTREMES_SAMPLES 个价格,返回其中的最高价和最低价,用于判断支撑位和阻力位
+    // 当前价格取现货价格,current_vs_*_pct 为当前价格相对最高价和最低价的百分比变化
+    pub async fn get_recent_price_extremes(
+        &self,
+        pool_id: Pubkey,
+        lookback_slots: u64,
+    ) -> anyhow::Result<PriceExtremes> {
+        let current_price = self.fetch_pool_state(pool_id).await?.spot_price();
+        let current_slot = self.client.get_slot().await?;
+        let history = self
+            .get_vault_history(
+                pool_id,
+                current_slot.saturating_sub(lookback_slots),
+                current_slot,
+                PRICE_EXTREMES_SAMPLES,
+            )
+            .await?
+            .into_iter()
+            .filter(|sample| sample.implied_price.is_finite())
+            .collect::<Vec<_>>();
+        let high = history
+            .iter()
+            .max_by(|a, b| a.implied_price.total_cmp(&b.implied_price))
+            .with_context(|| format!("No price history for pool {}", pool_id))?;
+        let low = history
+            .iter()
+            .min_by(|a, b| a.implied_price.total_cmp(&b.implied_price))
+            .with_context(|| format!("No price history for pool {}", pool_id))?;
+
+        Ok(PriceExtremes {
+            high: high.implied_price,
+            low: low.implied_price,
+            high_slot: high.slot,
+            low_slot: low.slot,
+            current_vs_high_pct: (current_price / high.implied_price - 1.0) * 100.0,
+            current_vs_low_pct: (current_price / low.implied_price - 1.0) * 100.0,
+        })
+    }
+
+    // 根据金库余额相对最近 7 天中位数的水平给池子的健康度打分,用于监控面板
+    // 余额高于中位数时为 1.0,低于中位数的 VAULT_HEALTH_FLOOR(10%)时为 0.0,两者之间线性变化
+    // 历史余额来自 get_vault_history,每小时一个检查点,与当前余额一样是包含未提取 pnl 的金库原始余额
+    // overall 取两个金库中较低的分数
+    pub async fn get_vault_health_score(
+        &self,
+        pool_id: Pubkey,
+    ) -> anyhow::Result<VaultHealthScore> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        let current_slot = self.client.get_slot().await?;
+        let history = self
+            .get_vault_history(
+                pool_id,
+                current_slot.saturating_sub(SLOTS_PER_DAY * 7),
+                current_slot,
+                VAULT_HEALTH_SAMPLES,
+            )
+            .await?;
+        if history.is_empty() {
+            return Err(anyhow!("No vault history for pool {}", pool_id));
+        }
+
+        let health = |amount: u64, history: Vec<f64>| {
+            let median = super::math::median(&history);
+            if median <= 0.0 {
+                return 1.0;
+            }
+            let floor = median * VAULT_HEALTH_FLOOR;
+            ((amount as f64 - floor) / (median - floor)).clamp(0.0, 1.0)
+        };
+        let coin_health = health(
+            state.coin_vault_balance,
+            history
+                .iter()
+                .map(|sample| sample.coin_amount as f64)
+                .collect(),
+        );
+        let pc_health = health(
+            state.pc_vault_balance,
+            history
+                .iter()
+                .map(|sample| sample.pc_amount as f64)
+                .collect(),
+        );
+        Ok(VaultHealthScore {
+            coin_health,
+            pc_health,
+            overall: coin_health.min(pc_health),
+        })
+    }
+
+    // 池子价格的波动速度,单位为每秒的 bps
+    // 在最近 VOLATILITY_LOOKBACK_SLOTS 内均匀采样 VOLATILITY_HISTORY_SAMPLES 个价格,
+    // 取相邻采样点对数收益率绝对值的均值,再除以两个采样点之间的秒数
+    // 与 auto_select_slippage_bps 不同,这里使用历史价格,不需要等待实时采样
+    pub async fn get_pool_volatility(&self, pool_id: Pubkey) -> anyhow::Result<f64> {
+        let current_slot = self.client.get_slot().await?;
+        let (history, slot_time) = futures_util::try_join!(
+            self.get_vault_history(
+                pool_id,
+                current_slot.saturating_sub(VOLATILITY_LOOKBACK_SLOTS),
+                current_slot,
+                VOLATILITY_HISTORY_SAMPLES,
+            ),
+            self.get_mean_slot_time(),
+        )?;
+        let prices = history
+            .into_iter()
+            .map(|sample| sample.implied_price)
+            .filter(|price| price.is_finite() && *price > 0.0)
+            .collect::<Vec<_>>();
+        if prices.len() < 2 {
+            return Err(anyhow!("not enough price samples for pool {}", pool_id));
+        }
+
+        let returns = super::math::log_returns(&prices);
+        let mean_abs_return = returns.iter().map(|r| r.abs()).sum::<f64>() / returns.len() as f64;
+        let secs_per_sample = slot_time.as_secs_f64() * VOLATILITY_LOOKBACK_SLOTS as f64
+            / (VOLATILITY_HISTORY_SAMPLES - 1) as f64;
+        Ok(mean_abs_return * 10_000.0 / secs_per_sample)
+    }
+
+    // 考虑从报价到上链之间价格变动的报价:
+    // 在用户的 slippage_bps 上加上 get_pool_volatility 得到的每秒波动 bps × 预计延迟秒数
+    pub async fn get_slippage_adjusted_quote(
+        &self,
+        swap_input: &SwapInput,
+        expected_latency_ms: u64,
+    ) -> anyhow::Result<RaydiumAmmQuote> {
+        let quote = self.quote(swap_input).await?;
+        let volatility_bps_per_second = self.get_pool_volatility(quote.market).await?;
+        let latency_seconds = expected_latency_ms as f64 / 1000.0;
+        let adjustment_bps = (volatility_bps_per_second * latency_seconds).ceil() as u64;
+        if adjustment_bps == 0 {
+            return Ok(quote);
+        }
+
+        let slippage_bps = (swap_input.slippage_bps as u64 + adjustment_bps).min(10_000) as u16;
+        log::debug!(
+            "pool {} volatility: {:.4} bps/s, slippage adjusted from {} to {} bps",
+            quote.market,
+            volatility_bps_per_second,
+            swap_input.slippage_bps,
+            slippage_bps
+        );
+        // 固定使用第一次报价选出的池子
+        self.quote(&SwapInput {
+            slippage_bps,
+            market: Some(quote.market),
+            ..*swap_input
+        })
+        .await
+    }
+
+    // 构建带限价的交换交易,limit_price 为按精度换算后以 pc 计的 coin 价格
+    // 不额外添加价格检查指令,而是收紧 other_amount_threshold,由 amm 程序自身的滑点检查保证限价:
+    // 卖出 coin 时成交均价(pc / coin)不低于 limit_price,买入 coin 时成交均价不高于 limit_price,
+    // 否则交易在执行时失败。成交均价包含手续费和价格影响,比现货价格更差,因此限价比现货价格更严格
+    pub async fn build_limit_price_swap(
+        &self,
+        owner: Pubkey,
+        mut quote: RaydiumAmmQuote,
+        limit_price: f64,
+        overrides: Option<&SwapConfigOverrides>,
+    ) -> anyhow::Result<VersionedTransaction> {
+        if !(limit_price.is_finite() && limit_price > 0.0) {
+            return Err(anyhow!("Invalid limit price {}", limit_price));
+        }
+        let sells_coin = quote.input_mint == quote.amm_keys.amm_coin_mint;
+
+        let (coin_decimals, pc_decimals) = if sells_coin {
+            (quote.input_mint_decimals, quote.output_mint_decimals)
+        } else {
+            (quote.output_mint_decimals, quote.input_mint_decimals)
+        };
+        let ui = |amount: u64, decimals: u8| crate::utils::to_ui_amount(amount, decimals as u64);
+        let raw = |amount: f64, decimals: u8| amount * 10f64.powi(decimals as i32);
+        let threshold = quote.other_amount_threshold;
+        quote.other_amount_threshold = match (sells_coin, quote.amount_specified_is_input) {
+            // 最少收到的 pc
+            (true, true) => threshold
+                .max(raw(ui(quote.amount, coin_decimals) * limit_price, pc_decimals).ceil() as u64),
+            // 最多付出的 coin
+            (true, false) => threshold.min(
+                raw(ui(quote.amount, pc_decimals) / limit_price, coin_decimals).floor() as u64,
+            ),
+            // 最少收到的 coin
+            (false, true) => threshold
+                .max(raw(ui(quote.amount, pc_decimals) / limit_price, coin_decimals).ceil() as u64),
+            // 最多付出的 pc
+            (false, false) => threshold.min(
+                raw(ui(quote.amount, coin_decimals) * limit_price, pc_decimals).floor() as u64,
+            ),
+        };
+        let limit_met = if quote.amount_specified_is_input {
+            quote.other_amount >= quote.other_amount_threshold
+        } else {
+            quote.other_amount <= quote.other_amount_threshold
+        };
+        if !limit_met {
+            log::warn!(
+                "The limit price {} is not met by the current quote, the swap fails unless the price moves",
+                limit_price
+            );
+        }
+
+        self.swap_transaction(owner, quote, overrides).await
+    }
+
+    // 读取池子两个金库代币账户的 owner 和 delegate,用于合规和审计日志
+    // 金库应只由 amm authority 控制,设置了 delegate 意味着第三方可以转走金库中的代币,记录安全警告
+    pub async fn get_vault_account_metadata(
+        &self,
+        pool_id: Pubkey,
+    ) -> anyhow::Result<VaultMetadata> {
+        let (amm_keys, _) = self.load_pool_keys(pool_id).await?;
+        let vaults = [amm_keys.amm_coin_vault, amm_keys.amm_pc_vault];
+        let accounts = crate::utils::get_multiple_account_data(&self.client, &vaults).await?;
+        let mut token_accounts = Vec::with_capacity(vaults.len());
+        for (vault, account) in vaults.iter().zip(accounts) {
+            let account = account.with_context(|| format!("Vault {} not found", vault))?;
+            let token_account = spl_token::state::Account::unpack(&account.data)?;
+            if let COption::Some(delegate) = token_account.delegate {
+                log::warn!(
+                    "Vault {} of pool {} has delegate {} approved for {}",
+                    vault,
+                    pool_id,
+                    delegate,
+                    token_account.delegated_amount
+                );
+            }
+            token_accounts.push(token_account);
+        }
+
+        Ok(VaultMetadata {
+            coin_vault_pubkey: amm_keys.amm_coin_vault,
+            pc_vault_pubkey: amm_keys.amm_pc_vault,
+            coin_vault_owner: token_accounts[0].owner,
+            pc_vault_owner: token_accounts[1].owner,
+            coin_vault_delegate: token_accounts[0].delegate.into(),
+            pc_vault_delegate: token_accounts[1].delegate.into(),
+        })
+    }
+
+    // 在路由图中寻找从 start_mint 出发、经过 2 或 3 个池子回到 start_mint 的套利路径
+    // 输入数量为 budget_usd 按 api 价格换算的 start_mint 数量,沿路径逐跳用当前储备计算扣除手续费后的输出,
+    // 返回收益超过 min_profit_bps 的路径中收益最高的一条
+    // 候选路径只保留最浅池子 tvl 最高的 MAX_ARBITRAGE_CYCLES 条,以限制需要读取的池子数量
+    pub async fn find_cross_pool_arbitrage(
+        &self,
+        start_mint: Pubkey,
+        budget_usd: f64,
+        min_profit_bps: u64,
+    ) -> anyhow::Result<Option<ArbitragePath>> {
+        let graph = self.get_routing_graph(ARBITRAGE_GRAPH_POOLS).await?;
+        let mut cycles = graph.cycles(start_mint);
+        if cycles.is_empty() {
+            return Ok(None);
+        }
+        let weakest_tvl =
+            |cycle: &[&RoutingEdge]| cycle.iter().map(|edge| edge.tvl).fold(f64::MAX, f64::min);
+        cycles.sort_by(|a, b| weakest_tvl(b).total_cmp(&weakest_tvl(a)));
+        cycles.truncate(MAX_ARBITRAGE_CYCLES);
+
+        let mut pool_ids = cycles
+            .iter()
+            .flatten()
+            .map(|edge| edge.pool_id)
+            .collect::<Vec<_>>();
+        pool_ids.sort();
+        pool_ids.dedup();
+        let states = futures_util::stream::iter(pool_ids)
+            .map(|pool_id| async move { (pool_id, self.fetch_pool_state(pool_id).await) })
+            .buffered(8)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .filter_map(|(pool_id, state)| match state {
+                Ok(state) => Some((pool_id, state)),
+                Err(e) => {
+                    log::debug!("skipping pool {}: {}", pool_id, e);
+                    None
+                }
+            })
+            .collect::<HashMap<_, _>>();
+
+        let start_price_usd = self
+            .api
+            .get_token_price(vec![start_mint.to_string()])
+            .await?
+            .get(&start_mint)
+            .copied()
+            .with_context(|| format!("No price available for {}", start_mint))?;
+        let start_decimals = states
+            .values()
+            .find_map(|state| {
+                if state.amm_keys.amm_coin_mint == start_mint {
+                    Some(state.amm.coin_decimals)
+                } else if state.amm_keys.amm_pc_mint == start_mint {
+                    Some(state.amm.pc_decimals)
+                } else {
+                    None
+                }
+            })
+            .with_context(|| format!("No pool state loaded for {}", start_mint))?;
+        let required_input =
+            (budget_usd / start_price_usd * 10f64.powi(start_decimals as i32)) as u64;
+        if required_input == 0 {
+            return Ok(None);
+        }
+
+        let mut best: Option<ArbitragePath> = None;
+        'cycles: for cycle in cycles {
+            let mut mint = start_mint;
+            let mut amount = required_input;
+            let mut hops = Vec::with_capacity(cycle.len());
+            for edge in cycle {
+                let Some(state) = states.get(&edge.pool_id) else {
+                    continue 'cycles;
+                };
+                let Ok(direction) = state.direction(&mint, &edge.to) else {
+                    continue 'cycles;
+                };
+                let Ok(output) = state.swap_output(direction, amount) else {
+                    continue 'cycles;
+                };
+                hops.push((edge.pool_id, direction));
+                mint = edge.to;
+                amount = output;
+            }
+            if amount <= required_input {
+                continue;
+            }
+            let expected_profit_bps =
+                (amount - required_input) as u128 * 10_000 / required_input as u128;
+            let expected_profit_bps = u64::try_from(expected_profit_bps).unwrap_or(u64::MAX);
+            if expected_profit_bps > min_profit_bps
+                && best
+                    .as_ref()
+                    .map_or(true, |best| expected_profit_bps > best.expected_profit_bps)
+            {
+                best = Some(ArbitragePath {
+                    hops,
+                    expected_profit_bps,
+                    required_input,
+                });
+            }
+        }
+        Ok(best)
+    }
+
+    // 汇总 owner 自 since_slot 以来在给定池子上的所有兑换,计算已实现盈亏
+    // 通过 getSignaturesForAddress 列出 owner 的交易签名(最多 MAX_POOL_HISTORY_TRANSACTIONS 个),
+    // 逐笔解析其对各个池子金库的影响,只统计 fee payer 为 owner 的兑换
+    // 流入金库的数量是 owner 的输入,流出的是 owner 的输出,手续费按池子费率从输入中扣除
+    // 每笔兑换的 coin 按该笔交易后金库余额得到的池子价格折算为 pc,即按成交所在 slot 的价格计价;
+    // api 只提供当前价格,pc 再按当前价格换算为美元
+    pub async fn get_user_realized_pnl(
+        &self,
+        owner: Pubkey,
+        pools: &[Pubkey],
+        since_slot: u64,
+    ) -> anyhow::Result<RealizedPnl> {
+        let states = futures_util::future::try_join_all(
+            pools.iter().map(|pool_id| self.fetch_pool_state(*pool_id)),
+        )
+        .await?;
+        let prices = futures_util::future::try_join_all(
+            states
+                .iter()
+                .map(|state| self.fetch_pool_token_prices(state)),
+        )
+        .await?;
+        let signatures = self.get_pool_signatures(owner, since_slot, None).await?;
+
+        let transactions = futures_util::stream::iter(signatures)
+            .map(|signature| async move {
+                let tx = self
+                    .client
+                    .get_transaction_with_config(
+                        &signature,
+                        RpcTransactionConfig {
+                            encoding: Some(UiTransactionEncoding::Base64),
+                            commitment: Some(CommitmentConfig::confirmed()),
+                            max_supported_transaction_version: Some(0),
+                        },
+                    )
+                    .await?;
+                Ok::<_, anyhow::Error>((signature, tx))
+            })
+            .buffered(8)
+            .collect::<Vec<_>>()
+            .await;
+
+        let mut pnl = RealizedPnl::default();
+        for tx in transactions {
+            let (signature, tx) = tx?;
+            for (state, (_, pc_price_usd)) in states.iter().zip(&prices) {
+                let Some(pool_tx) = history::parse_pool_transaction(
+                    signature,
+                    &tx,
+                    &state.amm_keys.amm_coin_vault,
+                    &state.amm_keys.amm_pc_vault,
+                    &state.amm_keys.amm_lp_mint,
+                ) else {
+                    continue;
+                };
+                if pool_tx.wallet != owner || pool_tx.coin_vault_after == 0 {
+                    continue;
+                }
+                let coin_price_in_pc =
+                    crate::utils::to_ui_amount(pool_tx.pc_vault_after, state.amm.pc_decimals)
+                        / crate::utils::to_ui_amount(
+                            pool_tx.coin_vault_after,
+                            state.amm.coin_decimals,
+                        );
+                let coin_usd = crate::utils::to_ui_amount(
+                    pool_tx.coin_delta.unsigned_abs(),
+                    state.amm.coin_decimals,
+                ) * coin_price_in_pc
+                    * pc_price_usd;
+                let pc_usd = crate::utils::to_ui_amount(
+                    pool_tx.pc_delta.unsigned_abs(),
+                    state.amm.pc_decimals,
+                ) * pc_price_usd;
+                let (input_usd, output_usd) = match pool_tx.kind() {
+                    PoolTransactionKind::Swap(SwapDirection::Coin2PC) => (coin_usd, pc_usd),
+                    PoolTransactionKind::Swap(SwapDirection::PC2Coin) => (pc_usd, coin_usd),
+                    _ => continue,
+                };
+                let fee_rate = state.amm.fees.swap_fee_numerator as f64
+                    / state.amm.fees.swap_fee_denominator as f64;
+                pnl.total_input_usd += input_usd;
+                pnl.total_output_usd += output_usd;
+                pnl.total_fees_paid_usd += input_usd * fee_rate;
+            }
+        }
+        pnl.net_pnl_usd = pnl.total_output_usd - pnl.total_input_usd;
+        Ok(pnl)
+    }
+
+    // 按恒定乘积池的份额计算 lp_amount 个 lp 代币的公允价值: 份额 = lp_amount / lp 总量,
+    // 可赎回的 coin 和 pc 为份额乘以扣除待提取 pnl 后的金库数量,再按 api 价格计价
+    // lp 代币的二级市场价格取 api 的代币价格,api 没有该 lp mint 的价格时 secondary_market_premium_pct 为 None
+    pub async fn get_lp_token_fair_value(
+        &self,
+        pool_id: Pubkey,
+        lp_amount: u64,
+    ) -> anyhow::Result<LpTokenFairValue> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        if state.amm.lp_amount == 0 {
+            return Err(anyhow!("Pool {} has no lp supply", pool_id));
+        }
+        let lp_mint = state.amm_keys.amm_lp_mint;
+        let ((coin_price_usd, pc_price_usd), lp_prices) = futures_util::try_join!(
+            self.fetch_pool_token_prices(&state),
+            self.api.get_token_price(vec![lp_mint.to_string()]),
+        )?;
+
+        let share_of = |vault_amount: u64| {
+            (vault_amount as u128 * lp_amount as u128 / state.amm.lp_amount as u128) as u64
+        };
+        let coin_entitlement = share_of(state.coin_vault_amount);
+        let pc_entitlement = share_of(state.pc_vault_amount);
+        let fair_value_usd = crate::utils::to_ui_amount(coin_entitlement, state.amm.coin_decimals)
+            * coin_price_usd
+            + crate::utils::to_ui_amount(pc_entitlement, state.amm.pc_decimals) * pc_price_usd;
+        // lp mint 的精度与 coin 相同
+        let market_value_usd = lp_prices
+            .get(&lp_mint)
+            .map(|price| crate::utils::to_ui_amount(lp_amount, state.amm.coin_decimals) * price);
+
+        Ok(LpTokenFairValue {
+            coin_entitlement,
+            pc_entitlement,
+            fair_value_usd,
+            secondary_market_premium_pct: market_value_usd
+                .filter(|_| fair_value_usd > 0.0)
+                .map(|market_value_usd| (market_value_usd / fair_value_usd - 1.0) * 100.0),
+        })
+    }
+
+    // 统计池子 lp 代币的持有情况,衡量 lp 的投入程度
+    // 持有人数量通过 getProgramAccounts 扫描 spl-token 程序中该 lp mint 的代币账户得到,只取余额字段,余额为 0 的账户不计入
+    // 前 10 大持有人的占比使用 getTokenLargestAccounts,不需要对全部持有人排序
+    pub async fn get_pool_open_interest(&self, pool_id: Pubkey) -> anyhow::Result<OpenInterest> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        let lp_mint = state.amm_keys.amm_lp_mint;
+        let (token_accounts, largest_accounts) = futures_util::try_join!(
+            self.client.get_program_accounts_with_config(
+                &spl_token::ID,
+                RpcProgramAccountsConfig {
+                    filters: Some(vec![
+                        RpcFilterType::DataSize(spl_token::state::Account::LEN as u64),
+                        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, lp_mint.as_ref())),
+                    ]),
+                    account_config: RpcAccountInfoConfig {
+                        encoding: Some(UiAccountEncoding::Base64),
+                        // only the amount is needed
+                        data_slice: Some(UiDataSliceConfig {
+                            offset: 64,
+                            length: 8,
+                        }),
+                        commitment: Some(CommitmentConfig::confirmed()),
+                        min_context_slot: None,
+                    },
+                    ..Default::default()
+                },
+            ),
+            self.client.get_token_largest_accounts(&lp_mint),
+        )?;
+
+        let num_lp_holders = token_accounts
+            .iter()
+            .filter(|(_, account)| {
+                account.data.len() == 8 && u64::from_le_bytes(*array_ref![account.data, 0, 8]) > 0
+            })
+            .count() as u64;
+        let top10_amount = largest_accounts
+            .iter()
+            .take(10)
+            .filter_map(|account| account.amount.amount.parse::<u64>().ok())
+            .map(|amount| amount as u128)
+            .sum::<u128>();
+        let top10_holders_share_bps = if state.amm.lp_amount == 0 {
+            0
+        } else {
+            (top10_amount * 10_000 / state.amm.lp_amount as u128).min(10_000) as u64
+        };
+
+        Ok(OpenInterest {
+            total_lp_tokens: state.amm.lp_amount,
+            num_lp_holders,
+            top10_holders_share_bps,
+        })
+    }
+
+    // 池子价格偏离 fair_value_price(以 pc 表示的 coin 价格)时,套利者会把价格拉回公允价格,lp 从这笔交易中赚取手续费
+    // 恒定乘积池中价格从 p 变为 p_f 时,输入侧储备变为原来的 sqrt(r) 倍(r = max(p / p_f, p_f / p)),
+    // 输入侧储备约占 tvl 的一半,因此套利交易的手续费占 tvl 的 pool_fee_bps * (sqrt(r) - 1) / 2
+    // 同时价格变化 r 倍会给 lp 带来无常损失;偏离不超过手续费时套利无利可图,建议 Hold,
+    // 否则手续费收入高于无常损失时建议 AddLiquidity,反之 RemoveLiquidity
+    pub async fn get_pool_rebalancing_opportunity(
+        &self,
+        pool_id: Pubkey,
+        fair_value_price: f64,
+    ) -> anyhow::Result<RebalancingOpportunity> {
+        if !(fair_value_price.is_finite() && fair_value_price > 0.0) {
+            return Err(anyhow!("Invalid fair value price {}", fair_value_price));
+        }
+        let state = self.fetch_pool_state(pool_id).await?;
+        let current_pool_price = state.spot_price();
+        if !(current_pool_price.is_finite() && current_pool_price > 0.0) {
+            return Err(anyhow!("pool {} has an empty vault", pool_id));
+        }
+
+        let divergence_bps =
+            ((current_pool_price / fair_value_price - 1.0) * 10_000.0).round() as i64;
+        let price_ratio =
+            (current_pool_price / fair_value_price).max(fair_value_price / current_pool_price);
+        let pool_fee_bps = state.pool_fee_bps();
+        let expected_rebalancing_fee_bps = pool_fee_bps as f64 * (price_ratio.sqrt() - 1.0) / 2.0;
+        let impermanent_loss_bps = -super::math::compute_impermanent_loss(price_ratio) * 10_000.0;
+        let recommended_action = if divergence_bps.unsigned_abs() <= pool_fee_bps {
+            LpAction::Hold
+        } else if expected_rebalancing_fee_bps > impermanent_loss_bps {
+            LpAction::AddLiquidity
+        } else {
+            LpAction::RemoveLiquidity
+        };
+
+        Ok(RebalancingOpportunity {
+            current_pool_price,
+            fair_value_price,
+            divergence_bps,
+            expected_rebalancing_fee_bps,
+            recommended_action,
+        })
+    }
+
+    // 在一笔交易中依次执行多个兑换,例如 SOL→USDC 后再 USDC→BONK,不需要多跳路由
+    // 每一项为 (输入代币账户, 报价),输出统一发送到 owner 对应输出 mint 的 ATA
+    // owner 取已存在的输入代币账户的 owner,所有输入账户必须属于同一个 owner;
+    // 尚不存在的输入账户必须是前面某个兑换输出的 ATA,或本项兑换包装 SOL 时创建的 wsol ATA
+    // 每个兑换依次包含与 make_swap 相同的账户创建和 SOL 包装指令、兑换指令以及 wsol 关闭指令,
+    // 因此输入为 SOL 的兑换必须从 owner 的 wsol ATA 转出,输出为 SOL 的兑换结束后即解包为 SOL
+    pub async fn batch_swap_instructions(
+        &self,
+        swaps: Vec<(Pubkey, RaydiumAmmQuote)>,
+    ) -> anyhow::Result<Vec<Instruction>> {
+        let sources = swaps.iter().map(|(source, _)| *source).collect::<Vec<_>>();
+        let source_accounts =
+            crate::utils::get_multiple_account_data(&self.client, &sources).await?;
+        let mut owner = None;
+        for (source, account) in sources.iter().zip(&source_accounts) {
+            let Some(account) = account else {
+                continue;
+            };
+            let source_owner = spl_token::state::Account::unpack(&account.data)
+                .with_context(|| format!("{} is not a token account", source))?
+                .owner;
+            match owner {
+                None => owner = Some(source_owner),
+                Some(owner) if owner != source_owner => {
+                    return Err(anyhow!(
+                        "Source accounts are owned by both {} and {}",
+                        owner,
+                        source_owner
+                    ))
+                }
+                Some(_) => {}
+            }
+        }
+        let Some(owner) = owner else {
+            return Err(anyhow!("None of the source token accounts exist"));
+        };
+
+        let wrap_and_unwrap_sol = self.config.wrap_and_unwrap_sol.unwrap_or(true);
+        let wsol_ata = spl_associated_token_account::get_associated_token_address(
+            &owner,
+            &spl_token::native_mint::ID,
+        );
+        let mut instructions = Vec::new();
+        let mut created_atas = Vec::new();
+        for ((source, quote), account) in swaps.iter().zip(&source_accounts) {
+            self.ensure_swap_allowed(quote).await?;
+            let wraps_input = quote.input_mint == spl_token::native_mint::ID;
+            if wraps_input && wrap_and_unwrap_sol && *source != wsol_ata {
+                return Err(anyhow!(
+                    "Sol is wrapped into {}, not into the source account {}",
+                    wsol_ata,
+                    source
+                ));
+            }
+            if wraps_input {
+                created_atas.push(wsol_ata);
+            }
+            if account.is_none() && !created_atas.contains(source) {
+                return Err(anyhow!(
+                    "Source account {} does not exist and is not created by an earlier swap",
+                    source
+                ));
+            }
+            let destination = spl_associated_token_account::get_associated_token_address(
+                &owner,
+                &quote.output_mint,
+            );
+            created_atas.push(destination);
+
+            let mut builder = self.swap_accounts_builder(owner, quote, None)?;
+            builder.swap_instruction = Some(swap_instruction(
+                &RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID,
+                &quote.amm_keys,
+                &quote.market_keys,
+                &owner,
+                source,
+                &destination,
+                quote.amount,
+                quote.other_amount_threshold,
+                quote.amount_specified_is_input,
+            )?);
+            instructions.extend(builder.build_instructions()?);
+        }
+        Ok(instructions)
+    }
+
+    // 兑换前检查 owner 的输入和输出 ATA 是否存在,返回创建缺失 ATA 所需的免租金最低余额之和,都存在时返回 0
+    // wsol 的 ATA 会在兑换结束时关闭并退还租金,但执行时仍需要先垫付,因此同样计入
+    pub async fn get_account_rent_needed(
+        &self,
+        owner: Pubkey,
+        input_mint: Pubkey,
+        output_mint: Pubkey,
+    ) -> anyhow::Result<u64> {
+        let mut atas = vec![
+            spl_associated_token_account::get_associated_token_address(&owner, &input_mint),
+            spl_associated_token_account::get_associated_token_address(&owner, &output_mint),
+        ];
+        atas.dedup();
+        let missing = crate::utils::get_multiple_account_data(&self.client, &atas)
+            .await?
+            .iter()
+            .filter(|account| account.is_none())
+            .count() as u64;
+        if missing == 0 {
+            return Ok(0);
+        }
+        let rent = self
+            .client
+            .get_minimum_balance_for_rent_exemption(spl_token::state::Account::LEN)
+            .await?;
+        Ok(missing * rent)
+    }
+
+    // 查询交易的确认深度,用于跟踪交易何时最终确认
+    // 通过 getSignatureStatuses(searchTransactionHistory)获取交易所在的 slot 和确认状态,
+    // 确认数为当前 slot(processed)减去交易所在的 slot,交易尚未达到 confirmed 时为 None
+    pub async fn get_transaction_confirmation_depth(
+        &self,
+        signature: Signature,
+    ) -> anyhow::Result<ConfirmationDepth> {
+        let (statuses, current_slot) = futures_util::try_join!(
+            self.client
+                .get_signature_statuses_with_history(&[signature]),
+            self.client
+                .get_slot_with_commitment(CommitmentConfig::processed()),
+        )?;
+        let status = statuses.value.into_iter().next().flatten();
+
+        let (confirmed_slot, is_finalized) = match &status {
+            Some(status) => match status.confirmation_status {
+                Some(TransactionConfirmationStatus::Finalized) => (Some(status.slot), true),
+                Some(TransactionConfirmationStatus::Confirmed) => (Some(status.slot), false),
+                // 没有 confirmation_status 的旧节点用 confirmations 为 None 表示已最终确认
+                None if status.confirmations.is_none() => (Some(status.slot), true),
+                _ => (None, false),
+            },
+            None => (None, false),
+        };
+        if let Some(err) = status.as_ref().and_then(|status| status.err.as_ref()) {
+            log::warn!("transaction {} failed: {}", signature, err);
+        }
+
+        Ok(ConfirmationDepth {
+            confirmed_slot,
+            finalized_slot: confirmed_slot.filter(|_| is_finalized),
+            current_slot,
+            confirmations: confirmed_slot.map(|slot| current_slot.saturating_sub(slot)),
+            is_finalized,
+        })
+    }
+
+    // 查询池子是否被紧急暂停,供风控系统使用
+    // amm v4 没有单独的管理员状态账户,暂停通过管理员修改 AmmInfo 的 status 实现,
+    // 因此 status 为 Uninitialized、Disabled 或无效值时视为暂停
+    // 暂停后池子无法交易,halt_slot 取池子账户最近一笔成功交易所在的 slot,即修改状态的管理员交易
+    pub async fn get_pool_emergency_halt_status(
+        &self,
+        pool_id: Pubkey,
+    ) -> anyhow::Result<EmergencyHaltStatus> {
+        let account = self.client.get_account(&pool_id).await?;
+        let status = AmmPoolStatus::from(decode_amm_info(&account.data)?.status);
+        let halt_reason = match status {
+            AmmPoolStatus::Uninitialized => Some("pool is not initialized".to_string()),
+            AmmPoolStatus::Disabled => Some("pool is disabled by the admin".to_string()),
+            AmmPoolStatus::Invalid(status) => {
+                Some(format!("pool has an invalid status {}", status))
+            }
+            _ => None,
+        };
+        if halt_reason.is_none() {
+            return Ok(EmergencyHaltStatus {
+                is_halted: false,
+                halt_reason: None,
+                halt_slot: None,
+            });
+        }
+
+        let halt_slot = self
+            .get_last_successful_slot(&pool_id, STATUS_CHANGE_LOOKBACK_TRANSACTIONS)
+            .await?;
+        Ok(EmergencyHaltStatus {
+            is_halted: true,
+            halt_reason,
+            halt_slot,
+        })
+    }
+
+    // 将池子的现货价格与多个来源的参考价格(来源名称, 价格)比较,计算 amm 价格相对参考价格分布的 Z 分数
+    // 标准差使用样本标准差,至少需要两个参考价格;|Z| 超过 ANOMALY_Z_SCORE_THRESHOLD 时视为异常
+    // 参考价格完全一致时标准差为 0,amm 价格与其不同即视为无穷大的偏离
+    pub async fn get_price_feed_anomaly_score(
+        &self,
+        pool_id: Pubkey,
+        reference_prices: Vec<(String, f64)>,
+    ) -> anyhow::Result<AnomalyScore> {
+        let prices = reference_prices
+            .iter()
+            .filter(|(_, price)| price.is_finite())
+            .map(|(_, price)| *price)
+            .collect::<Vec<_>>();
+        if prices.len() < 2 {
+            return Err(anyhow!(
+                "At least two reference prices are required, got {}",
+                prices.len()
+            ));
+        }
+        let amm_price = self.fetch_pool_state(pool_id).await?.spot_price();
+
+        let reference_mean = prices.iter().sum::<f64>() / prices.len() as f64;
+        let std_dev = (prices
+            .iter()
+            .map(|price| (price - reference_mean).powi(2))
+            .sum::<f64>()
+            / (prices.len() - 1) as f64)
+            .sqrt();
+        let deviation = amm_price - reference_mean;
+        let z_score = if std_dev > 0.0 {
+            deviation / std_dev
+        } else if deviation == 0.0 {
+            0.0
+        } else {
+            deviation.signum() * f64::INFINITY
+        };
+        log::debug!(
+            "pool {} price {} vs references {:?}: z-score {:.2}",
+            pool_id,
+            amm_price,
+            reference_prices,
+            z_score
+        );
+
+        Ok(AnomalyScore {
+            z_score,
+            is_anomalous: z_score.abs() > ANOMALY_Z_SCORE_THRESHOLD,
+            amm_price,
+            reference_mean,
+        })
+    }
+
+    // 衡量代币在池子中的周转速度: velocity = 回看期内的兑换量 / 回看期内金库的平均余额
+    // 兑换量为每笔兑换中金库余额变化的绝对值之和,平均余额取每笔兑换后的金库余额的均值,
+    // 回看期内没有兑换时两者都为 0
+    pub async fn get_token_velocity(
+        &self,
+        pool_id: Pubkey,
+        lookback_slots: u64,
+    ) -> anyhow::Result<TokenVelocity> {
+        let swaps = self
+            .get_trade_history(pool_id, lookback_slots)
+            .await?
+            .into_iter()
+            .filter(|tx| matches!(tx.kind(), PoolTransactionKind::Swap(_)))
+            .collect::<Vec<_>>();
+        if swaps.is_empty() {
+            return Ok(TokenVelocity {
+                coin_velocity: 0.0,
+                pc_velocity: 0.0,
+            });
+        }
+
+        let velocity = |volume: u128, vault_total: u128| {
+            let avg_vault_amount = vault_total as f64 / swaps.len() as f64;
+            if avg_vault_amount > 0.0 {
+                volume as f64 / avg_vault_amount
+            } else {
+                0.0
+            }
+        };
+        let coin_volume = swaps
+            .iter()
+            .map(|tx| tx.coin_delta.unsigned_abs() as u128)
+            .sum::<u128>();
+        let pc_volume = swaps
+            .iter()
+            .map(|tx| tx.pc_delta.unsigned_abs() as u128)
+            .sum::<u128>();
+        let coin_vault_total = swaps
+            .iter()
+            .map(|tx| tx.coin_vault_after as u128)
+            .sum::<u128>();
+        let pc_vault_total = swaps
+            .iter()
+            .map(|tx| tx.pc_vault_after as u128)
+            .sum::<u128>();
+        Ok(TokenVelocity {
+            coin_velocity: velocity(coin_volume, coin_vault_total),
+            pc_velocity: velocity(pc_volume, pc_vault_total),
+        })
+    }
+
+    // 检查两个金库的价值是否均衡,任意一侧超过总价值的 70% 即视为失衡
+    // 金库价值使用 api 的 usd 价格计算: 用池子自身的价格计算时恒定乘积池两侧的价值总是相等
+    pub async fn get_pool_token_concentration(
+        &self,
+        pool_id: Pubkey,
+    ) -> anyhow::Result<TokenConcentration> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        let (coin_price_usd, pc_price_usd) = self.fetch_pool_token_prices(&state).await?;
+        if coin_price_usd <= 0.0 || pc_price_usd <= 0.0 {
+            return Err(anyhow!("Failed to get usd prices for pool {}", pool_id));
+        }
+        let coin_value_usd =
+            crate::utils::to_ui_amount(state.coin_vault_amount, state.amm.coin_decimals)
+                * coin_price_usd;
+        let pc_value_usd =
+            crate::utils::to_ui_amount(state.pc_vault_amount, state.amm.pc_decimals) * pc_price_usd;
+        let total_value_usd = coin_value_usd + pc_value_usd;
+        if total_value_usd <= 0.0 {
+            return Err(anyhow!("Pool {} holds no liquidity", pool_id));
+        }
+
+        let coin_value_pct = coin_value_usd / total_value_usd * 100.0;
+        let pc_value_pct = pc_value_usd / total_value_usd * 100.0;
+        let dominant_token = if coin_value_pct > TOKEN_CONCENTRATION_THRESHOLD_PCT {
+            Some(state.amm_keys.amm_coin_mint)
+        } else if pc_value_pct > TOKEN_CONCENTRATION_THRESHOLD_PCT {
+            Some(state.amm_keys.amm_pc_mint)
+        } else {
+            None
+        };
+        Ok(TokenConcentration {
+            coin_value_pct,
+            pc_value_pct,
+            is_imbalanced: dominant_token.is_some(),
+            dominant_token,
+        })
+    }
+
+    // 评估从池子中闪电借出 borrow_amount 个 borrow_mint 代币的可行性
+    // amm v4 没有闪电贷指令,也不在交易结束时检查恒定乘积,借出代币的唯一方式是一笔 exact-out 兑换:
+    // 借出的代币由输出金库支付,归还的是另一侧的代币,数量为 exact-out 兑换所需的输入(包含手续费)
+    // 输出金库不能被完全取空,因此最多可借出金库余额减 1
+    pub async fn get_flash_swap_feasibility(
+        &self,
+        pool_id: Pubkey,
+        borrow_amount: u64,
+        borrow_mint: Pubkey,
+    ) -> anyhow::Result<FlashSwapFeasibility> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        let direction = if borrow_mint == state.amm_keys.amm_pc_mint {
+            SwapDirection::Coin2PC
+        } else if borrow_mint == state.amm_keys.amm_coin_mint {
+            SwapDirection::PC2Coin
+        } else {
+            return Err(anyhow!(
+                "Pool {} does not hold mint {}",
+                pool_id,
+                borrow_mint
+            ));
+        };
+
+        let max_borrowable = state.output_vault_amount(direction).saturating_sub(1);
+        let is_feasible = borrow_amount > 0 && borrow_amount <= max_borrowable;
+        let required_repayment = if is_feasible {
+            state.swap_input(direction, borrow_amount)?
+        } else {
+            0
+        };
+        Ok(FlashSwapFeasibility {
+            is_feasible,
+            max_borrowable,
+            required_repayment,
+            fee_bps: state.pool_fee_bps(),
+        })
+    }
+
+    // 寻找每单位输出成本最低的交易数量
+    // 执行成本 = 交易手续费(固定成本) + 相对无滑点兑换少得的输出(包括池子手续费和价格影响,随数量增加)的美元价值,
+    // 每单位成本为执行成本除以输出数量: 数量小时固定成本占主导,数量大时价格影响占主导,
+    // 两者之和是单峰函数,在输入侧储备的 10% 以内三分查找最小值
+    pub async fn get_trade_size_recommendation(
+        &self,
+        pool_id: Pubkey,
+        input_mint: Pubkey,
+        output_mint: Pubkey,
+        sol_price_usd: f64,
+    ) -> anyhow::Result<TradeSizeRecommendation> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        let direction = state.direction(&input_mint, &output_mint)?;
+        let (coin_price_usd, pc_price_usd) = self.fetch_pool_token_prices(&state).await?;
+        let (output_price, output_decimals) = match direction {
+            SwapDirection::Coin2PC => (pc_price_usd, state.amm.pc_decimals),
+            SwapDirection::PC2Coin => (coin_price_usd, state.amm.coin_decimals),
+        };
+        if output_price <= 0.0 {
+            return Err(anyhow!("Failed to get usd price for {}", output_mint));
+        }
+        let fee_usd =
+            crate::utils::to_ui_amount(self.expected_transaction_fee_lamports(), 9) * sol_price_usd;
+        let (input_vault, output_vault) = (
+            state.input_vault_amount(direction),
+            state.output_vault_amount(direction),
+        );
+        // 返回 (每单位输出成本, 执行成本)
+        let cost = |amount_in: u64| -> anyhow::Result<(f64, f64)> {
+            let amount_out = state.swap_output(direction, amount_in)?;
+            if amount_out == 0 {
+                return Ok((f64::INFINITY, fee_usd));
+            }
+            let ideal_out = amount_in as f64 * output_vault as f64 / input_vault as f64;
+            let shortfall_usd = crate::utils::to_ui_amount(1, output_decimals)
+                * (ideal_out - amount_out as f64)
+                * output_price;
+            let total_usd = fee_usd + shortfall_usd;
+            Ok((
+                total_usd / crate::utils::to_ui_amount(amount_out, output_decimals),
+                total_usd,
+            ))
+        };
+
+        let (mut low, mut high) = (1, input_vault / 10);
+        if high < low {
+            return Err(anyhow!("Pool {} has no liquidity", pool_id));
+        }
+        while high - low > 2 {
+            let third = (high - low) / 3;
+            let (m1, m2) = (low + third, high - third);
+            if cost(m1)?.0 < cost(m2)?.0 {
+                high = m2;
+            } else {
+                low = m1;
+            }
+        }
+        let mut best = (low, cost(low)?);
+        for amount_in in low + 1..=high {
+            let candidate = cost(amount_in)?;
+            if candidate.0 < best.1 .0 {
+                best = (amount_in, candidate);
+            }
+        }
+        let (optimal_amount, (cost_per_unit_usd, total_execution_cost_usd)) = best;
+        Ok(TradeSizeRecommendation {
+            optimal_amount,
+            cost_per_unit_usd,
+            total_execution_cost_usd,
+        })
+    }
+
+    // 估算回看期内池子收取的协议手续费
+    // 兑换手续费按 swap_fee 从输入代币中收取,其中 trade_fee 部分再按 pnl_numerator / pnl_denominator
+    // 的比例作为 pnl 归协议所有,其余留在池子中归 lp,因此按每笔兑换的输入数量分别统计 coin 与 pc 两侧
+    pub async fn get_protocol_fee_revenue(
+        &self,
+        pool_id: Pubkey,
+        lookback_slots: u64,
+    ) -> anyhow::Result<ProtocolFeeRevenue> {
+        let (state, trades) = futures_util::try_join!(
+            self.fetch_pool_state(pool_id),
+            self.get_trade_history(pool_id, lookback_slots),
+        )?;
+        let (mut coin_volume, mut pc_volume) = (0u128, 0u128);
+        for tx in &trades {
+            match tx.kind() {
+                PoolTransactionKind::Swap(SwapDirection::Coin2PC) => {
+                    coin_volume += tx.coin_delta.unsigned_abs() as u128;
+                }
+                PoolTransactionKind::Swap(SwapDirection::PC2Coin) => {
+                    pc_volume += tx.pc_delta.unsigned_abs() as u128;
+                }
+                _ => {}
+            }
+        }
+
+        let fees = &state.amm.fees;
+        let protocol_fee = |volume: u128| {
+            (volume * fees.trade_fee_numerator as u128 * fees.pnl_numerator as u128)
+                .checked_div(fees.trade_fee_denominator as u128 * fees.pnl_denominator as u128)
+                .unwrap_or_default() as u64
+        };
+        // 协议手续费占全部兑换手续费的比例只取决于费率参数
+        let protocol_fee_rate = fees.trade_fee_numerator as f64 / fees.trade_fee_denominator as f64
+            * fees.pnl_numerator as f64
+            / fees.pnl_denominator as f64;
+        let swap_fee_rate = fees.swap_fee_numerator as f64 / fees.swap_fee_denominator as f64;
+        Ok(ProtocolFeeRevenue {
+            protocol_fee_coin: protocol_fee(coin_volume),
+            protocol_fee_pc: protocol_fee(pc_volume),
+            protocol_fee_share_of_total_pct: if swap_fee_rate > 0.0 && protocol_fee_rate.is_finite()
+            {
+                protocol_fee_rate / swap_fee_rate * 100.0
+            } else {
+                0.0
+            },
+        })
+    }
+
+    // 比较 raydium 与其他交易场所对同一笔 exact-in 兑换的输出
+    // 每个场所由对应的 feature 开启: orca-comparison 对应 Orca Whirlpool,见 get_orca_quote;
+    // openbook-comparison 对应 Openbook(serum 的延续),见 get_openbook_quote
+    // 未开启任何 feature 时只返回 raydium 的报价。单个场所报价失败时跳过该场所
+    // raydium_advantage_bps 为 raydium 输出相对最好的竞争对手输出的差距,没有竞争对手报价时为 0
+    pub async fn get_competitor_pool_advantage(
+        &self,
+        input_mint: Pubkey,
+        output_mint: Pubkey,
+        amount: u64,
+    ) -> anyhow::Result<CompetitorComparison> {
+        let raydium_quote = self
+            .quote(&SwapInput {
+                input_token_mint: input_mint,
+                output_token_mint: output_mint,
+                slippage_bps: 0,
+                amount,
+                mode: SwapExecutionMode::ExactIn,
+                market: None,
+            })
+            .await?;
+        let raydium_output = raydium_quote.other_amount;
+        let competitor_outputs = self.get_competitor_outputs(&raydium_quote).await;
+
+        let best_competitor = competitor_outputs.iter().max_by_key(|(_, output)| **output);
+        let (best_venue, raydium_advantage_bps) = match best_competitor {
+            Some((venue, &output)) if output > 0 => (
+                if output > raydium_output {
+                    venue.clone()
+                } else {
+                    "Raydium".to_string()
+                },
+                ((raydium_output as f64 / output as f64 - 1.0) * 10_000.0).round() as i64,
+            ),
+            _ => ("Raydium".to_string(), 0),
+        };
+        Ok(CompetitorComparison {
+            raydium_output,
+            competitor_outputs,
+            best_venue,
+            raydium_advantage_bps,
+        })
+    }
+
+    // 并发获取开启的竞争对手场所对 raydium_quote 这笔兑换的输出,报价失败的场所不包含在结果中
+    #[cfg(any(feature = "orca-comparison", feature = "openbook-comparison"))]
+    async fn get_competitor_outputs(
+        &self,
+        raydium_quote: &RaydiumAmmQuote,
+    ) -> HashMap<String, u64> {
+        use futures_util::FutureExt;
+
+        let (venues, quotes): (Vec<_>, Vec<_>) = [
+            #[cfg(feature = "orca-comparison")]
+            ("Orca", self.get_orca_quote(raydium_quote).boxed()),
+            #[cfg(feature = "openbook-comparison")]
+            ("Openbook", self.get_openbook_quote(raydium_quote).boxed()),
+        ]
+        .into_iter()
+        .unzip();
+        let quotes = futures_util::future::join_all(quotes).await;
+        venues
+            .into_iter()
+            .zip(quotes)
+            .filter_map(|(venue, quote)| {
+                quote
+                    .map(|output| (venue.to_string(), output))
+                    .map_err(|e| log::debug!("{} quote failed: {}", venue, e))
+                    .ok()
+            })
+            .collect()
+    }
+
+    #[cfg(not(any(feature = "orca-comparison", feature = "openbook-comparison")))]
+    async fn get_competitor_outputs(
+        &self,
+        _raydium_quote: &RaydiumAmmQuote,
+    ) -> HashMap<String, u64> {
+        HashMap::new()
+    }
+
+    // 通过 Orca 的 whirlpool 列表接口找到该交易对 tvl 最高的 whirlpool,再读取链上的 whirlpool 账户计算输出
+    // 接口地址可以通过 RaydiumAmmExecutorOpts::orca_api_url 配置
+    // 只在当前 tick 区间内按恒定流动性计算,不跨越 tick,因此大额兑换的输出会被高估
+    #[cfg(feature = "orca-comparison")]
+    async fn get_orca_quote(&self, raydium_quote: &RaydiumAmmQuote) -> anyhow::Result<u64> {
+        #[derive(serde::Deserialize)]
+        struct WhirlpoolList {
+            whirlpools: Vec<WhirlpoolInfo>,
+        }
+        #[derive(serde::Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct WhirlpoolInfo {
+            address: String,
+            token_a: WhirlpoolToken,
+            token_b: WhirlpoolToken,
+            #[serde(default)]
+            tvl: Option<f64>,
+        }
+        #[derive(serde::Deserialize)]
+        struct WhirlpoolToken {
+            mint: String,
+        }
+
+        let input_mint = raydium_quote.input_mint.to_string();
+        let output_mint = raydium_quote.output_mint.to_string();
+        let list: WhirlpoolList = self
+            .http
+            .get(format!(
+                "{}/whirlpool/list",
+                self.orca_api_url.trim_end_matches('/')
+            ))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let whirlpool = list
+            .whirlpools
+            .into_iter()
+            .filter(|pool| {
+                (pool.token_a.mint == input_mint && pool.token_b.mint == output_mint)
+                    || (pool.token_a.mint == output_mint && pool.token_b.mint == input_mint)
+            })
+            .max_by(|a, b| {
+                a.tvl
+                    .unwrap_or_default()
+                    .total_cmp(&b.tvl.unwrap_or_default())
+            })
+            .with_context(|| format!("No whirlpool found for {} -> {}", input_mint, output_mint))?;
+
+        let address = Pubkey::from_str(&whirlpool.address)?;
+        let account = self.client.get_account(&address).await?;
+        // whirlpool 账户: 8 字节 discriminator、whirlpools_config、bump、tick_spacing、tick_spacing_seed 之后
+        // 依次为 fee_rate(u16)、protocol_fee_rate(u16)、liquidity(u128)、sqrt_price(u128)、tick_current_index(i32)、
+        // 两个 protocol_fee_owed(u64) 和 token_mint_a
+        let data = &account.data;
+        if account.owner != WHIRLPOOL_PROGRAM_ID || data.len() < 133 {
+            return Err(anyhow!("{} is not a whirlpool", address));
+        }
+        let fee_rate = u16::from_le_bytes(*array_ref![data, 45, 2]);
+        let liquidity = u128::from_le_bytes(*array_ref![data, 49, 16]);
+        let sqrt_price_x64 = u128::from_le_bytes(*array_ref![data, 65, 16]);
+        let mint_a = Pubkey::new_from_array(*array_ref![data, 101, 32]);
+        Ok(whirlpool_output(
+            liquidity,
+            sqrt_price_x64,
+            fee_rate,
+            raydium_quote.input_mint == mint_a,
+            raydium_quote.amount,
+        ))
+    }
+
+    // 在池子所用的 openbook 市场的订单簿上模拟 exact-in 的吃单: 卖出 coin 时从最高买价依次成交,
+    // 买入 coin 时从最低卖价依次成交,只成交整数个 lot;输入先扣除 OPENBOOK_TAKER_FEE_BPS 的吃单手续费
+    // 市场、买单和卖单账户来自 raydium 报价的池子 keys,通过一次 get_multiple_accounts 请求获取
+    #[cfg(feature = "openbook-comparison")]
+    async fn get_openbook_quote(&self, raydium_quote: &RaydiumAmmQuote) -> anyhow::Result<u64> {
+        let market = raydium_quote.amm_keys.market;
+        let accounts = crate::utils::get_multiple_account_data(
+            &self.client,
+            &[
+                market,
+                raydium_quote.market_keys.bids,
+                raydium_quote.market_keys.asks,
+            ],
+        )
+        .await?;
+        let [Some(market_account), Some(bids), Some(asks)] = accounts.as_slice() else {
+            return Err(anyhow!("Openbook market {} not found", market));
+        };
+        // 市场账户: 5 字节的 "serum" 头之后,coin_mint 位于 53,coin_lot_size 和 pc_lot_size 位于 349 和 357
+        let data = &market_account.data;
+        if data.len() < 365 {
+            return Err(anyhow!("{} is not an openbook market", market));
+        }
+        let coin_mint = Pubkey::new_from_array(*array_ref![data, 53, 32]);
+        let coin_lot_size = u64::from_le_bytes(*array_ref![data, 349, 8]) as u128;
+        let pc_lot_size = u64::from_le_bytes(*array_ref![data, 357, 8]) as u128;
+        if coin_lot_size == 0 || pc_lot_size == 0 {
+            return Err(anyhow!("Openbook market {} has no lot size", market));
+        }
+
+        let sells_coin = raydium_quote.input_mint == coin_mint;
+        let mut orders = if sells_coin {
+            openbook_orders(&bids.data)
+        } else {
+            openbook_orders(&asks.data)
+        };
+        if sells_coin {
+            orders.sort_unstable_by(|a, b| b.0.cmp(&a.0));
+        } else {
+            orders.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        }
+        let mut remaining =
+            raydium_quote.amount as u128 * (10_000 - OPENBOOK_TAKER_FEE_BPS) as u128 / 10_000;
+        let mut output = 0u128;
+        for (price_lots, quantity_lots) in orders {
+            // 每个 coin lot 的 pc 数量
+            let lot_price = price_lots as u128 * pc_lot_size;
+            let lots = if sells_coin {
+                remaining / coin_lot_size
+            } else {
+                remaining.checked_div(lot_price).unwrap_or_default()
+            }
+            .min(quantity_lots as u128);
+            if lots == 0 {
+                break;
+            }
+            if sells_coin {
+                remaining -= lots * coin_lot_size;
+                output += lots * lot_price;
+            } else {
+                remaining -= lots * lot_price;
+                output += lots * coin_lot_size;
+            }
+        }
+        Ok(u64::try_from(output).unwrap_or(u64::MAX))
+    }
+
+    // 找出回看期内单个钱包使 lp 总量变化超过 min_lp_change_pct 的加减流动性交易,用于监控大户动向
+    // 与 get_position_delta 相同,从当前的 lp 总量减去回看期内的净铸造量得到回看期开始时的 lp 总量,
+    // 再正向遍历交易,变化比例相对交易前的 lp 总量计算; 交易前 lp 总量为 0 时(池子初始化)视为 100%
+    pub async fn get_liquidity_event_alerts(
+        &self,
+        pool_id: Pubkey,
+        min_lp_change_pct: f64,
+        lookback_slots: u64,
+    ) -> anyhow::Result<Vec<LiquidityEvent>> {
+        let (state, transactions) = futures_util::try_join!(
+            self.fetch_pool_state(pool_id),
+            self.get_trade_history(pool_id, lookback_slots),
+        )?;
+        let minted_since = transactions.iter().map(|tx| tx.lp_delta).sum::<i64>();
+        let mut supply = (state.amm.lp_amount as i64 - minted_since).max(0) as u64;
+
+        let mut events = Vec::new();
+        for tx in &transactions {
+            let event_type = match tx.kind() {
+                PoolTransactionKind::AddLiquidity => LiquidityEventType::Add,
+                PoolTransactionKind::RemoveLiquidity => LiquidityEventType::Remove,
+                _ => continue,
+            };
+            let lp_tokens_delta = tx.lp_delta.unsigned_abs();
+            let pct_change = if supply > 0 {
+                lp_tokens_delta as f64 / supply as f64 * 100.0
+            } else {
+                100.0
+            };
+            supply = (supply as i64 + tx.lp_delta).max(0) as u64;
+            if pct_change > min_lp_change_pct {
+                events.push(LiquidityEvent {
+                    event_type,
+                    lp_tokens_delta,
+                    pct_change,
+                    wallet: tx.wallet,
+                    slot: tx.slot,
+                });
+            }
+        }
+        Ok(events)
+    }
+
+    // 并排比较多个池子对同一笔 exact-in 兑换的执行情况,按输出数量从多到少排序,rank 从 1 开始
+    // 所有池子交易同一对代币,只需请求一次两种代币的 api 价格来计算 tvl
+    pub async fn get_pool_comparison_table(
+        &self,
+        pool_ids: Vec<Pubkey>,
+        amount: u64,
+        input_mint: Pubkey,
+        output_mint: Pubkey,
+    ) -> anyhow::Result<PoolComparisonTable> {
+        let (states, prices) = futures_util::try_join!(
+            futures_util::future::try_join_all(
+                pool_ids
+                    .iter()
+                    .map(|pool_id| self.fetch_pool_state(*pool_id))
+            ),
+            self.api
+                .get_token_price(vec![input_mint.to_string(), output_mint.to_string()]),
+        )?;
+        let price_of = |mint: &Pubkey| prices.get(mint).copied().unwrap_or_default();
+
+        let mut rows = states
+            .iter()
+            .map(|state| {
+                let direction = state.direction(&input_mint, &output_mint)?;
+                Ok(PoolComparisonRow {
+                    pool_id: state.pool_id,
+                    output_amount: state.swap_output(direction, amount)?,
+                    price_impact_bps: state.price_impact_bps(direction, amount)?,
+                    fee_bps: state.pool_fee_bps(),
+                    tvl_usd: state.tvl_usd(
+                        price_of(&state.amm_keys.amm_coin_mint),
+                        price_of(&state.amm_keys.amm_pc_mint),
+                    ),
+                    rank: 0,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        rows.sort_by(|a, b| b.output_amount.cmp(&a.output_amount));
+        for (i, row) in rows.iter_mut().enumerate() {
+            row.rank = i + 1;
+        }
+        Ok(PoolComparisonTable { rows })
+    }
+
+    // 根据最近 MICROSTRUCTURE_LOOKBACK_SLOTS 内的兑换计算市场微观结构指标,价格均为 pc/coin(已按精度换算)
+    // get_pool_event_log 的兑换事件只记录输入数量,无法得到成交价格和兑换前后的价格,因此使用 get_trade_history,
+    // 由金库余额变化得到每笔兑换的成交价格以及兑换前后的池子价格:
+    // - effective_spread_bps: 成交价格相对兑换前价格偏离的两倍的平均值
+    // - kyle_lambda: 兑换前后的价格变化对带符号成交量(pc,买入 coin 为正)线性回归的斜率
+    // - amihud_illiquidity: 每笔兑换价格对数收益率的绝对值除以成交量(pc)的平均值
+    // - roll_spread_estimate: 2 * sqrt(-cov(Δp_t, Δp_t-1)),Δp 为相邻成交价格之差,协方差非负时为 0
+    pub async fn get_market_microstructure(
+        &self,
+        pool_id: Pubkey,
+    ) -> anyhow::Result<MarketMicrostructure> {
+        let (state, trades) = futures_util::try_join!(
+            self.fetch_pool_state(pool_id),
+            self.get_trade_history(pool_id, MICROSTRUCTURE_LOOKBACK_SLOTS),
+        )?;
+        let (coin_decimals, pc_decimals) = (state.amm.coin_decimals, state.amm.pc_decimals);
+        let price = |coin: u64, pc: u64| {
+            crate::utils::to_ui_amount(pc, pc_decimals)
+                / crate::utils::to_ui_amount(coin, coin_decimals)
+        };
+
+        let (mut spreads_bps, mut signed_volumes, mut price_changes) = (vec![], vec![], vec![]);
+        let (mut illiquidity, mut execution_prices) = (vec![], vec![]);
+        for tx in &trades {
+            let PoolTransactionKind::Swap(direction) = tx.kind() else {
+                continue;
+            };
+            let coin_before = (tx.coin_vault_after as i64 - tx.coin_delta) as u64;
+            let pc_before = (tx.pc_vault_after as i64 - tx.pc_delta) as u64;
+            let price_before = price(coin_before, pc_before);
+            let price_after = price(tx.coin_vault_after, tx.pc_vault_after);
+            let execution_price = price(tx.coin_delta.unsigned_abs(), tx.pc_delta.unsigned_abs());
+            let volume = crate::utils::to_ui_amount(tx.pc_delta.unsigned_abs(), pc_decimals);
+            if !(price_before.is_finite() && price_after.is_finite() && execution_price.is_finite())
+                || price_before <= 0.0
+                || volume <= 0.0
+            {
+                continue;
+            }
+
+            spreads_bps.push(2.0 * (execution_price / price_before - 1.0).abs() * 10_000.0);
+            signed_volumes.push(match direction {
+                SwapDirection::PC2Coin => volume,
+                SwapDirection::Coin2PC => -volume,
+            });
+            price_changes.push(price_after - price_before);
+            illiquidity.push((price_after / price_before).ln().abs() / volume);
+            execution_prices.push(execution_price);
+        }
+        if spreads_bps.is_empty() {
+            return Err(anyhow!("No recent swaps in pool {}", pool_id));
+        }
+
+        let mean = |values: &[f64]| values.iter().sum::<f64>() / values.len() as f64;
+        let trade_price_changes = execution_prices
+            .windows(2)
+            .map(|pair| pair[1] - pair[0])
+            .collect::<Vec<_>>();
+        let serial_covariance = if trade_price_changes.len() > 2 {
+            super::math::covariance(
+                &trade_price_changes[1..],
+                &trade_price_changes[..trade_price_changes.len() - 1],
+            )
+        } else {
+            0.0
+        };
+        Ok(MarketMicrostructure {
+            effective_spread_bps: mean(&spreads_bps).round() as u64,
+            kyle_lambda: super::math::linear_regression_slope(&signed_volumes, &price_changes),
+            amihud_illiquidity: mean(&illiquidity),
+            roll_spread_estimate: if serial_covariance < 0.0 {
+                2.0 * (-serial_covariance).sqrt()
+            } else {
+                0.0
+            },
+        })
+    }
+
+    // 按凯利公式计算仓位大小: f = (p * b - q * a) / (a * b),b 和 a 为盈利和亏损时的收益率,q = 1 - p
+    // 开仓和平仓各需要在池子中兑换一次,盈利和亏损幅度都按两次池子手续费调整; 没有正期望时 f 为 0
+    // 建议仓位为半凯利,并且与最大仓位一样不超过总资金的 MAX_KELLY_POSITION_FRACTION
+    pub async fn get_position_sizing_recommendation(
+        &self,
+        pool_id: Pubkey,
+        win_probability: f64,
+        win_size_bps: f64,
+        loss_size_bps: f64,
+        total_capital_usd: f64,
+    ) -> anyhow::Result<PositionSizing> {
+        if !(0.0..=1.0).contains(&win_probability) {
+            return Err(anyhow!(
+                "Win probability must be between 0 and 1, got {}",
+                win_probability
+            ));
+        }
+        let state = self.fetch_pool_state(pool_id).await?;
+        let round_trip_fee_bps = 2.0 * state.pool_fee_bps() as f64;
+        let win = (win_size_bps - round_trip_fee_bps) / 10_000.0;
+        let loss = (loss_size_bps + round_trip_fee_bps) / 10_000.0;
+
+        let kelly_fraction = if win > 0.0 && loss > 0.0 {
+            ((win_probability * win - (1.0 - win_probability) * loss) / (win * loss)).max(0.0)
+        } else {
+            0.0
+        };
+        let max_position_usd = kelly_fraction.min(MAX_KELLY_POSITION_FRACTION) * total_capital_usd;
+        Ok(PositionSizing {
+            kelly_fraction,
+            recommended_position_usd: (kelly_fraction / 2.0 * total_capital_usd)
+                .min(max_position_usd),
+            max_position_usd,
+        })
+    }
+
+    // 综合链上信号计算代币的风险评分,0 表示安全,100 表示危险
+    // 各项指标先归一化到 [0, 1](越大越危险),再按 TOKEN_RISK_SCORE_WEIGHTS 加权:
+    // 可以增发记为 1,最大持有者占总供应量的比例,包含该代币的 amm v4 池子 tvl 之和按数量级计 100 万美元以上记为 0,
+    // 最早的池子开放交易的天数 30 天以上记为 0; 没有池子时流动性和池龄都记为 1
+    // 最大持有者可能是池子的金库,此时占比反映的是池子深度而不是持仓集中度
+    pub async fn get_token_risk_score(&self, mint: Pubkey) -> anyhow::Result<TokenRiskScore> {
+        let (mint_authority, pools) = futures_util::try_join!(
+            self.get_token_mint_authority(mint),
+            self.get_adjacent_pools(mint),
+        )?;
+        let (largest_accounts, supply) = futures_util::try_join!(
+            self.client.get_token_largest_accounts(&mint),
+            self.client.get_token_supply(&mint),
+        )?;
+        let supply = supply.amount.parse::<u64>()?;
+        let largest_holder = largest_accounts
+            .first()
+            .and_then(|account| account.amount.amount.parse::<u64>().ok())
+            .unwrap_or_default();
+        let top1_holder_pct = if supply == 0 {
+            0.0
+        } else {
+            largest_holder as f64 / supply as f64 * 100.0
+        };
+
+        let liquidity_depth_usd = pools.iter().map(|pool| pool.tvl).sum::<f64>();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let pool_age_days = pools
+            .iter()
+            .filter_map(|pool| pool.open_time.parse::<u64>().ok())
+            .min()
+            .map(|open_time| now.saturating_sub(open_time) as f64 / 86_400.0)
+            .unwrap_or_default();
+
+        let component_scores = [
+            if mint_authority.is_mintable { 1.0 } else { 0.0 },
+            (top1_holder_pct / 100.0).min(1.0),
+            1.0 - (liquidity_depth_usd.max(1.0).log10() / 6.0).min(1.0),
+            1.0 - (pool_age_days / 30.0).min(1.0),
+        ];
+        let risk_score = component_scores
+            .iter()
+            .zip(TOKEN_RISK_SCORE_WEIGHTS)
+            .map(|(component, weight)| component * weight)
+            .sum::<f64>()
+            * 100.0;
+
+        Ok(TokenRiskScore {
+            is_mintable: mint_authority.is_mintable,
+            top1_holder_pct,
+            liquidity_depth_usd,
+            pool_age_days,
+            component_scores,
+            weights: TOKEN_RISK_SCORE_WEIGHTS,
+            risk_score,
+        })
+    }
+
+    // 返回池子在给定兑换方向上的恒定乘积曲线,x 为输入侧储备,y 为输出侧储备,
+    // 供需要光滑滑点函数的优化算法离线计算任意数量的滑点
+    pub async fn get_slippage_curve(
+        &self,
+        pool_id: Pubkey,
+        input_mint: Pubkey,
+        output_mint: Pubkey,
+    ) -> anyhow::Result<SlippageCurve> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        let direction = state.direction(&input_mint, &output_mint)?;
+        let x = state.input_vault_amount(direction) as f64;
+        let y = state.output_vault_amount(direction) as f64;
+        Ok(SlippageCurve { k: x * y, x, y })
+    }
+
+    // 检查池子金库余额是否长时间没有更新: 超过 VAULT_STALENESS_SLOTS 个 slot 没有交易时金库余额可能已经偏离市场
+    // 最后一笔交易为 get_pool_transaction_at_slot 找到的最后一笔改变金库余额的交易(兑换或加减流动性),
+    // 找不到时 last_trade_slot 为 None,slots_since_last_trade 按当前 slot 计算
+    pub async fn get_vault_staleness(&self, pool_id: Pubkey) -> anyhow::Result<VaultStaleness> {
+        let current_slot = self.client.get_slot().await?;
+        let last_trade_slot = self
+            .get_pool_transaction_at_slot(pool_id, current_slot)
+            .await?
+            .map(|tx| tx.slot);
+        let slots_since_last_trade = current_slot.saturating_sub(last_trade_slot.unwrap_or(0));
+        Ok(VaultStaleness {
+            last_trade_slot,
+            slots_since_last_trade,
+            is_stale: slots_since_last_trade > VAULT_STALENESS_SLOTS,
+        })
+    }
+
+    // 通过 jito block engine 的 getBundleStatuses 接口查询 bundle 的状态
+    // 本 crate 没有 swap_jito,bundle 由调用方使用 PriorityFeeConfig::JitoTip 构建的交易自行提交
+    // getBundleStatuses 只返回已经上链的 bundle: 没有记录时为 Pending,
+    // err 为 {"Ok": null} 时为 Landed,否则 bundle 中有交易失败,为 Failed
+    // 上链后 confirmation_level 为 bundle 所在区块的确认级别(processed / confirmed / finalized),无法识别的级别为 None
+    pub async fn get_jito_bundle_status(
+        &self,
+        bundle_id: &str,
+        jito_block_engine_url: &str,
+    ) -> anyhow::Result<JitoBundleStatus> {
+        #[derive(serde::Deserialize)]
+        struct RpcResponse {
+            result: Option<RpcResult>,
+            error: Option<serde_json::Value>,
+        }
+        #[derive(serde::Deserialize)]
+        struct RpcResult {
+            value: Vec<Option<BundleStatus>>,
+        }
+        #[derive(serde::Deserialize)]
+        struct BundleStatus {
+            transactions: Vec<String>,
+            slot: u64,
+            confirmation_status: String,
+            err: serde_json::Value,
+        }
+
+        let url = format!(
+            "{}/api/v1/bundles",
+            jito_block_engine_url.trim_end_matches('/')
+        );
+        let response: RpcResponse = self
+            .http
+            .post(url)
+            .json(&serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "getBundleStatuses",
+                "params": [[bundle_id]],
+            }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if let Some(error) = response.error {
+            return Err(anyhow!("getBundleStatuses failed: {}", error));
+        }
+
+        let bundle = response
+            .result
+            .context("getBundleStatuses returned no result")?
+            .value
+            .into_iter()
+            .next()
+            .flatten();
+        let Some(bundle) = bundle else {
+            return Ok(JitoBundleStatus {
+                status: BundleStatusEnum::Pending,
+                slot: None,
+                confirmation_level: None,
+                transactions: vec![],
+            });
+        };
+        let status = if bundle.err.get("Ok").is_some() {
+            BundleStatusEnum::Landed
+        } else {
+            BundleStatusEnum::Failed
+        };
+        let confirmation_level = match bundle.confirmation_status.as_str() {
+            "processed" => Some(TransactionConfirmationStatus::Processed),
+            "confirmed" => Some(TransactionConfirmationStatus::Confirmed),
+            "finalized" => Some(TransactionConfirmationStatus::Finalized),
+            _ => None,
+        };
+        Ok(JitoBundleStatus {
+            status,
+            slot: Some(bundle.slot),
+            confirmation_level,
+            transactions: bundle
+                .transactions
+                .iter()
+                .map(|signature| Signature::from_str(signature))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    // 从池子的 SetParams(AmmParams::Fees) 管理指令中还原兑换手续费的修改记录,按时间从旧到新排列
+    // 与 get_pool_event_log 相同,只能覆盖最近 MAX_POOL_HISTORY_TRANSACTIONS 笔池子交易,
+    // 更早的修改需要从更早的签名开始扫描; 没有修改记录时返回空列表,当前手续费可以从池子状态读取
+    pub async fn get_pool_fee_history(
+        &self,
+        pool_id: Pubkey,
+    ) -> anyhow::Result<Vec<FeeHistoryEntry>> {
+        let current_slot = self.client.get_slot().await?;
+        let events = self.get_pool_event_log(pool_id, 0, current_slot).await?;
+        Ok(events
+            .into_iter()
+            .filter_map(|event| match event {
+                PoolEvent::FeeChange {
+                    swap_fee_numerator,
+                    swap_fee_denominator,
+                    slot,
+                } => Some(FeeHistoryEntry {
+                    slot,
+                    fee_numerator: swap_fee_numerator,
+                    fee_denominator: swap_fee_denominator,
+                }),
+                _ => None,
+            })
+            .collect())
+    }
+
+    // 估算手续费上下调整 5 bps 对池子年化手续费收益的影响,见 math::compute_fee_sensitivity
+    // 成交量对手续费的弹性由 get_pool_event_log 中的 FeeChange 事件估计: 相邻两次手续费修改之间(最后一段到当前 slot)
+    // 手续费不变,按该段的 swap 输入量(coin 按当前现货价格折算为 pc)得到每天的成交量,再对 ln(成交量) 和 ln(手续费) 做回归;
+    // 事件日志只覆盖最近 MAX_POOL_HISTORY_TRANSACTIONS 笔交易,其中少于两个不同的手续费时弹性取 0,即假设成交量不随手续费变化
+    // 当前的日成交量取事件日志覆盖的全部 swap,tvl 和成交量都按 api 的美元价格计价
+    // swap 事件只包含该池子的 amm 指令输出的 ray_log(见 history::parse_pool_events),
+    // 经由多个池子路由的交易中其他池子的成交量不会混入各段的成交量和日成交量
+    pub async fn get_fee_sensitivity(
+        &self,
+        pool_id: Pubkey,
+    ) -> anyhow::Result<super::math::FeeSensitivity> {
+        let current_slot = self.client.get_slot().await?;
+        let (state, events) = futures_util::try_join!(
+            self.fetch_pool_state(pool_id),
+            self.get_pool_event_log(pool_id, 0, current_slot),
+        )?;
+        let (coin_price_usd, pc_price_usd) = self.fetch_pool_token_prices(&state).await?;
+        let (coin_decimals, pc_decimals) = (state.amm.coin_decimals, state.amm.pc_decimals);
+        let spot_price = state.spot_price();
+        let days = |from_slot: u64, to_slot: u64| {
+            to_slot.saturating_sub(from_slot).max(1) as f64 / SLOTS_PER_DAY as f64
+        };
+
+        // 当前这一段的 (手续费 bps, 开始的 slot, 以 pc 计的成交量)
+        let mut period: Option<(u64, u64, f64)> = None;
+        let mut fee_periods = Vec::new();
+        let (mut coin_volume, mut pc_volume) = (0u64, 0u64);
+        let mut first_swap_slot = None;
+        for event in &events {
+            match *event {
+                PoolEvent::FeeChange {
+                    swap_fee_numerator,
+                    swap_fee_denominator,
+                    slot,
+                } => {
+                    if let Some((fee_bps, from_slot, volume)) = period.take() {
+                        fee_periods.push((fee_bps, volume / days(from_slot, slot)));
+                    }
+                    let fee_bps = swap_fee_numerator
+                        .saturating_mul(10_000)
+                        .checked_div(swap_fee_denominator)
+                        .unwrap_or_default();
+                    period = Some((fee_bps, slot, 0.0));
+                }
+                PoolEvent::Swap {
+                    direction,
+                    amount,
+                    slot,
+                } => {
+                    first_swap_slot.get_or_insert(slot);
+                    let (coin_in, pc_in) = match direction {
+                        SwapDirection::Coin2PC => (amount, 0),
+                        SwapDirection::PC2Coin => (0, amount),
+                    };
+                    coin_volume = coin_volume.saturating_add(coin_in);
+                    pc_volume = pc_volume.saturating_add(pc_in);
+                    if let Some((_, _, volume)) = period.as_mut() {
+                        *volume += crate::utils::to_ui_amount(coin_in, coin_decimals) * spot_price
+                            + crate::utils::to_ui_amount(pc_in, pc_decimals);
+                    }
+                }
+                _ => {}
+            }
+        }
+        if let Some((fee_bps, from_slot, volume)) = period {
+            fee_periods.push((fee_bps, volume / days(from_slot, current_slot)));
+        }
+        let volume_elasticity = super::math::estimate_volume_fee_elasticity(&fee_periods)
+            .filter(|elasticity| elasticity.is_finite())
+            .unwrap_or_else(|| {
+                log::debug!(
+                    "pool {} has too few fee changes to estimate the volume elasticity",
+                    pool_id
+                );
+                0.0
+            });
+
+        let daily_volume_usd = (crate::utils::to_ui_amount(coin_volume, coin_decimals)
+            * coin_price_usd
+            + crate::utils::to_ui_amount(pc_volume, pc_decimals) * pc_price_usd)
+            / days(first_swap_slot.unwrap_or(current_slot), current_slot);
+        let tvl_usd = crate::utils::to_ui_amount(state.coin_vault_amount, coin_decimals)
+            * coin_price_usd
+            + crate::utils::to_ui_amount(state.pc_vault_amount, pc_decimals) * pc_price_usd;
+        Ok(super::math::compute_fee_sensitivity(
+            state.pool_fee_bps(),
+            daily_volume_usd,
+            tvl_usd,
+            volume_elasticity,
+        ))
+    }
+
+    // 计算 lp 仓位的最优再平衡间隔 T(天),模型如下:
+    // - 价格对数收益率的日方差 σ² 由最近一天均匀采样 DAILY_VARIANCE_SAMPLES 个价格估计,无常损失以 σ²/8 每天的速度累积
+    // - 每次再平衡的成本(占仓位的比例) = 撤出流动性、兑换、重新添加流动性三笔交易的手续费 / 仓位价值
+    //   + 兑换价格偏移带来的多余一侧(约 σ√T / 2)所付的池子手续费
+    // - 再平衡把累积的无常损失清零,间隔内平均承受 σ²T/16 的无常损失
+    // 每天的总成本 g(T) = 固定成本 / T + 池子手续费率 * σ / (2√T) + σ²T/16 是单峰函数,
+    // 在 1 小时到 MAX_REBALANCING_INTERVAL_HOURS 之间三分查找最小值;
+    // 净收益为不再平衡时每天累积的无常损失 σ²/8 减去 g(T),为负说明再平衡得不偿失
+    pub async fn get_optimal_rebalancing_frequency(
+        &self,
+        pool_id: Pubkey,
+        sol_price_usd: f64,
+        position_usd: f64,
+    ) -> anyhow::Result<RebalancingFrequency> {
+        if position_usd <= 0.0 {
+            return Err(anyhow!(
+                "Position value must be positive, got {}",
+                position_usd
+            ));
+        }
+        let current_slot = self.client.get_slot().await?;
+        let (state, history) = futures_util::try_join!(
+            self.fetch_pool_state(pool_id),
+            self.get_vault_history(
+                pool_id,
+                current_slot.saturating_sub(SLOTS_PER_DAY),
+                current_slot,
+                DAILY_VARIANCE_SAMPLES,
+            ),
+        )?;
+        let prices = history
+            .into_iter()
+            .map(|sample| sample.implied_price)
+            .filter(|price| price.is_finite() && *price > 0.0)
+            .collect::<Vec<_>>();
+        if prices.len() < 3 {
+            return Err(anyhow!("not enough price samples for pool {}", pool_id));
+        }
+        let returns = super::math::log_returns(&prices);
+        let daily_variance = super::math::covariance(&returns, &returns) * returns.len() as f64;
+
+        let fixed_cost = 3.0
+            * crate::utils::to_ui_amount(self.expected_transaction_fee_lamports(), 9)
+            * sol_price_usd
+            / position_usd;
+        let fee_rate = state.pool_fee_bps() as f64 / 10_000.0;
+        let daily_cost = |interval_days: f64| {
+            fixed_cost / interval_days
+                + fee_rate * daily_variance.sqrt() / (2.0 * interval_days.sqrt())
+                + daily_variance * interval_days / 16.0
+        };
+
+        let (mut low, mut high) = (1.0 / 24.0, MAX_REBALANCING_INTERVAL_HOURS / 24.0);
+        for _ in 0..100 {
+            let (m1, m2) = (low + (high - low) / 3.0, high - (high - low) / 3.0);
+            if daily_cost(m1) < daily_cost(m2) {
+                high = m2;
+            } else {
+                low = m1;
+            }
+        }
+        let interval_days = (low + high) / 2.0;
+        Ok(RebalancingFrequency {
+            optimal_interval_hours: interval_days * 24.0,
+            expected_net_benefit_bps_per_day: (daily_variance / 8.0 - daily_cost(interval_days))
+                * 10_000.0,
+        })
+    }
+
+    // 把 lp 仓位看作卖出 gamma,手续费收益即为对冲这部分风险的"资金费率"
+    // 年化费率 = 交易实际覆盖区间内的年化手续费收益率 / (1 - 无常损失),无常损失为正数比例,
+    // 由区间内第一笔交易执行前与当前的金库原始余额(与交易记录一致,包含未提取的 pnl)所隐含的价格计算,
+    // 回看期内没有交易时两者都为 0
+    // 交易记录通过 get_trade_history_window 获取,被截断时手续费收益和无常损失都只统计保留的最新部分,
+    // 年化按实际覆盖的天数计算,is_truncated 为 true
+    pub async fn get_implied_funding_rate(
+        &self,
+        pool_id: Pubkey,
+        lookback_slots: u64,
+    ) -> anyhow::Result<ImpliedFundingRate> {
+        let (state, window) = futures_util::try_join!(
+            self.fetch_pool_state(pool_id),
+            self.get_trade_history_window(pool_id, lookback_slots),
+        )?;
+        let (coin_volume, pc_volume) = window
+            .transactions
+            .iter()
+            .filter_map(PoolTransaction::swap_amounts_in)
+            .fold((0u64, 0u64), |(coin, pc), (coin_in, pc_in)| {
+                (coin.saturating_add(coin_in), pc.saturating_add(pc_in))
+            });
+        let (coin_price_usd, pc_price_usd) = self.fetch_pool_token_prices(&state).await?;
+        let volume_usd = crate::utils::to_ui_amount(coin_volume, state.amm.coin_decimals)
+            * coin_price_usd
+            + crate::utils::to_ui_amount(pc_volume, state.amm.pc_decimals) * pc_price_usd;
+        let fees_usd = volume_usd * state.pool_fee_bps() as f64 / 10_000.0;
+        let days = window.covered_days();
+        let tvl_usd = state.tvl_usd(coin_price_usd, pc_price_usd);
+        let fee_apy_pct = if tvl_usd > 0.0 {
+            fees_usd / days * 365.0 / tvl_usd * 100.0
+        } else {
+            0.0
+        };
+
+        let impermanent_loss = window
+            .transactions
+            .first()
+            .map(|tx| {
+                let coin_before = tx.coin_vault_after as f64 - tx.coin_delta as f64;
+                let pc_before = tx.pc_vault_after as f64 - tx.pc_delta as f64;
+                let price_ratio = (state.pc_vault_balance as f64 / state.coin_vault_balance as f64)
+                    / (pc_before / coin_before);
+                if price_ratio.is_finite() {
+                    -super::math::compute_impermanent_loss(price_ratio)
+                } else {
+                    0.0
+                }
+            })
+            .unwrap_or_default();
+        if impermanent_loss >= 1.0 {
+            return Err(anyhow!(
+                "Pool {} lost all value to impermanent loss",
+                pool_id
+            ));
+        }
+
+        let annualized_rate_pct = fee_apy_pct / (1.0 - impermanent_loss);
+        Ok(ImpliedFundingRate {
+            hourly_rate_pct: annualized_rate_pct / 365.0 / 24.0,
+            daily_rate_pct: annualized_rate_pct / 365.0,
+            annualized_rate_pct,
+            is_truncated: window.is_truncated,
+        })
+    }
+
+    // 查询池子的熔断状态
+    // amm v4 没有针对价格剧烈波动的熔断机制: 兑换只检查用户给出的最少输出,不限制价格偏离,也没有冷却期,
+    // 因此池子存在时总是返回未触发,trigger_price_deviation_bps 和 cooldown_remaining_slots 都为 0
+    // 唯一能停止交易的是管理员修改池子的 status,需要时使用 get_pool_emergency_halt_status 检查
+    pub async fn get_circuit_breaker_status(
+        &self,
+        pool_id: Pubkey,
+    ) -> anyhow::Result<CircuitBreakerStatus> {
+        let account = self.client.get_account(&pool_id).await?;
+        decode_amm_info(&account.data)?;
+        Ok(CircuitBreakerStatus {
+            is_tripped: false,
+            trigger_price_deviation_bps: 0,
+            cooldown_remaining_slots: 0,
+        })
+    }
+
+    // 查找仿冒 legitimate_pool_id 的池子: 使用与原池子代币符号相同或相近、但 mint 不同的代币建立的池子
+    // 候选池子为包含原池子任一代币的 amm v4 池子(get_adjacent_pools,每个代币最多 100 个),
+    // 只交易原池子两个代币之外代币的仿冒池子不在搜索范围内
+    // similarity_score 为两侧代币符号相似度的平均值,mint 与原池子相同的一侧记为 1,
+    // 两侧 mint 都与原池子相同的池子是同一交易对的其他池子,不视为仿冒
+    // is_verified 表示池子的两个代币是否都在 raydium api 的官方代币列表中
+    pub async fn detect_pool_clones(
+        &self,
+        legitimate_pool_id: Pubkey,
+    ) -> anyhow::Result<Vec<CloneCandidate>> {
+        let legitimate_pool = self
+            .api
+            .fetch_pools_by_ids::<Option<ApiV3StandardPool>>(vec![legitimate_pool_id.to_string()])
+            .await?
+            .into_iter()
+            .flatten()
+            .find(|pool| pool.id == legitimate_pool_id)
+            .with_context(|| format!("Pool {} is not listed by the api", legitimate_pool_id))?;
+        let (mint_a, mint_b) = (&legitimate_pool.mint_a, &legitimate_pool.mint_b);
+        let (pools_a, pools_b, token_list) = futures_util::try_join!(
+            self.get_adjacent_pools(mint_a.address),
+            self.get_adjacent_pools(mint_b.address),
+            self.api.get_token_list(),
+        )?;
+        let is_official = |mint: &Pubkey| {
+            token_list
+                .mint_list
+                .iter()
+                .any(|token| token.address == *mint)
+                || token_list.whitelist.contains(&mint.to_string())
+        };
+        let side_similarity = |legitimate: &ApiV3Token, candidate: &ApiV3Token| {
+            if legitimate.address == candidate.address {
+                1.0
+            } else {
+                crate::utils::symbol_similarity(&legitimate.symbol, &candidate.symbol)
+            }
+        };
+
+        let mut candidates: Vec<CloneCandidate> = Vec::new();
+        for pool in pools_a.iter().chain(&pools_b) {
+            let same_pair = [pool.mint_a.address, pool.mint_b.address]
+                .iter()
+                .all(|mint| *mint == mint_a.address || *mint == mint_b.address);
+            if same_pair || candidates.iter().any(|c| c.suspicious_pool_id == pool.id) {
+                continue;
+            }
+            // 仿冒池子的代币顺序可能与原池子相反,取两种对应方式中较相似的一种
+            let similarity_score = f64::max(
+                (side_similarity(mint_a, &pool.mint_a) + side_similarity(mint_b, &pool.mint_b))
+                    / 2.0,
+                (side_similarity(mint_a, &pool.mint_b) + side_similarity(mint_b, &pool.mint_a))
+                    / 2.0,
+            );
+            if similarity_score < CLONE_SIMILARITY_THRESHOLD {
+                continue;
+            }
+            candidates.push(CloneCandidate {
+                suspicious_pool_id: pool.id,
+                similarity_score,
+                coin_mint: pool.mint_a.address,
+                pc_mint: pool.mint_b.address,
+                is_verified: is_official(&pool.mint_a.address) && is_official(&pool.mint_b.address),
+            });
+        }
+        candidates.sort_by(|a, b| b.similarity_score.total_cmp(&a.similarity_score));
+        Ok(candidates)
+    }
+
+    // 返回不给夹子攻击留下过多空间的滑点上限: auto_select_slippage_bps 给出的正常情况下成交所需的滑点的两倍,
+    // 且不超过 MAX_SAFE_SLIPPAGE_BPS; 设置的滑点越大,夹子攻击可以榨取的价值越多
+    // 报价已经包含了交易本身的价格影响,滑点只需覆盖报价到成交之间的价格波动,与交易数量无关,因此不需要传入数量
+    pub async fn get_max_safe_slippage_bps(&self, pool_id: Pubkey) -> anyhow::Result<u16> {
+        let min_required_slippage_bps = self.auto_select_slippage_bps(pool_id).await?;
+        Ok(min_required_slippage_bps
+            .saturating_mul(2)
+            .min(MAX_SAFE_SLIPPAGE_BPS))
+    }
+
+    // 汇总池子的安全检查结果,供钱包在用户确认兑换前展示风险摘要
+    // 池龄与 get_pool_risk_score 相同,取 AmmInfo 中的 pool_open_time 到现在的天数;
+    // authority 校验使用链上记录的 nonce 重新推导 authority,与池子的 AmmKeys 中的 authority 比较;
+    // lp 的集中度取自 get_pool_open_interest
+    pub async fn get_pool_audit_report(&self, pool_id: Pubkey) -> anyhow::Result<PoolAuditReport> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        let (
+            is_verified,
+            coin_mint_authority,
+            pc_mint_authority,
+            vault_verification,
+            lp_concentration,
+            upgrade_status,
+        ) = futures_util::try_join!(
+            self.is_verified_pool(pool_id),
+            self.get_token_mint_authority(state.amm_keys.amm_coin_mint),
+            self.get_token_mint_authority(state.amm_keys.amm_pc_mint),
+            self.get_vault_token_accounts(pool_id),
+            self.get_pool_open_interest(pool_id),
+            self.get_pool_upgrade_status(pool_id),
+        )?;
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let pool_age_days =
+            now.saturating_sub(state.amm.state_data.pool_open_time) as f64 / 86_400.0;
+        let is_authority_nonce_valid = u8::try_from(state.amm.nonce).is_ok_and(|nonce| {
+            Self::verify_authority_nonce(
+                &state.amm_keys.amm_authority,
+                nonce,
+                &upgrade_status.program_id,
+            )
+        });
+
+        Ok(PoolAuditReport {
+            pool_id,
+            is_verified,
+            pool_age_days,
+            coin_mint_authority,
+            pc_mint_authority,
+            is_authority_nonce_valid,
+            vault_verification,
+            lp_concentration,
+            upgrade_status,
+        })
+    }
+
+    // 模拟几种指令顺序,返回消耗计算单元最少的顺序及其消耗的计算单元数
+    // 包装 SOL 和创建 ATA 必须在兑换之前,关闭 wSOL 账户必须在兑换之后,因此只调整计算预算指令的位置:
+    // 最前面(make_swap 的默认顺序)、兑换之前、以及最后; 模拟失败的顺序会被跳过
+    // 模拟不校验签名并替换最近的区块哈希,返回的指令保留原有的 SetComputeUnitLimit,调用方可以按返回的计算单元数调整
+    pub async fn get_gas_optimized_instructions(
+        &self,
+        owner: Pubkey,
+        quote: RaydiumAmmQuote,
+        overrides: Option<&SwapConfigOverrides>,
+    ) -> anyhow::Result<(Vec<Instruction>, u32)> {
+        let SwapInstructionsBuilder {
+            compute_budget_instructions,
+            setup_instructions,
+            swap_instruction,
+            post_swap_instructions,
+            cleanup_instruction,
+            address_lookup_table_addresses: _,
+        } = self.make_swap(owner, quote, overrides, vec![]).await?;
+        let swap_instruction = swap_instruction.context("Swap instruction not set")?;
+
+        let ordering = |budget_position: usize| {
+            let mut instructions = setup_instructions.clone();
+            instructions.push(swap_instruction.clone());
+            instructions.extend(post_swap_instructions.iter().cloned());
+            instructions.extend(cleanup_instruction.clone());
+            let position = match budget_position {
+                0 => 0,
+                1 => setup_instructions.len(),
+                _ => instructions.len(),
+            };
+            instructions.splice(
+                position..position,
+                compute_budget_instructions.iter().cloned(),
+            );
+            instructions
+        };
+        let mut orderings = (0..3).map(ordering).collect::<Vec<_>>();
+        // 没有 setup 指令或计算预算指令时,不同位置会得到相同的顺序
+        orderings.dedup();
+
+        let simulations = orderings.iter().map(|instructions| async move {
+            let transaction = VersionedTransaction {
+                signatures: vec![Signature::default()],
+                message: VersionedMessage::Legacy(Message::new(instructions, Some(&owner))),
+            };
+            let result = self
+                .client
+                .simulate_transaction_with_config(
+                    &transaction,
+                    RpcSimulateTransactionConfig {
+                        sig_verify: false,
+                        replace_recent_blockhash: true,
+                        commitment: Some(CommitmentConfig::confirmed()),
+                        ..Default::default()
+                    },
+                )
+                .await?
+                .value;
+            if let Some(err) = result.err {
+                return Err(anyhow!("Simulation failed: {}", err));
+            }
+            result
+                .units_consumed
+                .context("Simulation did not report the consumed compute units")
+        });
+        let units = futures_util::future::join_all(simulations).await;
+
+        let (instructions, units_consumed) = orderings
+            .into_iter()
+            .zip(units)
+            .filter_map(|(instructions, units)| {
+                units
+                    .map_err(|e| log::debug!("Instruction ordering failed: {}", e))
+                    .ok()
+                    .map(|units| (instructions, units))
+            })
+            .min_by_key(|(_, units)| *units)
+            .context("All instruction orderings failed to simulate")?;
+        Ok((instructions, u32::try_from(units_consumed)?))
+    }
+
+    // 量化一笔兑换面临的夹子攻击风险
+    // 攻击者先以 front_run_size 同方向兑换抬高价格,受害者在滑点允许的范围内成交,攻击者再把抢跑得到的代币
+    // (back_run_size)反向卖回池子; 按恒定乘积公式(手续费从输入中扣除,全部输入进入金库)解析计算各步的输出,
+    // front_run_size 取受害者输出仍不低于其最少输出的最大抢跑数量,由二分查找得到
+    // 受害者的兑换方向未知,分别计算两个方向,返回攻击者利润较大的一个; 利润按 api 价格换算为 lamports,
+    // 无利可图时各数量都为 0
+    pub async fn get_max_extractable_value(
+        &self,
+        pool_id: Pubkey,
+        victim_amount: u64,
+        victim_slippage_bps: u16,
+    ) -> anyhow::Result<MevExposure> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        let (coin_mint, pc_mint) = (state.amm_keys.amm_coin_mint, state.amm_keys.amm_pc_mint);
+        let prices = self
+            .api
+            .get_token_price(vec![
+                coin_mint.to_string(),
+                pc_mint.to_string(),
+                spl_token::native_mint::ID.to_string(),
+            ])
+            .await?;
+        let price_of = |mint: &Pubkey| prices.get(mint).copied().unwrap_or_default();
+        let sol_price_usd = price_of(&spl_token::native_mint::ID);
+        let fee_rate =
+            state.amm.fees.swap_fee_numerator as f64 / state.amm.fees.swap_fee_denominator as f64;
+        let swap_out = |reserve_in: f64, reserve_out: f64, amount_in: f64| {
+            let amount_in_after_fee = amount_in * (1.0 - fee_rate);
+            reserve_out * amount_in_after_fee / (reserve_in + amount_in_after_fee)
+        };
+
+        let mut best = MevExposure {
+            front_run_size: 0,
+            back_run_size: 0,
+            attacker_profit_lamports: 0,
+            victim_extra_slippage_bps: 0,
+        };
+        for direction in [SwapDirection::Coin2PC, SwapDirection::PC2Coin] {
+            let (input_mint, input_decimals) = match direction {
+                SwapDirection::Coin2PC => (coin_mint, state.amm.coin_decimals),
+                SwapDirection::PC2Coin => (pc_mint, state.amm.pc_decimals),
+            };
+            let x = state.input_vault_amount(direction) as f64;
+            let y = state.output_vault_amount(direction) as f64;
+            let victim = victim_amount as f64;
+            let expected_out = swap_out(x, y, victim);
+            let min_out = expected_out * (1.0 - victim_slippage_bps as f64 / 10_000.0);
+            // 抢跑 front_run 之后受害者的输出
+            let victim_out_after = |front_run: f64| {
+                let front_out = swap_out(x, y, front_run);
+                swap_out(x + front_run, y - front_out, victim)
+            };
+
+            // 受害者的输出随抢跑数量单调减少,二分查找满足最少输出的最大抢跑数量
+            let (mut low, mut high) = (0u64, state.input_vault_amount(direction));
+            while low < high {
+                let mid = low + (high - low + 1) / 2;
+                if victim_out_after(mid as f64) >= min_out {
+                    low = mid;
+                } else {
+                    high = mid - 1;
+                }
+            }
+            let front_run = low as f64;
+            let front_out = swap_out(x, y, front_run);
+            let victim_out = victim_out_after(front_run);
+            let (x_after, y_after) = (x + front_run + victim, y - front_out - victim_out);
+            let back_out = swap_out(y_after, x_after, front_out);
+            let profit = back_out - front_run;
+            let input_price_usd = price_of(&input_mint);
+            if profit <= 0.0 || input_price_usd <= 0.0 || sol_price_usd <= 0.0 {
+                continue;
+            }
+
+            let attacker_profit_lamports = (profit / 10f64.powi(input_decimals as i32)
+                * input_price_usd
+                / sol_price_usd
+                * 1e9) as u64;
+            if attacker_profit_lamports > best.attacker_profit_lamports {
+                best = MevExposure {
+                    front_run_size: low,
+                    back_run_size: front_out as u64,
+                    attacker_profit_lamports,
+                    victim_extra_slippage_bps: ((1.0 - victim_out / expected_out) * 10_000.0)
+                        .round() as u64,
+                };
+            }
+        }
+        Ok(best)
+    }
+
+    // 比较池子全部流动性作为 lp 仓位与持有入场时的代币之间的价值差异
+    // 入场时的代币数量由当前的恒定乘积 k(按精度换算)和入场时的相对价格 P0 = initial_coin_usd / initial_pc_usd 推出:
+    // coin = sqrt(k / P0), pc = sqrt(k * P0); lp 价值 = 持有价值 * (1 + 无常损失(P1 / P0))
+    // 恒定乘积池中 lp 价值不会超过持有价值: 两种代币同时上涨而相对价格不变时差值为 0,相对价格变化时为负,
+    // 只有手续费收入可以带来"无常收益",这里不包括手续费,因此 is_gain 总是 false
+    pub async fn get_impermanent_gain(
+        &self,
+        pool_id: Pubkey,
+        initial_coin_usd: f64,
+        initial_pc_usd: f64,
+        current_coin_usd: f64,
+        current_pc_usd: f64,
+    ) -> anyhow::Result<ImpermanentGainLoss> {
+        if [
+            initial_coin_usd,
+            initial_pc_usd,
+            current_coin_usd,
+            current_pc_usd,
+        ]
+        .iter()
+        .any(|price| !price.is_finite() || *price <= 0.0)
+        {
+            return Err(anyhow!("Token prices must be positive"));
+        }
+        let state = self.fetch_pool_state(pool_id).await?;
+        let k = crate::utils::to_ui_amount(state.coin_vault_amount, state.amm.coin_decimals)
+            * crate::utils::to_ui_amount(state.pc_vault_amount, state.amm.pc_decimals);
+
+        let initial_price = initial_coin_usd / initial_pc_usd;
+        let current_price = current_coin_usd / current_pc_usd;
+        let (initial_coin, initial_pc) = ((k / initial_price).sqrt(), (k * initial_price).sqrt());
+        let hodl_value_usd = initial_coin * current_coin_usd + initial_pc * current_pc_usd;
+        let lp_value_usd = hodl_value_usd
+            * (1.0 + super::math::compute_impermanent_loss(current_price / initial_price));
+        let difference_usd = lp_value_usd - hodl_value_usd;
+        Ok(ImpermanentGainLoss {
+            hodl_value_usd,
+            lp_value_usd,
+            difference_usd,
+            is_gain: difference_usd > 0.0,
+        })
+    }
+
+    // 计算回看期内兑换的成交量加权平均价格(coin 以 pc 计价,已按精度换算),可作为限价单策略的参考价格
+    // 每笔兑换的成交价格为金库 pc 变化量 / coin 变化量,权重为兑换量; 两个方向的输入代币不同,
+    // 因此兑换量统一以 coin 计量,加权平均即为 Σ pc / Σ coin
+    pub async fn get_vwap(&self, pool_id: Pubkey, lookback_slots: u64) -> anyhow::Result<f64> {
+        let (state, trades) = futures_util::try_join!(
+            self.fetch_pool_state(pool_id),
+            self.get_trade_history(pool_id, lookback_slots),
+        )?;
+        let (coin_volume, pc_volume) = trades
+            .iter()
+            .filter(|tx| matches!(tx.kind(), PoolTransactionKind::Swap(_)))
+            .fold((0u128, 0u128), |(coin, pc), tx| {
+                (
+                    coin + tx.coin_delta.unsigned_abs() as u128,
+                    pc + tx.pc_delta.unsigned_abs() as u128,
+                )
+            });
+        if coin_volume == 0 {
+            return Err(anyhow!(
+                "No swaps in pool {} over the lookback period",
+                pool_id
+            ));
+        }
+        Ok(pc_volume as f64
+            / 10f64.powi(state.amm.pc_decimals as i32)
+            / (coin_volume as f64 / 10f64.powi(state.amm.coin_decimals as i32)))
+    }
+
+    // 评估代币解锁对池子的冲击: 假设解锁的 unlock_amount 个 coin 全部在池子中卖出换成 pc
+    // 本 crate 没有 get_price_impact_table,价格影响直接由 RaydiumAmmPoolState::price_impact_bps 按当前储备计算;
+    // 卖出压力按 api 的 coin 价格计算,vault_drain_pct 为卖出得到的 pc 占 pc 金库的比例,
+    // 价格影响不超过 UNLOCK_ABSORB_MAX_IMPACT_BPS 时认为池子能够承接
+    pub async fn get_token_unlock_impact(
+        &self,
+        pool_id: Pubkey,
+        unlock_amount: u64,
+    ) -> anyhow::Result<UnlockImpact> {
+        let state = self.fetch_pool_state(pool_id).await?;
+        let (coin_price_usd, _) = self.fetch_pool_token_prices(&state).await?;
+        let direction = SwapDirection::Coin2PC;
+        let price_impact_if_all_sold_bps =
+            state.price_impact_bps(direction, unlock_amount)?.round() as u64;
+        let pc_out = state.swap_output(direction, unlock_amount)?;
+        let vault_drain_pct = if state.pc_vault_amount == 0 {
+            0.0
+        } else {
+            pc_out as f64 / state.pc_vault_amount as f64 * 100.0
+        };
+        Ok(UnlockImpact {
+            estimated_sell_pressure_usd: crate::utils::to_ui_amount(
+                unlock_amount,
+                state.amm.coin_decimals,
+            ) * coin_price_usd,
+            price_impact_if_all_sold_bps,
+            vault_drain_pct,
+            is_pool_sufficient_to_absorb: price_impact_if_all_sold_bps
+                <= UNLOCK_ABSORB_MAX_IMPACT_BPS,
+        })
+    }
+
+    // 根据紧急程度推荐路由、滑点和优先费:
+    // - High: 只走直接兑换(账户和计算单元最少),滑点为输入的两倍以减少因价格变动失败,优先费取最近 slot 的 90 分位
+    // - Medium: 两跳路由的输出比直接兑换多 MULTI_HOP_MIN_IMPROVEMENT_BPS 以上时才选择两跳,滑点不变,优先费取中位数
+    // - Low: 选择输出最多的路由,滑点减半(至少 1 bps),优先费取最近 slot 的最小值
+    // 两跳路由与 get_best_route_for_amount 相同,由 quote_two_hop_routes 经由 SOL 或 USDC 报价,
+    // 只支持 exact-in,exact-out 时只考虑直接兑换
+    // 优先费来自 getRecentPrioritizationFees,单位为每个 CU 的 micro-lamports
+    pub async fn get_smart_routing_recommendation(
+        &self,
+        swap_input: &SwapInput,
+        urgency: Urgency,
+    ) -> anyhow::Result<RoutingRecommendation> {
+        let slippage_bps = match urgency {
+            Urgency::High => swap_input.slippage_bps.saturating_mul(2),
+            Urgency::Medium => swap_input.slippage_bps,
+            Urgency::Low => (swap_input.slippage_bps / 2).max(1),
+        };
+        let direct = self
+            .quote(&SwapInput {
+                slippage_bps,
+                ..*swap_input
+            })
+            .await?;
+        if !direct.amount_specified_is_input {
+            return Ok(RoutingRecommendation {
+                route_type: RouteType::Direct,
+                expected_input: direct.other_amount,
+                expected_output: direct.amount,
+                slippage_bps,
+                priority_fee: self.recommended_priority_fee(urgency).await?,
+            });
+        }
+
+        let mut route_type = RouteType::Direct;
+        let mut expected_output = direct.other_amount;
+        if urgency != Urgency::High {
+            let best_two_hop = self
+                .quote_two_hop_routes(
+                    swap_input.input_token_mint,
+                    swap_input.output_token_mint,
+                    swap_input.amount,
+                    slippage_bps,
+                )
+                .await
+                .into_iter()
+                .map(|(intermediate_mint, _, second)| (intermediate_mint, second.other_amount))
+                .max_by_key(|(_, output)| *output);
+            let min_improvement_bps = match urgency {
+                Urgency::Medium => MULTI_HOP_MIN_IMPROVEMENT_BPS,
+                _ => 0,
+            };
+            if let Some((intermediate_mint, output)) = best_two_hop {
+                let threshold = expected_output as u128 * (10_000 + min_improvement_bps) as u128;
+                if output as u128 * 10_000 > threshold {
+                    route_type = RouteType::TwoHop { intermediate_mint };
+                    expected_output = output;
+                }
+            }
+        }
+
+        Ok(RoutingRecommendation {
+            route_type,
+            expected_input: swap_input.amount,
+            expected_output,
+            slippage_bps,
+            priority_fee: self.recommended_priority_fee(urgency).await?,
+        })
+    }
+
+    // 按紧急程度从 get_priority_fee_distribution 中取分位数: High 取 90 分位,Medium 取中位数,Low 取最小值
+    async fn recommended_priority_fee(
+        &self,
+        urgency: Urgency,
+    ) -> anyhow::Result<PriorityFeeConfig> {
+        let fees = self.get_priority_fee_distribution().await?;
+        let percentile = match urgency {
+            Urgency::High => 90,
+            Urgency::Medium => 50,
+            Urgency::Low => 0,
+        };
+        let cu_price = if fees.is_empty() {
+            0
+        } else {
+            fees[(fees.len() - 1) * percentile / 100]
+        };
+        Ok(PriorityFeeConfig::FixedCuPrice(cu_price))
+    }
+
+    // 汇总池子的健康指标,供每 30 秒轮询一次的监控服务使用,最多 3 次 RPC 请求:
+    // 1. 池子状态所需的账户通过一次 get_multiple_accounts 请求获取(池子 keys 来自 API)
+    // 2. get_last_successful_slot 获取最近 LAST_TRADE_LOOKBACK_TRANSACTIONS 笔交易中最后一笔成功交易的 slot 为 last_trade_slot,
+    //    其中可能包括加减流动性等非兑换交易
+    // 3. get_token_largest_accounts 获取最大 lp 持有者,用于风险评分
+    // 代币价格、是否在官方列表中以及 24 小时价格区间来自 API,不计入 RPC 请求
+    // 波动率为 API 给出的 24 小时价格区间 (max - min) / 当前价格,池子不在 API 中时为 None;
+    // 风险评分与 get_pool_risk_score 相同,但最近价格的变异系数按价格区间约为 4 个标准差估算,波动率未知时按最高风险计,
+    // 是否可信按执行器的 PoolVerificationPolicy 判断(见 is_verified_pool),池子账户的 owner 取自第 1 次请求
+    // API 不可用时返回错误,不会回退到链上读取 keys,以免超出 RPC 请求的上限
+    pub async fn get_pool_health_dashboard(
+        &self,
+        pool_id: Pubkey,
+    ) -> anyhow::Result<PoolHealthDashboard> {
+        let (amm_keys, market_keys) = self
+            .load_pool_keys_by_api(pool_id)
+            .await
+            .with_context(|| format!("Failed to load keys for pool {} from api", pool_id))?;
+        let load_pubkeys = Self::pool_state_pubkeys(&amm_keys, &market_keys);
+        let lp_mint = amm_keys.amm_lp_mint;
+
+        let (rsps, api_pools) = futures_util::try_join!(
+            crate::utils::get_multiple_account_data(&self.client, &load_pubkeys),
+            self.api
+                .fetch_pools_by_ids::<Option<ApiV3StandardPool>>(vec![pool_id.to_string()]),
+        )?;
+        let (last_trade_slot, largest_lp_accounts) = futures_util::try_join!(
+            self.get_last_successful_slot(&pool_id, LAST_TRADE_LOOKBACK_TRANSACTIONS),
+            async {
+                Ok::<_, anyhow::Error>(self.client.get_token_largest_accounts(&lp_mint).await?)
+            },
+        )?;
+        let pool_owner = rsps[0].as_ref().map(|account| account.owner);
+        let state = Self::pool_state_from_accounts(amm_keys, market_keys, array_ref![rsps, 0, 7])?;
+        let (coin_price_usd, pc_price_usd) = self.fetch_pool_token_prices(&state).await?;
+
+        let coin_value_usd =
+            crate::utils::to_ui_amount(state.coin_vault_amount, state.amm.coin_decimals)
+                * coin_price_usd;
+        let pc_value_usd =
+            crate::utils::to_ui_amount(state.pc_vault_amount, state.amm.pc_decimals) * pc_price_usd;
+        let tvl_usd = coin_value_usd + pc_value_usd;
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let pool_age_days =
+            now.saturating_sub(state.amm.state_data.pool_open_time) as f64 / 86_400.0;
+
+        // api 对不认识的池子 id 返回 null
+        let api_pool = api_pools.into_iter().flatten().find(|pool| {
+            pool.id == pool_id && pool.program_id == RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID
+        });
+        let volatility_24h = api_pool
+            .as_ref()
+            .filter(|pool| pool.price > 0.0)
+            .map(|pool| (pool.day.price_max - pool.day.price_min) / pool.price);
+
+        let largest_holder = largest_lp_accounts
+            .first()
+            .and_then(|account| account.amount.amount.parse::<u64>().ok())
+            .unwrap_or_default();
+        let lp_concentration_score = if state.amm.lp_amount == 0 {
+            1.0
+        } else {
+            (largest_holder as f64 / state.amm.lp_amount as f64).min(1.0)
+        };
+        let risk_score = PoolRiskScore::new(
+            pool_age_days,
+            tvl_usd,
+            lp_concentration_score,
+            self.satisfies_verification_policy(api_pool.is_some(), pool_owner),
+            volatility_24h.map_or(f64::INFINITY, |range| range / 4.0),
+        );
+
+        Ok(PoolHealthDashboard {
+            pool_id,
+            spot_price: state.spot_price(),
+            tvl_usd,
+            fee_rate_bps: state.pool_fee_bps(),
+            pool_age_days,
+            last_trade_slot,
+            vault_imbalance_ratio: if pc_value_usd > 0.0 {
+                coin_value_usd / pc_value_usd
+            } else {
+                0.0
+            },
+            stability_index: state.stability_index(coin_price_usd, pc_price_usd)?,
+            volatility_24h,
+            risk_score,
+        })
+    }
+
+    // 更新配置
+    pub fn update_config(&mut self, config: &SwapConfig) {
+        // 将传入的配置赋值给self的config
+        self.config = *config;
+    }
+
+    // 创建交换指令构建器,添加输出账户的创建以及 SOL 的包装和解包指令,由 make_swap 和 batch_swap_instructions 共用
+    fn swap_accounts_builder(
+        &self,
+        owner: Pubkey,
+        quote: &RaydiumAmmQuote,
+        overrides: Option<&SwapConfigOverrides>,
+    ) -> anyhow::Result<SwapInstructionsBuilder> {
+        // 获取是否需要包装和解包 SOL
+        let wrap_and_unwrap_sol = overrides
+            .and_then(|o| o.wrap_and_unwrap_sol)
+            .or(self.config.wrap_and_unwrap_sol)
+            .unwrap_or(true);
+
+        let mut builder = SwapInstructionsBuilder::default();
+        // 处理令牌包装和解包以及账户创建
+        let _associated_accounts = builder.handle_token_wrapping_and_accounts_creation(
+            owner,
+            wrap_and_unwrap_sol,
+            if quote.amount_specified_is_input {
+                quote.amount
+            } else {
+                quote.other_amount
+            },
+            quote.input_mint,
+            quote.output_mint,
+            spl_token::ID,
+            spl_token::ID,
+            None,
+        )?;
+        Ok(builder)
+    }
+
+    // 异步函数，用于创建交换指令
+    async fn make_swap(
+        &self,
+        input_pubkey: Pubkey, // 输入公钥
+        output: RaydiumAmmQuote, // 交换输出
+        overrides: Option<&SwapConfigOverrides>, // 交换配置覆盖
+        post_swap_instructions: Vec<Instruction>, // 交换之后、清理之前执行的指令
+    ) -> anyhow::Result<SwapInstructionsBuilder> { // 返回交换指令构建器
+        // 获取优先费用
+        let priority_fee = overrides
+            .and_then(|o| o.priority_fee)
+            .or(self.config.priority_fee);
+        // 获取计算单元限制
+        let cu_limits = overrides
+            .and_then(|o| o.cu_limits)
+            .or(self.config.cu_limits);
+
+        // 创建交换指令构建器,并处理令牌包装和解包以及账户创建
+        let mut builder = self.swap_accounts_builder(input_pubkey, &output, overrides)?;
+        // 创建交换指令
+        let instruction = swap_instruction(
+            &RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID,
+            &output.amm_keys,
+            &output.market_keys,
+            &input_pubkey,
+            &spl_associated_token_account::get_associated_token_address(
+                &input_pubkey,
+                &output.input_mint,
+            ),
+            &spl_associated_token_account::get_associated_token_address(
+                &input_pubkey,
+                &output.output_mint,
+            ),
+            output.amount,
+            output.other_amount_threshold,
+            output.amount_specified_is_input,
+        )?;
+        // 将交换指令添加到构建器中
+        builder.swap_instruction = Some(instruction);
+        builder.post_swap_instructions = post_swap_instructions;
+
+        // 处理计算单元参数
+        let compute_units = builder
+            .handle_compute_units_params(cu_limits, &self.client, input_pubkey)
+            .await?;
+        // 处理优先费用参数
+        builder.handle_priority_fee_params(priority_fee, compute_units, input_pubkey)?;
+
+        // 返回交换指令构建器
+        Ok(builder)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RaydiumAmmQuote {
+    /// The address of the amm pool
+    pub market: Pubkey,
+    /// The input mint
+    pub input_mint: Pubkey,
+    /// The output mint,
+    pub output_mint: Pubkey,
+    /// The amount specified
+    pub amount: u64,
+    /// The other amount
+    pub other_amount: u64,
+    /// The other amount with slippage
+    pub other_amount_threshold: u64,
+    /// Whether the amount specified is in terms of the input token
+    pub amount_specified_is_input: bool,
+    /// The input mint decimals
+    pub input_mint_decimals: u8,
     /// The output mint decimals
     pub output_mint_decimals: u8,
+    /// The swap fee numerator of the pool
+    pub swap_fee_numerator: u64,
+    /// The swap fee denominator of the pool
+    pub swap_fee_denominator: u64,
+    /// Amm keys
+    pub amm_keys: AmmKeys,
+    /// Market keys
+    pub market_keys: MarketKeys,
+}
+
+#[derive(Debug)]
+pub struct RaydiumAmmPoolState {
+    /// The address of the amm pool
+    pub pool_id: Pubkey,
+    /// The decoded amm account
+    pub amm: raydium_amm::state::AmmInfo,
     /// Amm keys
     pub amm_keys: AmmKeys,
     /// Market keys
     pub market_keys: MarketKeys,
+    /// The token balance of the coin vault
+    pub coin_vault_balance: u64,
+    /// The token balance of the pc vault
+    pub pc_vault_balance: u64,
+    /// The coin amount owned by the pool, excluding pnl that has yet to be taken
+    pub coin_vault_amount: u64,
+    /// The pc amount owned by the pool, excluding pnl that has yet to be taken
+    pub pc_vault_amount: u64,
+}
+
+#[derive(Debug)]
+pub struct TransactionSizeReport {
+    /// The serialized size of the transaction in bytes
+    pub size: usize,
+    /// Whether the transaction exceeds the packet size limit
+    pub exceeds_limit: bool,
+    /// Suggestions to shrink the transaction when it exceeds the limit
+    pub suggestions: Vec<String>,
+}
+
+impl RaydiumAmmPoolState {
+    /// The spot price of the coin in terms of pc, adjusted for decimals
+    pub fn spot_price(&self) -> f64 {
+        crate::utils::to_ui_amount(self.pc_vault_amount, self.amm.pc_decimals)
+            / crate::utils::to_ui_amount(self.coin_vault_amount, self.amm.coin_decimals)
+    }
+
+    /// The swap fee charged by the pool in basis points
+    pub fn pool_fee_bps(&self) -> u64 {
+        self.amm
+            .fees
+            .swap_fee_numerator
+            .saturating_mul(10_000)
+            .checked_div(self.amm.fees.swap_fee_denominator)
+            .unwrap_or_default()
+    }
+
+    /// The swap direction for the given input and output mint
+    pub fn direction(
+        &self,
+        input_mint: &Pubkey,
+        output_mint: &Pubkey,
+    ) -> anyhow::Result<SwapDirection> {
+        let (coin_mint, pc_mint) = (&self.amm_keys.amm_coin_mint, &self.amm_keys.amm_pc_mint);
+        if input_mint == coin_mint && output_mint == pc_mint {
+            Ok(SwapDirection::Coin2PC)
+        } else if input_mint == pc_mint && output_mint == coin_mint {
+            Ok(SwapDirection::PC2Coin)
+        } else {
+            Err(anyhow!(
+                "Pool {} does not trade {} for {}",
+                self.pool_id,
+                input_mint,
+                output_mint
+            ))
+        }
+    }
+
+    /// The amount held by the pool of the token paid in for the given direction
+    pub fn input_vault_amount(&self, direction: SwapDirection) -> u64 {
+        match direction {
+            SwapDirection::Coin2PC => self.coin_vault_amount,
+            SwapDirection::PC2Coin => self.pc_vault_amount,
+        }
+    }
+
+    /// The amount held by the pool of the token paid out for the given direction
+    pub fn output_vault_amount(&self, direction: SwapDirection) -> u64 {
+        match direction {
+            SwapDirection::Coin2PC => self.pc_vault_amount,
+            SwapDirection::PC2Coin => self.coin_vault_amount,
+        }
+    }
+
+    /// The output of an exact-in swap against the current reserves, after fees
+    pub fn swap_output(&self, direction: SwapDirection, amount_in: u64) -> anyhow::Result<u64> {
+        let (other_amount, _) = raydium_library::amm::swap_with_slippage(
+            self.pc_vault_amount,
+            self.coin_vault_amount,
+            self.amm.fees.swap_fee_numerator,
+            self.amm.fees.swap_fee_denominator,
+            direction,
+            amount_in,
+            true,
+            0,
+        )?;
+        Ok(other_amount)
+    }
+
+    /// The input of an exact-out swap against the current reserves, including fees
+    pub fn swap_input(&self, direction: SwapDirection, amount_out: u64) -> anyhow::Result<u64> {
+        let (other_amount, _) = raydium_library::amm::swap_with_slippage(
+            self.pc_vault_amount,
+            self.coin_vault_amount,
+            self.amm.fees.swap_fee_numerator,
+            self.amm.fees.swap_fee_denominator,
+            direction,
+            amount_out,
+            false,
+            0,
+        )?;
+        Ok(other_amount)
+    }
+
+    /// The price impact of an exact-in swap in basis points, excluding the swap fee
+    pub fn price_impact_bps(
+        &self,
+        direction: SwapDirection,
+        amount_in: u64,
+    ) -> anyhow::Result<f64> {
+        let fee_rate =
+            self.amm.fees.swap_fee_numerator as f64 / self.amm.fees.swap_fee_denominator as f64;
+        let ideal_output =
+            amount_in as f64 * (1.0 - fee_rate) * self.output_vault_amount(direction) as f64
+                / self.input_vault_amount(direction) as f64;
+        if ideal_output <= 0.0 {
+            return Ok(0.0);
+        }
+        let output = self.swap_output(direction, amount_in)? as f64;
+        Ok(((1.0 - output / ideal_output) * 10_000.0).max(0.0))
+    }
+
+    /// The largest exact-in amount whose price impact does not exceed `max_impact_bps`, found by
+    /// binary search over the input vault depth with a 0.1% tolerance
+    pub fn max_amount_for_impact(
+        &self,
+        direction: SwapDirection,
+        max_impact_bps: f64,
+    ) -> anyhow::Result<u64> {
+        let (mut low, mut high) = (1u64, self.input_vault_amount(direction));
+        if self.price_impact_bps(direction, low)? > max_impact_bps {
+            return Ok(0);
+        }
+        if self.price_impact_bps(direction, high)? <= max_impact_bps {
+            return Ok(high);
+        }
+        while (high - low) as f64 > high as f64 * 0.001 {
+            let mid = low + (high - low) / 2;
+            if self.price_impact_bps(direction, mid)? <= max_impact_bps {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        Ok(low)
+    }
+
+    /// The total value locked in the pool for the given token prices
+    pub fn tvl_usd(&self, coin_price_usd: f64, pc_price_usd: f64) -> f64 {
+        crate::utils::to_ui_amount(self.coin_vault_amount, self.amm.coin_decimals) * coin_price_usd
+            + crate::utils::to_ui_amount(self.pc_vault_amount, self.amm.pc_decimals) * pc_price_usd
+    }
+
+    /// The usd cost of moving the price by 1%, 5% and 10% in the cheaper direction
+    pub fn stability_index(
+        &self,
+        coin_price_usd: f64,
+        pc_price_usd: f64,
+    ) -> anyhow::Result<StabilityIndex> {
+        let cost_to_move = |impact_bps: f64| -> anyhow::Result<f64> {
+            let coin_in = self.max_amount_for_impact(SwapDirection::Coin2PC, impact_bps)?;
+            let pc_in = self.max_amount_for_impact(SwapDirection::PC2Coin, impact_bps)?;
+            let coin_cost =
+                crate::utils::to_ui_amount(coin_in, self.amm.coin_decimals) * coin_price_usd;
+            let pc_cost = crate::utils::to_ui_amount(pc_in, self.amm.pc_decimals) * pc_price_usd;
+            Ok(coin_cost.min(pc_cost))
+        };
+
+        Ok(StabilityIndex {
+            cost_to_move_1pct_usd: cost_to_move(100.0)?,
+            cost_to_move_5pct_usd: cost_to_move(500.0)?,
+            cost_to_move_10pct_usd: cost_to_move(1_000.0)?,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct YieldRate {
+    /// The fee income of the lookback period extrapolated to a year, as a percentage of tvl
+    pub annualized_fee_yield_pct: f64,
+    /// The fees collected by the fetched transactions
+    pub total_fees_collected_usd: f64,
+    /// The average daily swap volume over the slots covered by the fetched transactions
+    pub avg_daily_volume_usd: f64,
+    /// Whether the lookback period held more transactions than were fetched, in which case the
+    /// yield is extrapolated from the most recent part of the period only
+    pub is_truncated: bool,
+}
+
+/// The pool transactions of a lookback period and the slots they actually cover
+#[derive(Clone, Debug)]
+pub struct TradeHistoryWindow {
+    /// The pool transactions, oldest first
+    pub transactions: Vec<PoolTransaction>,
+    /// The first slot covered. Later than the start of the lookback period when truncated
+    pub from_slot: u64,
+    /// The last slot covered
+    pub to_slot: u64,
+    /// Whether the lookback period held more than `MAX_POOL_HISTORY_TRANSACTIONS` transactions
+    pub is_truncated: bool,
+}
+
+impl TradeHistoryWindow {
+    /// The number of days covered by the transactions, at least one slot
+    pub fn covered_days(&self) -> f64 {
+        self.to_slot.saturating_sub(self.from_slot).max(1) as f64 / SLOTS_PER_DAY as f64
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct MaxTradeableAmount {
+    /// The largest input amount whose price impact stays within the limit
+    pub amount: u64,
+    /// The price impact of swapping `amount`, in basis points
+    pub price_impact_bps: f64,
+}
+
+#[derive(Debug)]
+pub struct OracleComparison {
+    /// The spot price of the pool, in pc per coin
+    pub amm_price: f64,
+    /// The price reported by the oracle
+    pub oracle_price: f64,
+    /// The divergence of the amm price from the oracle price
+    pub divergence_bps: i64,
+    /// Whether the divergence exceeds the configured threshold
+    pub is_anomalous: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PoolMetricsSnapshot {
+    /// The address of the amm pool
+    #[serde(with = "field_as_string")]
+    pub pool_id: Pubkey,
+    /// The spot price of the coin in terms of pc
+    pub spot_price: f64,
+    /// The total value locked in the pool
+    pub tvl_usd: f64,
+    /// The swap fee in basis points
+    pub fee_rate_bps: u64,
+    /// The usd value of the coin vault divided by the usd value of the pc vault
+    pub vault_imbalance_ratio: f64,
+    /// The status of the pool
+    pub pool_status: AmmPoolStatus,
+    /// The lp supply tracked by the pool
+    pub lp_supply: u64,
+    /// The coin amount owned by the pool
+    pub coin_vault_amount: u64,
+    /// The pc amount owned by the pool
+    pub pc_vault_amount: u64,
+}
+
+#[derive(Debug)]
+pub struct ImpermanentLossReport {
+    /// The slot of the pool transaction the entry price was taken from
+    pub deposit_slot: u64,
+    /// The price of the coin in terms of pc at the deposit
+    pub price_at_deposit: f64,
+    /// The current price of the coin in terms of pc
+    pub current_price: f64,
+    /// The impermanent loss as a (non-positive) percentage of the holding value
+    pub impermanent_loss_pct: f64,
+    /// The current value of the lp tokens
+    pub lp_value_usd: f64,
+    /// The current value of the tokens had they been held instead of deposited
+    pub hodl_value_usd: f64,
+    /// The difference between the lp value and the holding value
+    pub impermanent_loss_usd: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PoolConfigParams {
+    #[serde(with = "field_as_string")]
+    pub pool_id: Pubkey,
+    /// The raw pool status, which determines every permission below
+    pub status: AmmPoolStatus,
+    /// Whether swaps are allowed
+    pub swap_permission: bool,
+    /// Whether liquidity can be added
+    pub deposit_permission: bool,
+    /// Whether liquidity can be removed
+    pub withdraw_permission: bool,
+    /// Whether the pool places orders on the openbook market
+    pub orderbook_permission: bool,
+    /// The state machine of the orderbook integration: 0 invalid, 1 idle, 2 cancel all orders,
+    /// 3 plan orders, 4 cancel order, 5 place orders, 6 purge order
+    pub amm_state: u64,
+    /// 0 if the orderbook state should be reset on the next crank, 1 otherwise
+    pub reset_flag: u64,
+    /// The maximum number of orders placed on each side of the market
+    pub order_num: u64,
+    /// The price range covered by the orders, 5 => 5%
+    pub depth: u64,
+    /// The minimum order size, 1 -> 0.000001
+    pub min_size: u64,
+    /// The vol max cut ratio numerator, `sys_decimal_value` as denominator
+    pub vol_max_cut_ratio: u64,
+    /// The amount wave numerator, `sys_decimal_value` as denominator
+    pub amount_wave: u64,
+    /// The coin lot size of the market
+    pub coin_lot_size: u64,
+    /// The pc lot size of the market
+    pub pc_lot_size: u64,
+    /// The lower bound of the order price as a multiple of the minimum price step
+    pub min_price_multiplier: u64,
+    /// The upper bound of the order price as a multiple of the minimum price step
+    pub max_price_multiplier: u64,
+    /// The system decimal value used to normalize coin and pc amounts
+    pub sys_decimal_value: u64,
+    /// The unix timestamp from which swaps are allowed
+    pub pool_open_time: u64,
+    /// The unix timestamp at which the pool switches from orderbook only to initialized
+    pub orderbook_to_init_time: u64,
+    /// The admin of the pool
+    #[serde(with = "field_as_string")]
+    pub amm_owner: Pubkey,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct PriceTick {
+    /// The price of the coin in terms of pc when selling coin, including the pool fee
+    pub bid: f64,
+    /// The price of the coin in terms of pc when buying coin, including the pool fee
+    pub ask: f64,
+    /// The spot price of the coin in terms of pc
+    pub mid: f64,
+    /// The slot of the vault update that produced this tick
+    pub slot: u64,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct VaultSample {
+    /// The checkpoint slot
+    pub slot: u64,
+    /// The coin vault balance at the checkpoint
+    pub coin_amount: u64,
+    /// The pc vault balance at the checkpoint
+    pub pc_amount: u64,
+    /// The price of the coin in terms of pc implied by the vault balances
+    pub implied_price: f64,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct RecommendedPool {
+    pub pool_id: Pubkey,
+    /// The composite score, the sum of the three components below
+    pub score: f64,
+    /// The weighted liquidity component, relative to the deepest candidate
+    pub liquidity_score: f64,
+    /// The weighted inverse fee component, relative to the cheapest candidate
+    pub fee_score: f64,
+    /// The weighted inverse volatility component, relative to the most stable candidate
+    pub volatility_score: f64,
+    /// The liquidity of the pool in usd
+    pub tvl: f64,
+    /// The swap fee of the pool in basis points
+    pub fee_bps: f64,
+    /// The 24h price range relative to the current price
+    pub volatility: f64,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct UserLpPosition {
+    pub pool_id: Pubkey,
+    pub lp_mint: Pubkey,
+    pub coin_mint: Pubkey,
+    pub pc_mint: Pubkey,
+    pub coin_decimals: u64,
+    pub pc_decimals: u64,
+    /// The amount of lp tokens held
+    pub lp_balance: u64,
+    /// The coin amount the lp tokens can be redeemed for
+    pub coin_share: u64,
+    /// The pc amount the lp tokens can be redeemed for
+    pub pc_share: u64,
+}
+
+#[cfg(feature = "cex-comparison")]
+#[derive(Clone, Copy, Debug)]
+pub struct PriceDeviation {
+    /// The spot price of the coin in terms of pc in the pool
+    pub amm_price: f64,
+    /// The price reported by the centralized exchange
+    pub cex_price: f64,
+    /// How far the amm price is above (positive) or below (negative) the cex price
+    pub deviation_bps: i64,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct BaseTokenExposure {
+    pub base_mint: Pubkey,
+    /// The value of the lp positions in pools with this base token
+    pub value_usd: f64,
+    /// The share of the total lp value
+    pub share_pct: f64,
+    /// Whether the share exceeds the concentration threshold
+    pub is_concentrated: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct ConcentrationRiskReport {
+    /// The value of all lp positions
+    pub total_value_usd: f64,
+    /// The exposure per base token, largest first
+    pub exposures: Vec<BaseTokenExposure>,
+}
+
+#[derive(Debug)]
+pub struct ExecutedPrice {
+    /// The change of the trader's coin balance
+    pub base_amount_delta: i64,
+    /// The change of the trader's pc balance
+    pub quote_amount_delta: i64,
+    /// The fill price of the coin in terms of pc, adjusted for decimals
+    pub execution_price: f64,
+    pub direction: SwapDirection,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct FeeTierQuote {
+    /// The pool with the best output in this fee tier
+    pub pool_id: Pubkey,
+    /// The swap fee of the tier in basis points
+    pub fee_bps: u64,
+    /// The output of the trade after fees
+    pub net_output: u64,
+    /// The price impact of the trade, excluding the fee
+    pub price_impact_bps: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct FeeTierRecommendation {
+    /// The tier with the highest net output
+    pub best: FeeTierQuote,
+    /// All available tiers, highest net output first
+    pub tiers: Vec<FeeTierQuote>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct PoolRiskScore {
+    /// Days since the pool opened for trading
+    pub age_days: f64,
+    /// The total value locked in the pool
+    pub tvl_usd: f64,
+    /// The share of the lp supply held by the largest holder, from 0 to 1
+    pub lp_concentration_score: f64,
+    /// Whether the pool is listed by the raydium api
+    pub is_verified: bool,
+    /// The coefficient of variation of the fill prices over the last hour
+    pub recent_price_volatility: f64,
+    /// The weighted score from 0 (high risk) to 10 (low risk)
+    pub composite_score: f64,
+}
+
+impl PoolRiskScore {
+    /// Normalizes each metric to [0, 1] (higher is safer) and weights them by `RISK_SCORE_WEIGHTS`
+    pub fn new(
+        age_days: f64,
+        tvl_usd: f64,
+        lp_concentration_score: f64,
+        is_verified: bool,
+        recent_price_volatility: f64,
+    ) -> Self {
+        let components = [
+            (age_days / 30.0).min(1.0),
+            (tvl_usd.max(1.0).log10() / 6.0).min(1.0),
+            1.0 - lp_concentration_score,
+            if is_verified { 1.0 } else { 0.0 },
+            1.0 - (recent_price_volatility / 0.05).min(1.0),
+        ];
+        let composite_score = components
+            .iter()
+            .zip(RISK_SCORE_WEIGHTS)
+            .map(|(component, weight)| component * weight)
+            .sum::<f64>()
+            * 10.0;
+
+        Self {
+            age_days,
+            tvl_usd,
+            lp_concentration_score,
+            is_verified,
+            recent_price_volatility,
+            composite_score,
+        }
+    }
+}
+
+/// A quote in the format of Jupiter's `QuoteResponse`
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JupiterCompatibleQuote {
+    #[serde(with = "field_as_string")]
+    pub input_mint: Pubkey,
+    #[serde(with = "field_as_string")]
+    pub in_amount: u64,
+    #[serde(with = "field_as_string")]
+    pub output_mint: Pubkey,
+    #[serde(with = "field_as_string")]
+    pub out_amount: u64,
+    #[serde(with = "field_as_string")]
+    pub other_amount_threshold: u64,
+    /// `ExactIn` or `ExactOut`
+    pub swap_mode: String,
+    pub slippage_bps: u16,
+    /// The price impact as a fraction, like jupiter reports it
+    #[serde(with = "field_as_string")]
+    pub price_impact_pct: f64,
+    pub route_plan: Vec<JupiterRoutePlanStep>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JupiterRoutePlanStep {
+    pub swap_info: JupiterSwapInfo,
+    /// The share of the input routed through this step
+    pub percent: u8,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JupiterSwapInfo {
+    #[serde(with = "field_as_string")]
+    pub amm_key: Pubkey,
+    pub label: String,
+    #[serde(with = "field_as_string")]
+    pub input_mint: Pubkey,
+    #[serde(with = "field_as_string")]
+    pub output_mint: Pubkey,
+    #[serde(with = "field_as_string")]
+    pub in_amount: u64,
+    #[serde(with = "field_as_string")]
+    pub out_amount: u64,
+    #[serde(with = "field_as_string")]
+    pub fee_amount: u64,
+    #[serde(with = "field_as_string")]
+    pub fee_mint: Pubkey,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct PositionDelta {
+    /// The change of the coin amount the position can be redeemed for
+    pub coin_delta: i64,
+    /// The change of the pc amount the position can be redeemed for
+    pub pc_delta: i64,
+    /// The share of the coin swap fees earned by the position
+    pub fee_earnings_coin: u64,
+    /// The share of the pc swap fees earned by the position
+    pub fee_earnings_pc: u64,
+    /// The change of the position value at current prices
+    pub net_pnl_usd: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct TargetOrdersState {
+    /// The address of the target orders account
+    pub target_orders: Pubkey,
+    /// The coin amount the amm aims to hold (`target_x`)
+    pub target_coin_amount: u128,
+    /// The pc amount the amm aims to hold (`target_y`)
+    pub target_pc_amount: u128,
+    /// The coin reserve the next pnl is computed against (`calc_pnl_x`)
+    pub pnl_coin_baseline: u128,
+    /// The pc reserve the next pnl is computed against (`calc_pnl_y`)
+    pub pnl_pc_baseline: u128,
+    /// The coin amount currently placed on the orderbook (`placed_x`)
+    pub placed_coin_amount: u128,
+    /// The pc amount currently placed on the orderbook (`placed_y`)
+    pub placed_pc_amount: u128,
+    /// The valid buy orders as (price, volume) in lots
+    pub buy_orders: Vec<(u64, u64)>,
+    /// The valid sell orders as (price, volume) in lots
+    pub sell_orders: Vec<(u64, u64)>,
+    pub last_order_numerator: u64,
+    pub last_order_denominator: u64,
+}
+
+impl TargetOrdersState {
+    /// Whether the amm currently has orders on the orderbook that affect its quotes
+    pub fn has_active_orders(&self) -> bool {
+        !self.buy_orders.is_empty() || !self.sell_orders.is_empty()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RebalancingCost {
+    /// The swap fees paid across all planned swaps
+    pub total_fees_usd: f64,
+    /// The value lost to price impact across all planned swaps
+    pub total_slippage_usd: f64,
+    /// The total cost of the rebalance
+    pub net_cost_usd: f64,
+    /// The swaps to execute, in order
+    pub instructions_plan: Vec<SwapInput>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DepthChartData {
+    /// (price in pc, cumulative coin sold into the pool) below the spot price, nearest first
+    pub bids: Vec<(f64, f64)>,
+    /// (price in pc, cumulative coin bought from the pool) above the spot price, nearest first
+    pub asks: Vec<(f64, f64)>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct ApyEstimate {
+    /// The annualized fee income as a percentage of tvl
+    pub fee_apy_pct: f64,
+    /// The annualized return of holding the deposited tokens
+    pub price_appreciation_apy_pct: f64,
+    /// The sum of the fee income, the appreciation and the impermanent loss drag
+    pub total_apy_pct: f64,
+    /// The annualized impermanent loss, non-positive
+    pub impermanent_loss_drag_pct: f64,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct LpEntrySignal {
+    /// The current price of the coin in terms of pc
+    pub current_price: f64,
+    /// The mean of the sampled historical prices
+    pub historical_mean: f64,
+    /// How far the current price is from the mean
+    pub deviation_from_mean_pct: f64,
+    pub signal: EntrySignal,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TokenBalance {
+    /// The token mint, or the system program id for native sol
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub decimals: u8,
+    /// The value of the balance in usd, zero if no price is available
+    pub value_usd: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct UserPortfolioSummary {
+    /// The combined value of the lp positions and token balances in usd
+    pub total_value_usd: f64,
+    pub lp_positions: Vec<UserLpPosition>,
+    /// Token balances excluding lp tokens, sorted by value descending
+    pub raw_token_balances: Vec<TokenBalance>,
+    /// The change in value over the last 24 hours, if price history is available
+    pub pnl_24h_usd: Option<f64>,
+}
+
+/// The rent, in lamports, of the accounts created alongside a new amm pool
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct PoolCreationCost {
+    pub total_lamports_required: u64,
+    pub amm_info_rent: u64,
+    /// The rent of both the coin and pc vaults
+    pub vaults_rent: u64,
+    pub lp_mint_rent: u64,
+    pub open_orders_rent: u64,
+    pub target_orders_rent: u64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct PoolUpgradeStatus {
+    pub current_version: u64,
+    /// Whether the pool runs on the latest amm version
+    pub is_latest: bool,
+    /// The liquidity pool program owning the pool account
+    #[serde(with = "field_as_string")]
+    pub program_id: Pubkey,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct CoinToPcRatio {
+    /// The coin amount owned by the pool
+    pub coin_amount: u64,
+    /// The pc amount owned by the pool
+    pub pc_amount: u64,
+    /// The coin amount divided by the pc amount, adjusted for decimals
+    pub ratio: f64,
+    /// How far the ratio is above (positive) or below (negative) 1
+    pub deviation_from_parity_bps: i64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct VaultVerification {
+    /// Whether the coin vault is a token account owned by the amm authority
+    pub coin_vault_owner_valid: bool,
+    /// Whether the pc vault is a token account owned by the amm authority
+    pub pc_vault_owner_valid: bool,
+    /// Whether either vault failed verification
+    pub is_tampered: bool,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct PoolStateDiff {
+    /// The change of the coin vault balance between the two slots
+    pub coin_delta: i64,
+    /// The change of the pc vault balance between the two slots
+    pub pc_delta: i64,
+    /// The net amount of lp tokens minted (positive) or burned (negative) between the two slots
+    pub lp_supply_delta: i64,
+    /// The change of the pool price between the two slots
+    pub implied_price_delta_bps: i64,
+    /// The number of pool transactions landed between the two slots
+    pub num_transactions_between: u64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct ExchangeRate {
+    /// The amount of the output token received for one input token, adjusted for decimals
+    pub rate: f64,
+    /// The amount of the input token received for one output token, adjusted for decimals
+    pub inverse_rate: f64,
+    /// The pool the rate was read from
+    #[serde(with = "field_as_string")]
+    pub pool_id: Pubkey,
+}
+
+#[derive(Clone, Debug)]
+pub struct BestRoute {
+    pub route_type: RouteType,
+    /// The expected output amount of the route, before slippage
+    pub expected_output: u64,
+    /// The instructions executing the route for the owner, in execution order
+    pub instructions: Vec<Instruction>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct AnchorPrice {
+    /// The price implied by the pnl baseline of the target orders account, adjusted for decimals
+    pub target_price: f64,
+    /// The spot price of the coin in terms of pc, adjusted for decimals
+    pub current_price: f64,
+    /// How far the current price is above (positive) or below (negative) the target price
+    pub delta_bps: i64,
+    pub is_above_target: bool,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct ProtocolLiquidityInfo {
+    /// The lp tokens held by the protocol addresses
+    pub protocol_lp_tokens: u64,
+    /// The lp tokens held by everyone else
+    pub community_lp_tokens: u64,
+    /// The share of the lp supply held by the protocol addresses
+    pub protocol_share_bps: u64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct VaultUtilization {
+    /// The coin amount owned by the pool, excluding pnl that has yet to be taken
+    pub total_coin_vault: u64,
+    /// The part of the coin amount placed in the openbook open orders
+    pub coin_in_open_orders: u64,
+    /// The part of the coin amount held directly by the pool
+    pub coin_available: u64,
+    /// The share of the coin amount placed in the open orders, in percent
+    pub utilization_pct: f64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct PerformanceScore {
+    /// The tvl of the pool on a log scale, between 0 and 1
+    pub liquidity_score: f64,
+    /// The annualized fee yield of the pool, between 0 and 1
+    pub yield_score: f64,
+    /// The price stability of the pool over the last hour, between 0 and 1
+    pub stability_score: f64,
+    /// The weighted sum of the component scores, between 0 and 1
+    pub composite: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct DcaPlan {
+    /// The swaps of the plan, the i-th swap is due at `start + i * interval`
+    pub orders: Vec<SwapInput>,
+    /// When the first swap is due
+    pub start: Instant,
+    /// The time between two swaps
+    pub interval: Duration,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct TokenFlow {
+    /// The coin taken out of the pool by traders, negative when more coin was sold than bought
+    pub net_coin_bought: i64,
+    /// The pc taken out of the pool by traders, negative when more pc was paid than received
+    pub net_pc_bought: i64,
+    /// The pc paid into the pool to buy coin
+    pub buy_volume: u64,
+    /// The pc received from the pool by selling coin
+    pub sell_volume: u64,
+    /// The buy volume divided by the sell volume, 1 when there were no swaps
+    pub buy_sell_ratio: f64,
+}
+
+impl TokenFlow {
+    /// Whether the buy volume exceeds the sell volume by more than 50%
+    pub fn is_strong_buying_pressure(&self) -> bool {
+        self.buy_sell_ratio > STRONG_BUYING_PRESSURE_RATIO
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct WashTradingScore {
+    /// The share of swaps that are part of a same slot round trip, between 0 and 1
+    pub suspicion_level: f64,
+    /// The number of swaps where the wallet both bought and sold within the same slot
+    pub round_trip_txn_count: u64,
+    /// The number of wallets that both bought and sold during the lookback
+    pub same_wallet_both_sides_count: u64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct SupplyRatio {
+    /// The coin amount owned by the pool
+    pub coin_pool_depth: u64,
+    /// The total supply of the coin mint
+    pub coin_total_supply: u64,
+    /// The share of the coin supply held by the pool, in percent
+    pub pool_depth_pct: f64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct HistoricalPoolState {
+    /// The coin vault balance at the slot
+    pub coin_vault_amount: u64,
+    /// The pc vault balance at the slot
+    pub pc_vault_amount: u64,
+    /// The price of the coin in terms of pc implied by the vault balances
+    pub implied_price: f64,
+}
+
+/// Statistics of the spot prices of the coin in terms of pc across all pools of a pair
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct ConsensusPrice {
+    pub median_price: f64,
+    pub mean_price: f64,
+    pub min_price: f64,
+    pub max_price: f64,
+    /// The number of pools the statistics are computed from
+    pub pool_count: usize,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct BurnedLp {
+    /// The lp tokens burned from the lp mint, which can never be withdrawn
+    pub burned_lp_tokens: u64,
+    /// The share of the lp minted by the pool that is burned, in percent
+    pub percentage_burned: f64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct MintAuthorityInfo {
+    /// The authority allowed to mint new tokens
+    #[serde(with = "option_field_as_string")]
+    pub authority: Option<Pubkey>,
+    /// Whether new tokens can still be minted
+    pub is_mintable: bool,
+    /// Whether token accounts of the mint can be frozen
+    pub is_freeze_authority_set: bool,
+}
+
+impl MintAuthorityInfo {
+    /// Whether the supply of the mint can be inflated
+    pub fn is_high_risk(&self) -> bool {
+        self.is_mintable
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct ReserveRatio {
+    /// The product of the coin and pc amounts owned by the pool
+    pub k_invariant: u128,
+    /// The product recorded by the previous call for the same pool
+    pub previous_k: Option<u128>,
+    /// The change of the product per lp token since the previous call, in percent
+    pub k_drift_pct: f64,
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct AccumulatedFees {
+    /// The fees charged on swaps paying coin into the pool
+    pub coin_fees: u64,
+    /// The fees charged on swaps paying pc into the pool
+    pub pc_fees: u64,
+    pub num_swap_events: u64,
+}
+
+impl AccumulatedFees {
+    /// Sums the fees of the swap events at the given fee rate, other events are ignored
+    pub fn from_events(events: &[PoolEvent], fee_numerator: u64, fee_denominator: u64) -> Self {
+        let fee_of = |amount: u64| {
+            (amount as u128 * fee_numerator as u128)
+                .checked_div(fee_denominator as u128)
+                .unwrap_or_default() as u64
+        };
+        let mut fees = Self::default();
+        for event in events {
+            let PoolEvent::Swap {
+                direction, amount, ..
+            } = *event
+            else {
+                continue;
+            };
+            match direction {
+                SwapDirection::Coin2PC => fees.coin_fees += fee_of(amount),
+                SwapDirection::PC2Coin => fees.pc_fees += fee_of(amount),
+            }
+            fees.num_swap_events += 1;
+        }
+        fees
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct MarketMakerPnl {
+    /// The coin amount the lp position can be redeemed for
+    pub current_coin: u64,
+    /// The pc amount the lp position can be redeemed for
+    pub current_pc: u64,
+    /// The share of the pool fees since the entry attributable to the position
+    pub fee_earnings: AccumulatedFees,
+    /// The impermanent loss since the entry in percent, non-positive
+    pub impermanent_loss_pct: f64,
+    /// The current value of the position minus the value of the initial deposit at entry, in usd
+    pub net_pnl_usd: f64,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ArbitrageSpread {
+    /// The spot price of the coin of pool a in terms of its pc, in pool a
+    pub pool_a_price: f64,
+    /// The spot price of the coin of pool a in terms of its pc, in pool b
+    pub pool_b_price: f64,
+    /// How far the price in pool b is above (positive) or below (negative) the price in pool a
+    pub spread_bps: i64,
+    /// The direction to trade on pool a, `None` if the spread does not cover the fees
+    pub profitable_direction: Option<SwapDirection>,
+    /// The absolute spread minus the swap fees of both pools
+    pub estimated_profit_bps_after_fees: i64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct MigrationStatus {
+    /// Whether the pool is disabled and a clmm pool exists for the same pair
+    pub is_migrated: bool,
+    /// The deepest clmm pool of the same pair
+    #[serde(with = "option_field_as_string")]
+    pub new_pool_id: Option<Pubkey>,
+    /// The slot of the last successful transaction of the pool
+    pub migration_slot: Option<u64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct AggregatedQuote {
+    /// The quote of each pool for its portion of the amount
+    pub quotes: Vec<RaydiumAmmQuote>,
+    /// The input amount summed over all pools
+    pub total_input: u64,
+    /// The output amount summed over all pools, before slippage
+    pub total_output: u64,
+    /// The output received per input token, adjusted for decimals
+    pub effective_price: f64,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct BlockTimeEstimate {
+    /// The mean slot duration over the recent performance samples, in milliseconds
+    pub mean_slot_duration_ms: f64,
+    pub current_slot: u64,
+    /// The estimated wall-clock time at which the next block is produced
+    pub estimated_next_block_time: SystemTime,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct StabilityIndex {
+    /// The usd value of the cheapest swap that causes a 1% price impact
+    pub cost_to_move_1pct_usd: f64,
+    /// The usd value of the cheapest swap that causes a 5% price impact
+    pub cost_to_move_5pct_usd: f64,
+    /// The usd value of the cheapest swap that causes a 10% price impact
+    pub cost_to_move_10pct_usd: f64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct UserTokenUtilization {
+    /// The combined value of the wallet balances, lp positions and open orders
+    pub total_balance_usd: f64,
+    /// The value of the tokens redeemable from the lp positions
+    pub in_lp_positions_usd: f64,
+    /// The value of the tokens held in openbook open orders accounts
+    pub in_pending_transactions_usd: f64,
+    /// The value of the token balances not committed anywhere
+    pub idle_usd: f64,
+    /// The share of the total balance held in lp positions and open orders
+    pub utilization_pct: f64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct PriceMomentum {
+    /// The spot price of the coin in terms of pc
+    pub current_price: f64,
+    /// The exponential moving average of the price over the requested period
+    pub ema: f64,
+    /// The current price divided by the ema, minus one
+    pub momentum: f64,
+    pub trend: Trend,
+}
+
+/// The coin liquidity between the spot price and a target price, in raw coin amounts
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct PoolDepth {
+    /// The coin that can be bought before the price rises to the target
+    pub buy_depth_tokens: u64,
+    /// The coin that can be sold before the price falls to the target
+    pub sell_depth_tokens: u64,
+    pub buy_depth_usd: f64,
+    pub sell_depth_usd: f64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct PriceExtremes {
+    /// The highest sampled price over the lookback period, in pc per coin
+    pub high: f64,
+    /// The lowest sampled price over the lookback period, in pc per coin
+    pub low: f64,
+    pub high_slot: u64,
+    pub low_slot: u64,
+    /// The percentage change from the high to the current price
+    pub current_vs_high_pct: f64,
+    /// The percentage change from the low to the current price
+    pub current_vs_low_pct: f64,
+}
+
+/// Vault balances scored against their median over the last 7 days, each between 0 and 1
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct VaultHealthScore {
+    pub coin_health: f64,
+    pub pc_health: f64,
+    /// The lower of the two vault scores
+    pub overall: f64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct VaultMetadata {
+    #[serde(with = "field_as_string")]
+    pub coin_vault_pubkey: Pubkey,
+    #[serde(with = "field_as_string")]
+    pub pc_vault_pubkey: Pubkey,
+    /// The owner of the coin vault token account, expected to be the amm authority
+    #[serde(with = "field_as_string")]
+    pub coin_vault_owner: Pubkey,
+    /// The owner of the pc vault token account, expected to be the amm authority
+    #[serde(with = "field_as_string")]
+    pub pc_vault_owner: Pubkey,
+    /// The delegate approved on the coin vault, if any
+    #[serde(with = "option_field_as_string")]
+    pub coin_vault_delegate: Option<Pubkey>,
+    /// The delegate approved on the pc vault, if any
+    #[serde(with = "option_field_as_string")]
+    pub pc_vault_delegate: Option<Pubkey>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ArbitragePath {
+    /// The pools of the cycle in order, with the swap direction taken on each
+    pub hops: Vec<(Pubkey, SwapDirection)>,
+    /// The output of the last hop over the input of the first, after pool fees
+    pub expected_profit_bps: u64,
+    /// The amount of the start mint swapped on the first hop
+    pub required_input: u64,
+}
+
+/// The swaps of a wallet, the coin valued at the pool price of the slot each swap landed in and
+/// the pc at its current price
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct RealizedPnl {
+    /// The value of the tokens paid into the pools
+    pub total_input_usd: f64,
+    /// The value of the tokens received from the pools
+    pub total_output_usd: f64,
+    /// The pool fees included in the input
+    pub total_fees_paid_usd: f64,
+    /// The output minus the input, fees are already deducted from the output
+    pub net_pnl_usd: f64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct LpTokenFairValue {
+    /// The coin redeemable for the lp tokens
+    pub coin_entitlement: u64,
+    /// The pc redeemable for the lp tokens
+    pub pc_entitlement: u64,
+    /// The value of the redeemable tokens in usd
+    pub fair_value_usd: f64,
+    /// How much the api price of the lp tokens exceeds their fair value, if the api prices the lp
+    /// mint
+    pub secondary_market_premium_pct: Option<f64>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct OpenInterest {
+    /// The lp supply tracked by the pool
+    pub total_lp_tokens: u64,
+    /// The number of token accounts holding a non-zero lp balance
+    pub num_lp_holders: u64,
+    /// The share of the lp supply held by the ten largest token accounts
+    pub top10_holders_share_bps: u64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct RebalancingOpportunity {
+    /// The spot price of the coin in terms of pc
+    pub current_pool_price: f64,
+    /// The reference price the pool is expected to return to
+    pub fair_value_price: f64,
+    /// The divergence of the pool price from the fair value price
+    pub divergence_bps: i64,
+    /// The fees of the trade moving the pool back to the fair value price, in basis points of tvl
+    pub expected_rebalancing_fee_bps: f64,
+    pub recommended_action: LpAction,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct ConfirmationDepth {
+    /// The slot of the transaction, once it reached the confirmed commitment
+    pub confirmed_slot: Option<u64>,
+    /// The slot of the transaction, once it reached the finalized commitment
+    pub finalized_slot: Option<u64>,
+    /// The latest processed slot
+    pub current_slot: u64,
+    /// The number of slots produced on top of the confirmed slot
+    pub confirmations: Option<u64>,
+    pub is_finalized: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct EmergencyHaltStatus {
+    pub is_halted: bool,
+    /// Why the pool is considered halted
+    pub halt_reason: Option<String>,
+    /// The slot of the last successful pool transaction, which changed the status of a halted pool
+    pub halt_slot: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct AnomalyScore {
+    /// The number of standard deviations between the amm price and the mean reference price
+    pub z_score: f64,
+    /// Whether the z-score exceeds 3 in either direction
+    pub is_anomalous: bool,
+    /// The spot price of the pool, in pc per coin
+    pub amm_price: f64,
+    /// The mean of the reference prices
+    pub reference_mean: f64,
+}
+
+/// Swap volume over the lookback period divided by the average vault balance
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct TokenVelocity {
+    pub coin_velocity: f64,
+    pub pc_velocity: f64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct TokenConcentration {
+    /// The share of the pool value held in the coin vault
+    pub coin_value_pct: f64,
+    /// The share of the pool value held in the pc vault
+    pub pc_value_pct: f64,
+    /// Whether one vault holds more than 70% of the pool value
+    pub is_imbalanced: bool,
+    /// The mint of the vault holding more than 70% of the pool value
+    #[serde(with = "option_field_as_string")]
+    pub dominant_token: Option<Pubkey>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct FlashSwapFeasibility {
+    /// Whether the pool can pay out the borrowed amount
+    pub is_feasible: bool,
+    /// The most the pool can pay out of the borrowed mint
+    pub max_borrowable: u64,
+    /// The amount of the other mint of the pool to pay back, including fees. Zero when the borrow
+    /// is not feasible
+    pub required_repayment: u64,
+    /// The swap fee charged by the pool in basis points
+    pub fee_bps: u64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct TradeSizeRecommendation {
+    /// The input amount with the lowest execution cost per unit of output
+    pub optimal_amount: u64,
+    /// The execution cost per ui unit of output, in usd
+    pub cost_per_unit_usd: f64,
+    /// The transaction fee plus the output lost to the pool fee and price impact, in usd
+    pub total_execution_cost_usd: f64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct ProtocolFeeRevenue {
+    /// The protocol fee taken from coin paid into the pool
+    pub protocol_fee_coin: u64,
+    /// The protocol fee taken from pc paid into the pool
+    pub protocol_fee_pc: u64,
+    /// The share of the swap fees going to the protocol rather than the lps
+    pub protocol_fee_share_of_total_pct: f64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CompetitorComparison {
+    /// The output of the swap on raydium
+    pub raydium_output: u64,
+    /// The output of the swap by venue, for the enabled competitor venues that returned a quote
+    pub competitor_outputs: HashMap<String, u64>,
+    /// The venue with the largest output
+    pub best_venue: String,
+    /// How much the raydium output exceeds (positive) or falls short of (negative) the best
+    /// competitor output
+    pub raydium_advantage_bps: i64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct LiquidityEvent {
+    pub event_type: LiquidityEventType,
+    /// The amount of lp tokens minted or burned
+    pub lp_tokens_delta: u64,
+    /// The lp tokens minted or burned as a share of the lp supply before the event
+    pub pct_change: f64,
+    /// The fee payer of the transaction
+    #[serde(with = "field_as_string")]
+    pub wallet: Pubkey,
+    pub slot: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PoolComparisonTable {
+    /// The compared pools, best output first
+    pub rows: Vec<PoolComparisonRow>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct PoolComparisonRow {
+    #[serde(with = "field_as_string")]
+    pub pool_id: Pubkey,
+    /// The output of the swap after fees
+    pub output_amount: u64,
+    /// The price impact of the swap, excluding the swap fee
+    pub price_impact_bps: f64,
+    /// The swap fee charged by the pool
+    pub fee_bps: u64,
+    pub tvl_usd: f64,
+    /// The position of the pool by output amount, starting at 1
+    pub rank: usize,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct MarketMicrostructure {
+    /// Twice the average deviation of the execution price from the pool price before the swap
+    pub effective_spread_bps: u64,
+    /// The price change per unit of signed pc volume, fitted by linear regression
+    pub kyle_lambda: f64,
+    /// The average absolute log return per unit of pc volume
+    pub amihud_illiquidity: f64,
+    /// The spread implied by the serial covariance of execution price changes, in pc per coin
+    pub roll_spread_estimate: f64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct PositionSizing {
+    /// The share of the capital the kelly criterion bets, after the pool fees
+    pub kelly_fraction: f64,
+    /// Half of the kelly position
+    pub recommended_position_usd: f64,
+    /// The full kelly position, capped at 25% of the capital
+    pub max_position_usd: f64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct TokenRiskScore {
+    /// Whether the mint authority can still mint new tokens
+    pub is_mintable: bool,
+    /// The share of the supply held by the largest token account
+    pub top1_holder_pct: f64,
+    /// The total value locked in the amm v4 pools trading the token
+    pub liquidity_depth_usd: f64,
+    /// Days since the oldest pool trading the token opened
+    pub pool_age_days: f64,
+    /// The mintability, top holder, liquidity and pool age risks, from 0 (safe) to 1 (dangerous)
+    pub component_scores: [f64; 4],
+    /// The weights of the component scores, summing to 1
+    pub weights: [f64; 4],
+    /// The weighted score from 0 (safe) to 100 (dangerous)
+    pub risk_score: f64,
+}
+
+/// The constant product curve of a pool for one swap direction
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct SlippageCurve {
+    /// The invariant `x * y`
+    pub k: f64,
+    /// The reserve of the input token
+    pub x: f64,
+    /// The reserve of the output token
+    pub y: f64,
+}
+
+impl SlippageCurve {
+    /// The slippage of an exact-in swap of `amount` in basis points, excluding the swap fee.
+    /// The output `y - k / (x + amount)` falls short of the spot output `amount * y / x` by
+    /// `amount / (x + amount)`, which is smooth in the amount.
+    pub fn slippage_at_amount(&self, amount: u64) -> f64 {
+        let amount = amount as f64;
+        if self.x + amount <= 0.0 {
+            return 0.0;
+        }
+        amount / (self.x + amount) * 10_000.0
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct VaultStaleness {
+    /// The slot of the last transaction that moved the vault balances, if one was found
+    pub last_trade_slot: Option<u64>,
+    pub slots_since_last_trade: u64,
+    /// Whether the vaults have not moved for more than 1000 slots
+    pub is_stale: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct JitoBundleStatus {
+    pub status: BundleStatusEnum,
+    /// The slot the bundle landed in
+    pub slot: Option<u64>,
+    /// The commitment reached by the block the bundle landed in, `None` while pending or when
+    /// the block engine reports an unknown level
+    pub confirmation_level: Option<TransactionConfirmationStatus>,
+    /// The signatures of the transactions in the bundle, empty while pending
+    pub transactions: Vec<Signature>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct FeeHistoryEntry {
+    /// The slot the new fee was set in
+    pub slot: u64,
+    /// The new swap fee numerator
+    pub fee_numerator: u64,
+    /// The new swap fee denominator
+    pub fee_denominator: u64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct RebalancingFrequency {
+    /// The rebalancing interval with the lowest combined rebalancing cost and impermanent loss
+    pub optimal_interval_hours: f64,
+    /// The impermanent loss avoided per day minus the cost of rebalancing at the optimal interval,
+    /// negative when rebalancing does not pay off
+    pub expected_net_benefit_bps_per_day: f64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct ImpliedFundingRate {
+    pub hourly_rate_pct: f64,
+    pub daily_rate_pct: f64,
+    /// The fee yield grossed up by the impermanent loss over the slots covered by the fetched
+    /// transactions
+    pub annualized_rate_pct: f64,
+    /// Whether the lookback period held more transactions than were fetched, in which case the
+    /// rate only reflects the most recent part of the period
+    pub is_truncated: bool,
+}
+
+/// The circuit breaker state of a pool. The amm v4 program has no circuit breaker, so this is
+/// never tripped for amm v4 pools
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct CircuitBreakerStatus {
+    pub is_tripped: bool,
+    /// The price deviation that trips the circuit breaker
+    pub trigger_price_deviation_bps: u64,
+    /// The slots until trading resumes after the circuit breaker tripped
+    pub cooldown_remaining_slots: u64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct CloneCandidate {
+    #[serde(with = "field_as_string")]
+    pub suspicious_pool_id: Pubkey,
+    /// How similar the token symbols are to those of the legitimate pool, from 0 to 1
+    pub similarity_score: f64,
+    #[serde(with = "field_as_string")]
+    pub coin_mint: Pubkey,
+    #[serde(with = "field_as_string")]
+    pub pc_mint: Pubkey,
+    /// Whether both mints are on the official raydium token list
+    pub is_verified: bool,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct PoolAuditReport {
+    #[serde(with = "field_as_string")]
+    pub pool_id: Pubkey,
+    /// Whether the pool passes the verification policy of the executor
+    pub is_verified: bool,
+    /// Days since the pool opened for trading
+    pub pool_age_days: f64,
+    pub coin_mint_authority: MintAuthorityInfo,
+    pub pc_mint_authority: MintAuthorityInfo,
+    /// Whether the authority of the pool keys derives from the nonce stored in the pool
+    pub is_authority_nonce_valid: bool,
+    pub vault_verification: VaultVerification,
+    /// How the lp supply is spread across holders
+    pub lp_concentration: OpenInterest,
+    pub upgrade_status: PoolUpgradeStatus,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct MevExposure {
+    /// The input amount the attacker swaps ahead of the victim
+    pub front_run_size: u64,
+    /// The output of the front run, swapped back by the attacker after the victim
+    pub back_run_size: u64,
+    /// The profit of the attacker before transaction fees
+    pub attacker_profit_lamports: u64,
+    /// How much less the victim receives than without the attack
+    pub victim_extra_slippage_bps: u64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct ImpermanentGainLoss {
+    /// The value of the tokens deposited into the pool had they been held
+    pub hodl_value_usd: f64,
+    /// The value of the pool liquidity, excluding fees
+    pub lp_value_usd: f64,
+    /// The lp value minus the hodl value
+    pub difference_usd: f64,
+    /// Whether the lp value exceeds the hodl value
+    pub is_gain: bool,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct UnlockImpact {
+    /// The value of the unlocked coin
+    pub estimated_sell_pressure_usd: f64,
+    /// The price impact of selling all unlocked coin into the pool, excluding the swap fee
+    pub price_impact_if_all_sold_bps: u64,
+    /// The share of the pc vault paid out for the unlocked coin
+    pub vault_drain_pct: f64,
+    /// Whether selling all unlocked coin moves the price by at most 5%
+    pub is_pool_sufficient_to_absorb: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct RoutingRecommendation {
+    pub route_type: RouteType,
+    /// The input of the recommended route before slippage. For exact-out swaps this is the
+    /// required input, otherwise the amount specified
+    pub expected_input: u64,
+    /// The output of the recommended route before slippage. For exact-out swaps this is the
+    /// amount specified
+    pub expected_output: u64,
+    /// The slippage tolerance for the urgency level
+    pub slippage_bps: u16,
+    /// The compute unit price for the urgency level
+    pub priority_fee: PriorityFeeConfig,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct PoolHealthDashboard {
+    /// The address of the amm pool
+    #[serde(with = "field_as_string")]
+    pub pool_id: Pubkey,
+    /// The spot price of the coin in terms of pc
+    pub spot_price: f64,
+    /// The total value locked in the pool
+    pub tvl_usd: f64,
+    /// The swap fee in basis points
+    pub fee_rate_bps: u64,
+    /// Days since the pool opened for trading
+    pub pool_age_days: f64,
+    /// The slot of the most recent successful transaction touching the pool
+    pub last_trade_slot: Option<u64>,
+    /// The usd value of the coin vault divided by the usd value of the pc vault
+    pub vault_imbalance_ratio: f64,
+    pub stability_index: StabilityIndex,
+    /// The 24 hour price range relative to the current price, if the pool is listed by the api
+    pub volatility_24h: Option<f64>,
+    pub risk_score: PoolRiskScore,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -459,6 +9086,69 @@ pub struct MarketKeys {
     pub vault_signer_key: Pubkey,
 }
 
+// 将 amm 账户数据解析为 AmmInfo
+fn decode_amm_info(data: &[u8]) -> anyhow::Result<raydium_amm::state::AmmInfo> {
+    Ok(
+        transmute_one_pedantic::<super::amm_info::AmmInfo>(transmute_to_bytes(data))
+            .map_err(|e| e.without_src())?
+            .into(),
+    )
+}
+
+// 在当前 tick 区间内按恒定流动性 liquidity 计算 whirlpool 的 exact-in 输出,不跨越 tick
+// sqrt_price 为 Q64.64 格式的 sqrt(token b / token a),fee_rate 以百万分之一计;a_to_b 时价格下降
+#[cfg(feature = "orca-comparison")]
+fn whirlpool_output(
+    liquidity: u128,
+    sqrt_price_x64: u128,
+    fee_rate: u16,
+    a_to_b: bool,
+    amount: u64,
+) -> u64 {
+    let liquidity = liquidity as f64;
+    let sqrt_price = sqrt_price_x64 as f64 / 2f64.powi(64);
+    if liquidity <= 0.0 || sqrt_price <= 0.0 {
+        return 0;
+    }
+    let amount_in = amount as f64 * (1.0 - fee_rate as f64 / 1_000_000.0);
+    let output = if a_to_b {
+        let next_sqrt_price = liquidity * sqrt_price / (liquidity + amount_in * sqrt_price);
+        liquidity * (sqrt_price - next_sqrt_price)
+    } else {
+        let next_sqrt_price = sqrt_price + amount_in / liquidity;
+        liquidity * (1.0 / sqrt_price - 1.0 / next_sqrt_price)
+    };
+    output.max(0.0).floor() as u64
+}
+
+// 解析 openbook 订单簿(bids 或 asks)账户中的所有挂单,返回 (以 lot 计的价格, 以 coin lot 计的数量)
+// 账户数据为 5 字节的 "serum" 头、8 字节的 account flags 和 32 字节的 slab 头,之后是 72 字节的节点;
+// slab 头的第一个字段 bump_index 为已使用的节点数,tag 为 2 的节点是挂单,其 key 的高 64 位为价格
+#[cfg(feature = "openbook-comparison")]
+fn openbook_orders(data: &[u8]) -> Vec<(u64, u64)> {
+    const NODES_OFFSET: usize = 5 + 8 + 32;
+    const NODE_SIZE: usize = 72;
+    const LEAF_TAG: u32 = 2;
+
+    let Some(header) = data.get(13..NODES_OFFSET) else {
+        return Vec::new();
+    };
+    let bump_index = u64::from_le_bytes(*array_ref![header, 0, 8]) as usize;
+    data.get(NODES_OFFSET..)
+        .unwrap_or_default()
+        .chunks_exact(NODE_SIZE)
+        .take(bump_index)
+        .filter(|node| u32::from_le_bytes(*array_ref![node, 0, 4]) == LEAF_TAG)
+        .map(|node| {
+            let key = u128::from_le_bytes(*array_ref![node, 8, 16]);
+            (
+                (key >> 64) as u64,
+                u64::from_le_bytes(*array_ref![node, 56, 8]),
+            )
+        })
+        .collect()
+}
+
 #[allow(clippy::too_many_arguments)]
 // 定义一个函数，用于生成交换指令
 fn swap_instruction(
@@ -671,3 +9361,137 @@ impl TryFrom<&crate::api_v3::response::ApiV3StandardPoolKeys> for AmmKeys {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn swap_input(amount: u64, mode: SwapExecutionMode) -> SwapInput {
+        SwapInput {
+            input_token_mint: Pubkey::default(),
+            output_token_mint: Pubkey::default(),
+            slippage_bps: 100,
+            amount,
+            mode,
+            market: None,
+        }
+    }
+
+    #[test]
+    fn quote_from_vault_amounts_exact_in() {
+        let (coin_mint, pc_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let quote = RaydiumAmm::quote_from_vault_amounts(
+            1_000_000,
+            1_000_000,
+            25,
+            10_000,
+            9,
+            6,
+            coin_mint,
+            pc_mint,
+            coin_mint,
+            &swap_input(1_000, SwapExecutionMode::ExactIn),
+        )
+        .unwrap();
+        // 扣除 25 bps 手续费(向上取整)后输入 997,输出 997 * 1e6 / (1e6 + 997) 向下取整
+        assert_eq!(quote.other_amount, 996);
+        assert!(quote.other_amount_threshold < quote.other_amount);
+        assert!(quote.amount_specified_is_input);
+        assert_eq!(
+            (quote.input_mint_decimals, quote.output_mint_decimals),
+            (9, 6)
+        );
+        assert_eq!(quote.amm_keys.amm_coin_mint, coin_mint);
+        assert_eq!(quote.amm_keys.amm_pc_mint, pc_mint);
+    }
+
+    #[test]
+    fn quote_from_vault_amounts_exact_out() {
+        let (coin_mint, pc_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let quote = RaydiumAmm::quote_from_vault_amounts(
+            1_000_000,
+            1_000_000,
+            25,
+            10_000,
+            9,
+            6,
+            pc_mint,
+            coin_mint,
+            coin_mint,
+            &swap_input(1_000, SwapExecutionMode::ExactOut),
+        )
+        .unwrap();
+        assert!(quote.other_amount > 1_000);
+        assert!(quote.other_amount_threshold > quote.other_amount);
+        assert!(!quote.amount_specified_is_input);
+        assert_eq!(
+            (quote.input_mint_decimals, quote.output_mint_decimals),
+            (6, 9)
+        );
+        assert_eq!(quote.amm_keys.amm_pc_mint, pc_mint);
+    }
+
+    #[test]
+    fn quote_from_vault_amounts_rejects_unrelated_mints() {
+        let (coin_mint, pc_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let quote = |input_mint, output_mint| {
+            RaydiumAmm::quote_from_vault_amounts(
+                1_000_000,
+                1_000_000,
+                25,
+                10_000,
+                9,
+                6,
+                input_mint,
+                output_mint,
+                coin_mint,
+                &swap_input(1_000, SwapExecutionMode::ExactIn),
+            )
+        };
+        assert!(quote(coin_mint, coin_mint).is_err());
+        assert!(quote(pc_mint, Pubkey::new_unique()).is_err());
+    }
+
+    #[test]
+    fn accumulated_fees_of_a_two_pool_transaction() {
+        use crate::amm::history::{pool_events, ExecutedInstruction};
+        use base64::Engine;
+
+        let swap_log = |amount_in: u64, direction: u64| {
+            let log = (3u8, amount_in, 0u64, direction, 0u64, 0u64, 0u64, 0u64);
+            format!(
+                "Program log: ray_log: {}",
+                base64::engine::general_purpose::STANDARD.encode(bincode::serialize(&log).unwrap())
+            )
+        };
+        let amm = RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID;
+        let [pool, other_pool] = [(); 2].map(|_| Pubkey::new_unique());
+        let swap = |pool_id| ExecutedInstruction {
+            program_id: amm,
+            accounts: vec![spl_token::ID, pool_id],
+            data: vec![],
+        };
+        // 两跳兑换: 先在 other_pool 中把 coin 换成中间代币,再在 pool 中换成目标代币
+        let instructions = [swap(other_pool), swap(pool)];
+        let logs = [
+            format!("Program {} invoke [1]", amm),
+            swap_log(1_000_000, 2),
+            format!("Program {} success", amm),
+            format!("Program {} invoke [1]", amm),
+            swap_log(40_000, 1),
+            format!("Program {} success", amm),
+        ];
+
+        let events = pool_events(&logs, &instructions, &pool, &amm, 7);
+        let fees = AccumulatedFees::from_events(&events, 25, 10_000);
+        assert_eq!(fees.num_swap_events, 1);
+        assert_eq!(fees.coin_fees, 0);
+        assert_eq!(fees.pc_fees, 100);
+
+        let events = pool_events(&logs, &instructions, &other_pool, &amm, 7);
+        let fees = AccumulatedFees::from_events(&events, 25, 10_000);
+        assert_eq!(fees.num_swap_events, 1);
+        assert_eq!(fees.coin_fees, 2_500);
+        assert_eq!(fees.pc_fees, 0);
+    }
+}
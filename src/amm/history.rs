@@ -0,0 +1,608 @@
+//! Reconstructs pool activity from the token balance changes recorded in transaction metadata
+
+use crate::types::AmmPoolStatus;
+use base64::Engine;
+use raydium_amm::instruction::AmmInstruction;
+use raydium_library::amm::utils::SwapDirection;
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::option_serializer::OptionSerializer;
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, UiInstruction, UiTransactionTokenBalance,
+};
+use std::str::FromStr;
+
+/// A transaction that moved funds in or out of the pool vaults
+#[derive(Clone, Debug)]
+pub struct PoolTransaction {
+    /// The transaction signature
+    pub signature: Signature,
+    /// The slot the transaction landed in
+    pub slot: u64,
+    /// The block time of the slot, if known
+    pub block_time: Option<i64>,
+    /// The fee payer of the transaction
+    pub wallet: Pubkey,
+    /// The change of the coin vault balance, positive when coin flowed into the pool
+    pub coin_delta: i64,
+    /// The change of the pc vault balance, positive when pc flowed into the pool
+    pub pc_delta: i64,
+    /// The net amount of lp tokens minted (positive) or burned (negative)
+    pub lp_delta: i64,
+    /// The coin vault balance after the transaction
+    pub coin_vault_after: u64,
+    /// The pc vault balance after the transaction
+    pub pc_vault_after: u64,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum PoolTransactionKind {
+    Swap(SwapDirection),
+    AddLiquidity,
+    RemoveLiquidity,
+    Other,
+}
+
+impl PoolTransaction {
+    pub fn kind(&self) -> PoolTransactionKind {
+        if self.lp_delta > 0 {
+            PoolTransactionKind::AddLiquidity
+        } else if self.lp_delta < 0 {
+            PoolTransactionKind::RemoveLiquidity
+        } else if self.coin_delta > 0 && self.pc_delta < 0 {
+            PoolTransactionKind::Swap(SwapDirection::Coin2PC)
+        } else if self.pc_delta > 0 && self.coin_delta < 0 {
+            PoolTransactionKind::Swap(SwapDirection::PC2Coin)
+        } else {
+            PoolTransactionKind::Other
+        }
+    }
+
+    /// The amount paid into the pool if this is a swap, as (coin_in, pc_in)
+    pub fn swap_amounts_in(&self) -> Option<(u64, u64)> {
+        match self.kind() {
+            PoolTransactionKind::Swap(SwapDirection::Coin2PC) => {
+                Some((self.coin_delta.unsigned_abs(), 0))
+            }
+            PoolTransactionKind::Swap(SwapDirection::PC2Coin) => {
+                Some((0, self.pc_delta.unsigned_abs()))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Parses a confirmed transaction into a [`PoolTransaction`] for the given vaults.
+/// Returns `None` if the transaction failed or did not touch both vaults.
+pub fn parse_pool_transaction(
+    signature: Signature,
+    tx: &EncodedConfirmedTransactionWithStatusMeta,
+    coin_vault: &Pubkey,
+    pc_vault: &Pubkey,
+    lp_mint: &Pubkey,
+) -> Option<PoolTransaction> {
+    let meta = tx.transaction.meta.as_ref()?;
+    if meta.err.is_some() {
+        return None;
+    }
+    let account_keys = transaction_account_keys(tx)?;
+    let pre_balances = Option::<Vec<_>>::from(meta.pre_token_balances.clone()).unwrap_or_default();
+    let post_balances =
+        Option::<Vec<_>>::from(meta.post_token_balances.clone()).unwrap_or_default();
+
+    let balance_of = |balances: &[UiTransactionTokenBalance], key: &Pubkey| -> Option<u64> {
+        balances
+            .iter()
+            .find(|balance| account_keys.get(balance.account_index as usize) == Some(key))
+            .and_then(|balance| balance.ui_token_amount.amount.parse().ok())
+    };
+    let coin_before = balance_of(&pre_balances, coin_vault)?;
+    let coin_after = balance_of(&post_balances, coin_vault)?;
+    let pc_before = balance_of(&pre_balances, pc_vault)?;
+    let pc_after = balance_of(&post_balances, pc_vault)?;
+
+    let lp_mint = lp_mint.to_string();
+    let lp_total = |balances: &[UiTransactionTokenBalance]| -> i64 {
+        balances
+            .iter()
+            .filter(|balance| balance.mint == lp_mint)
+            .filter_map(|balance| balance.ui_token_amount.amount.parse::<i64>().ok())
+            .sum()
+    };
+
+    Some(PoolTransaction {
+        signature,
+        slot: tx.slot,
+        block_time: tx.block_time,
+        wallet: *account_keys.first()?,
+        coin_delta: coin_after as i64 - coin_before as i64,
+        pc_delta: pc_after as i64 - pc_before as i64,
+        lp_delta: lp_total(&post_balances) - lp_total(&pre_balances),
+        coin_vault_after: coin_after,
+        pc_vault_after: pc_after,
+    })
+}
+
+/// Returns the full account list of a transaction, including addresses loaded from lookup tables
+fn transaction_account_keys(tx: &EncodedConfirmedTransactionWithStatusMeta) -> Option<Vec<Pubkey>> {
+    let transaction = tx.transaction.transaction.decode()?;
+    let mut keys = transaction.message.static_account_keys().to_vec();
+    if let Some(OptionSerializer::Some(loaded)) = tx
+        .transaction
+        .meta
+        .as_ref()
+        .map(|meta| &meta.loaded_addresses)
+    {
+        for key in loaded.writable.iter().chain(loaded.readonly.iter()) {
+            keys.push(Pubkey::from_str(key).ok()?);
+        }
+    }
+    Some(keys)
+}
+
+/// A major state change of a pool, decoded from the amm program logs and instructions
+#[derive(Clone, Copy, Debug)]
+pub enum PoolEvent {
+    Swap {
+        direction: SwapDirection,
+        /// The amount paid into the pool
+        amount: u64,
+        slot: u64,
+    },
+    AddLiquidity {
+        lp_minted: u64,
+        slot: u64,
+    },
+    RemoveLiquidity {
+        lp_burned: u64,
+        slot: u64,
+    },
+    StatusChange {
+        new_status: AmmPoolStatus,
+        slot: u64,
+    },
+    FeeChange {
+        swap_fee_numerator: u64,
+        swap_fee_denominator: u64,
+        slot: u64,
+    },
+}
+
+/// Discriminants of the `ray_log` entries emitted by the amm program
+const LOG_TYPE_DEPOSIT: u8 = 1;
+const LOG_TYPE_WITHDRAW: u8 = 2;
+const LOG_TYPE_SWAP_BASE_IN: u8 = 3;
+const LOG_TYPE_SWAP_BASE_OUT: u8 = 4;
+/// `AmmParams::Status` of the `SetParams` admin instruction
+const SET_PARAMS_STATUS: u8 = 0;
+/// `AmmParams::Fees` of the `SetParams` admin instruction
+const SET_PARAMS_FEES: u8 = 9;
+
+#[derive(Deserialize)]
+struct DepositLog {
+    _log_type: u8,
+    _max_coin: u64,
+    _max_pc: u64,
+    _base: u64,
+    _pool_coin: u64,
+    _pool_pc: u64,
+    _pool_lp: u64,
+    _calc_pnl_x: u128,
+    _calc_pnl_y: u128,
+    _deduct_coin: u64,
+    _deduct_pc: u64,
+    mint_lp: u64,
+}
+
+#[derive(Deserialize)]
+struct WithdrawLog {
+    _log_type: u8,
+    withdraw_lp: u64,
+}
+
+/// Shared layout of the swap base in and swap base out logs
+#[derive(Deserialize)]
+struct SwapLog {
+    _log_type: u8,
+    /// `amount_in` for swap base in, `max_in` for swap base out
+    amount_in: u64,
+    _amount_out: u64,
+    direction: u64,
+    _user_source: u64,
+    _pool_coin: u64,
+    _pool_pc: u64,
+    /// `out_amount` for swap base in, `deduct_in` for swap base out
+    last: u64,
+}
+
+/// An instruction executed by a transaction, with its program and accounts resolved
+#[derive(Clone, Debug)]
+pub(crate) struct ExecutedInstruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<Pubkey>,
+    pub data: Vec<u8>,
+}
+
+/// Decodes the events of a transaction that touched the pool.
+/// The `ray_log` entries do not name the pool, so each one is attributed to the amm instruction
+/// that emitted it by walking the invoke and success frames of the log alongside the executed
+/// instructions. Only events of instructions whose amm account is `pool_id` are returned, so a
+/// transaction routed through several raydium pools reports the events of this pool only.
+pub fn parse_pool_events(
+    tx: &EncodedConfirmedTransactionWithStatusMeta,
+    pool_id: &Pubkey,
+    amm_program: &Pubkey,
+) -> Vec<PoolEvent> {
+    let Some(meta) = tx.transaction.meta.as_ref() else {
+        return vec![];
+    };
+    if meta.err.is_some() {
+        return vec![];
+    }
+    let (Some(transaction), Some(account_keys)) = (
+        tx.transaction.transaction.decode(),
+        transaction_account_keys(tx),
+    ) else {
+        return vec![];
+    };
+    let resolve = |program_id_index: u8, accounts: &[u8], data: Vec<u8>| {
+        Some(ExecutedInstruction {
+            program_id: *account_keys.get(program_id_index as usize)?,
+            accounts: accounts
+                .iter()
+                .map(|index| account_keys.get(*index as usize).copied())
+                .collect::<Option<_>>()?,
+            data,
+        })
+    };
+
+    // each top-level instruction is followed by the instructions it invoked, the order of the invoke logs
+    let inner_instructions =
+        Option::<Vec<_>>::from(meta.inner_instructions.clone()).unwrap_or_default();
+    let mut instructions = Vec::new();
+    for (index, ix) in transaction.message.instructions().iter().enumerate() {
+        instructions.extend(resolve(ix.program_id_index, &ix.accounts, ix.data.clone()));
+        let inner = inner_instructions
+            .iter()
+            .filter(|inner| inner.index as usize == index)
+            .flat_map(|inner| inner.instructions.iter())
+            .filter_map(|ix| match ix {
+                UiInstruction::Compiled(ix) => resolve(
+                    ix.program_id_index,
+                    &ix.accounts,
+                    bs58::decode(&ix.data).into_vec().ok()?,
+                ),
+                UiInstruction::Parsed(_) => None,
+            });
+        instructions.extend(inner);
+    }
+
+    let log_messages = Option::<Vec<String>>::from(meta.log_messages.clone()).unwrap_or_default();
+    pool_events(&log_messages, &instructions, pool_id, amm_program, tx.slot)
+}
+
+/// Decodes the events of `pool_id` from the log messages and the executed instructions of a
+/// transaction, see [`parse_pool_events`]
+pub(crate) fn pool_events(
+    log_messages: &[String],
+    instructions: &[ExecutedInstruction],
+    pool_id: &Pubkey,
+    amm_program: &Pubkey,
+    slot: u64,
+) -> Vec<PoolEvent> {
+    // the amm is the second account of every amm instruction, after the token program
+    let is_pool_instruction = |ix: &ExecutedInstruction| {
+        ix.program_id == *amm_program && ix.accounts.get(1) == Some(pool_id)
+    };
+    let mut events = Vec::new();
+
+    // each invoke belongs to the next instruction of the same program, instructions that log
+    // nothing (precompiles) are skipped; frames holds whether each call on the stack is a pool
+    // instruction
+    let mut next_instruction = 0;
+    let mut frames: Vec<bool> = Vec::new();
+    for message in log_messages {
+        match log_frame(message) {
+            Some(LogFrame::Invoke(program_id)) => {
+                let position = instructions[next_instruction..]
+                    .iter()
+                    .position(|ix| ix.program_id == program_id);
+                let is_pool = match position {
+                    Some(position) => {
+                        next_instruction += position + 1;
+                        is_pool_instruction(&instructions[next_instruction - 1])
+                    }
+                    None => false,
+                };
+                frames.push(is_pool);
+            }
+            Some(LogFrame::Exit) => {
+                frames.pop();
+            }
+            None if frames.last() == Some(&true) => {
+                events.extend(parse_ray_log(message, slot));
+            }
+            None => {}
+        }
+    }
+
+    for ix in instructions.iter().filter(|ix| is_pool_instruction(ix)) {
+        if let Ok(AmmInstruction::SetParams(params)) = AmmInstruction::unpack(&ix.data) {
+            match (params.param, params.value, params.fees) {
+                (SET_PARAMS_STATUS, Some(status), _) => events.push(PoolEvent::StatusChange {
+                    new_status: AmmPoolStatus::from(status),
+                    slot,
+                }),
+                (SET_PARAMS_FEES, _, Some(fees)) => events.push(PoolEvent::FeeChange {
+                    swap_fee_numerator: fees.swap_fee_numerator,
+                    swap_fee_denominator: fees.swap_fee_denominator,
+                    slot,
+                }),
+                _ => {}
+            }
+        }
+    }
+    events
+}
+
+enum LogFrame {
+    /// `Program <id> invoke [<depth>]`
+    Invoke(Pubkey),
+    /// `Program <id> success` or `Program <id> failed: <error>`
+    Exit,
+}
+
+/// Recognizes the log messages the runtime emits when a program is entered or left
+fn log_frame(message: &str) -> Option<LogFrame> {
+    let (program_id, rest) = message.strip_prefix("Program ")?.split_once(' ')?;
+    let program_id = Pubkey::from_str(program_id).ok()?;
+    if rest.starts_with("invoke [") {
+        Some(LogFrame::Invoke(program_id))
+    } else if rest == "success" || rest.starts_with("failed") {
+        Some(LogFrame::Exit)
+    } else {
+        None
+    }
+}
+
+/// Decodes a single `ray_log` log message, returns `None` for other messages
+fn parse_ray_log(message: &str, slot: u64) -> Option<PoolEvent> {
+    let data = message
+        .strip_prefix("Program log: ray_log: ")
+        .and_then(|log| base64::engine::general_purpose::STANDARD.decode(log).ok())?;
+    match data.first().copied() {
+        Some(LOG_TYPE_DEPOSIT) => {
+            bincode::deserialize::<DepositLog>(&data)
+                .ok()
+                .map(|log| PoolEvent::AddLiquidity {
+                    lp_minted: log.mint_lp,
+                    slot,
+                })
+        }
+        Some(LOG_TYPE_WITHDRAW) => {
+            bincode::deserialize::<WithdrawLog>(&data)
+                .ok()
+                .map(|log| PoolEvent::RemoveLiquidity {
+                    lp_burned: log.withdraw_lp,
+                    slot,
+                })
+        }
+        Some(log_type @ (LOG_TYPE_SWAP_BASE_IN | LOG_TYPE_SWAP_BASE_OUT)) => {
+            bincode::deserialize::<SwapLog>(&data).ok().and_then(|log| {
+                let direction = match log.direction {
+                    1 => SwapDirection::PC2Coin,
+                    2 => SwapDirection::Coin2PC,
+                    _ => return None,
+                };
+                let amount = if log_type == LOG_TYPE_SWAP_BASE_IN {
+                    log.amount_in
+                } else {
+                    log.last
+                };
+                Some(PoolEvent::Swap {
+                    direction,
+                    amount,
+                    slot,
+                })
+            })
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ray_log(log: &impl serde::Serialize) -> String {
+        format!(
+            "Program log: ray_log: {}",
+            base64::engine::general_purpose::STANDARD.encode(bincode::serialize(log).unwrap())
+        )
+    }
+
+    #[test]
+    fn parse_deposit_log() {
+        let log = ray_log(&(
+            LOG_TYPE_DEPOSIT,
+            10u64,
+            20u64,
+            0u64,
+            1_000u64,
+            2_000u64,
+            500u64,
+            0u128,
+            0u128,
+            10u64,
+            20u64,
+            5u64,
+        ));
+        assert!(matches!(
+            parse_ray_log(&log, 7),
+            Some(PoolEvent::AddLiquidity {
+                lp_minted: 5,
+                slot: 7
+            })
+        ));
+    }
+
+    #[test]
+    fn parse_withdraw_log() {
+        let log = ray_log(&(LOG_TYPE_WITHDRAW, 42u64));
+        assert!(matches!(
+            parse_ray_log(&log, 7),
+            Some(PoolEvent::RemoveLiquidity {
+                lp_burned: 42,
+                slot: 7
+            })
+        ));
+    }
+
+    #[test]
+    fn parse_swap_logs() {
+        // swap base in reports the exact input
+        let log = ray_log(&(
+            LOG_TYPE_SWAP_BASE_IN,
+            100u64,
+            90u64,
+            2u64,
+            0u64,
+            0u64,
+            0u64,
+            95u64,
+        ));
+        assert!(matches!(
+            parse_ray_log(&log, 7),
+            Some(PoolEvent::Swap {
+                direction: SwapDirection::Coin2PC,
+                amount: 100,
+                slot: 7
+            })
+        ));
+
+        // swap base out reports the deducted input rather than the maximum input
+        let log = ray_log(&(
+            LOG_TYPE_SWAP_BASE_OUT,
+            100u64,
+            90u64,
+            1u64,
+            0u64,
+            0u64,
+            0u64,
+            95u64,
+        ));
+        assert!(matches!(
+            parse_ray_log(&log, 7),
+            Some(PoolEvent::Swap {
+                direction: SwapDirection::PC2Coin,
+                amount: 95,
+                slot: 7
+            })
+        ));
+    }
+
+    #[test]
+    fn ignore_unknown_logs() {
+        let log = ray_log(&(
+            LOG_TYPE_SWAP_BASE_IN,
+            100u64,
+            90u64,
+            3u64,
+            0u64,
+            0u64,
+            0u64,
+            95u64,
+        ));
+        assert!(parse_ray_log(&log, 7).is_none());
+        assert!(parse_ray_log(&ray_log(&(0u8, 1u64)), 7).is_none());
+        assert!(parse_ray_log(&ray_log(&(LOG_TYPE_WITHDRAW,)), 7).is_none());
+        assert!(parse_ray_log("Program log: ray_log: not base64!", 7).is_none());
+        assert!(parse_ray_log("Program log: Instruction: SwapBaseIn", 7).is_none());
+    }
+
+    fn swap_base_in_log(amount_in: u64) -> String {
+        ray_log(&(
+            LOG_TYPE_SWAP_BASE_IN,
+            amount_in,
+            0u64,
+            2u64,
+            0u64,
+            0u64,
+            0u64,
+            0u64,
+        ))
+    }
+
+    fn instruction(program_id: Pubkey, accounts: Vec<Pubkey>) -> ExecutedInstruction {
+        ExecutedInstruction {
+            program_id,
+            accounts,
+            data: vec![],
+        }
+    }
+
+    #[test]
+    fn ray_logs_are_attributed_to_their_pool() {
+        let [amm, aggregator, precompile, token_program] = [(); 4].map(|_| Pubkey::new_unique());
+        let [pool, other_pool] = [(); 2].map(|_| Pubkey::new_unique());
+        let instructions = [
+            instruction(precompile, vec![]),
+            instruction(amm, vec![token_program, other_pool]),
+            instruction(aggregator, vec![]),
+            instruction(amm, vec![token_program, pool]),
+            instruction(amm, vec![token_program, other_pool]),
+        ];
+        let logs = [
+            format!("Program {} invoke [1]", amm),
+            swap_base_in_log(100),
+            format!("Program {} consumed 20000 of 200000 compute units", amm),
+            format!("Program {} success", amm),
+            format!("Program {} invoke [1]", aggregator),
+            format!("Program {} invoke [2]", amm),
+            swap_base_in_log(200),
+            format!("Program {} success", amm),
+            "Program log: route done".to_string(),
+            format!("Program {} invoke [2]", amm),
+            swap_base_in_log(300),
+            format!("Program {} success", amm),
+            format!("Program {} success", aggregator),
+        ];
+
+        let events = pool_events(&logs, &instructions, &pool, &amm, 7);
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            events[0],
+            PoolEvent::Swap {
+                direction: SwapDirection::Coin2PC,
+                amount: 200,
+                slot: 7
+            }
+        ));
+
+        let amounts = pool_events(&logs, &instructions, &other_pool, &amm, 7)
+            .into_iter()
+            .filter_map(|event| match event {
+                PoolEvent::Swap { amount, .. } => Some(amount),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(amounts, vec![100, 300]);
+    }
+
+    #[test]
+    fn ray_logs_of_other_programs_are_ignored() {
+        let [amm, other_program, token_program, pool] = [(); 4].map(|_| Pubkey::new_unique());
+        let instructions = [instruction(other_program, vec![token_program, pool])];
+        let logs = [
+            format!("Program {} invoke [1]", other_program),
+            swap_base_in_log(100),
+            format!(
+                "Program {} failed: custom program error: 0x1",
+                other_program
+            ),
+            swap_base_in_log(100),
+        ];
+        assert!(pool_events(&logs, &instructions, &pool, &amm, 7).is_empty());
+    }
+}
@@ -0,0 +1,307 @@
+//! Pure constant-product math used by the pool analytics
+
+/// The impermanent loss of a constant-product lp position after the price moved by `price_ratio`
+/// (current price / entry price), relative to holding the tokens. Returns a non-positive fraction,
+/// e.g. `-0.057` for a 2x price move.
+pub fn compute_impermanent_loss(price_ratio: f64) -> f64 {
+    if price_ratio <= 0.0 {
+        return -1.0;
+    }
+    2.0 * price_ratio.sqrt() / (1.0 + price_ratio) - 1.0
+}
+
+/// The standard deviation of `values` divided by their mean, 0 for fewer than two values
+pub fn coefficient_of_variation(values: &[f64]) -> f64 {
+    if values.len() < 2 {
+        return 0.0;
+    }
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let variance = values
+        .iter()
+        .map(|value| (value - mean).powi(2))
+        .sum::<f64>()
+        / values.len() as f64;
+    variance.sqrt() / mean
+}
+
+/// The median of `values`, the mean of the two middle values for an even count and 0 for an empty
+/// slice
+pub fn median(values: &[f64]) -> f64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let n = sorted.len();
+    match n {
+        0 => 0.0,
+        _ if n % 2 == 0 => (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0,
+        _ => sorted[n / 2],
+    }
+}
+
+/// The log returns between consecutive `prices`, one fewer than the number of prices
+pub fn log_returns(prices: &[f64]) -> Vec<f64> {
+    prices
+        .windows(2)
+        .map(|window| (window[1] / window[0]).ln())
+        .collect()
+}
+
+/// The exponential moving average of `values`, oldest first, where each new value is weighted
+/// by `alpha`. Returns 0 for an empty slice.
+pub fn exponential_moving_average(values: &[f64], alpha: f64) -> f64 {
+    let Some((first, rest)) = values.split_first() else {
+        return 0.0;
+    };
+    rest.iter()
+        .fold(*first, |ema, value| alpha * value + (1.0 - alpha) * ema)
+}
+
+/// The Pearson correlation coefficient of two equally long series, between -1 and 1.
+/// Returns 0 for fewer than two values or when either series is constant.
+pub fn pearson_correlation(xs: &[f64], ys: &[f64]) -> f64 {
+    let n = xs.len().min(ys.len());
+    if n < 2 {
+        return 0.0;
+    }
+    let (xs, ys) = (&xs[..n], &ys[..n]);
+    let mean_x = xs.iter().sum::<f64>() / n as f64;
+    let mean_y = ys.iter().sum::<f64>() / n as f64;
+    let (mut covariance, mut variance_x, mut variance_y) = (0.0, 0.0, 0.0);
+    for (x, y) in xs.iter().zip(ys) {
+        covariance += (x - mean_x) * (y - mean_y);
+        variance_x += (x - mean_x).powi(2);
+        variance_y += (y - mean_y).powi(2);
+    }
+    if variance_x == 0.0 || variance_y == 0.0 {
+        return 0.0;
+    }
+    covariance / (variance_x * variance_y).sqrt()
+}
+
+/// The sample covariance of two equally long series, 0 for fewer than two values
+pub fn covariance(xs: &[f64], ys: &[f64]) -> f64 {
+    let n = xs.len().min(ys.len());
+    if n < 2 {
+        return 0.0;
+    }
+    let (xs, ys) = (&xs[..n], &ys[..n]);
+    let mean_x = xs.iter().sum::<f64>() / n as f64;
+    let mean_y = ys.iter().sum::<f64>() / n as f64;
+    xs.iter()
+        .zip(ys)
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum::<f64>()
+        / (n - 1) as f64
+}
+
+/// The slope of the ordinary least squares fit of `ys` on `xs`. Returns 0 for fewer than two
+/// values or when `xs` is constant.
+pub fn linear_regression_slope(xs: &[f64], ys: &[f64]) -> f64 {
+    let variance_x = covariance(xs, xs);
+    if variance_x == 0.0 {
+        return 0.0;
+    }
+    covariance(xs, ys) / variance_x
+}
+
+/// The elasticity of the swap volume to the pool fee, estimated from the `(fee_bps, daily_volume)`
+/// of periods with a constant fee as the least squares slope of ln(volume) on ln(fee). Returns
+/// `None` when fewer than two distinct fees with volume were observed.
+pub fn estimate_volume_fee_elasticity(periods: &[(u64, f64)]) -> Option<f64> {
+    let (log_fees, log_volumes): (Vec<f64>, Vec<f64>) = periods
+        .iter()
+        .filter(|(fee_bps, volume)| *fee_bps > 0 && *volume > 0.0)
+        .map(|(fee_bps, volume)| ((*fee_bps as f64).ln(), volume.ln()))
+        .unzip();
+    if covariance(&log_fees, &log_fees) == 0.0 {
+        return None;
+    }
+    Some(linear_regression_slope(&log_fees, &log_volumes))
+}
+
+/// The fee yield of a pool at its current fee and 5 bps either side
+#[derive(Clone, Copy, Debug, serde::Serialize)]
+pub struct FeeSensitivity {
+    /// The annualized fee yield at the current fee, as a percentage of tvl
+    pub current_apy_pct: f64,
+    /// The annualized fee yield with the fee raised by 5 bps
+    pub fee_plus_5_bps_apy: f64,
+    /// The annualized fee yield with the fee lowered by 5 bps, floored at a zero fee
+    pub fee_minus_5_bps_apy: f64,
+    /// The relative change of the yield per relative change of the fee, measured between the two
+    /// neighbouring fees
+    pub elasticity: f64,
+}
+
+/// The sensitivity of the annualized fee yield to the pool fee. The daily volume at another fee
+/// is `daily_volume_usd * (fee / current_fee) ^ volume_elasticity`, see
+/// [`estimate_volume_fee_elasticity`]. With a `volume_elasticity` of 0 the yield scales linearly
+/// with the fee and the elasticity is 1.
+pub fn compute_fee_sensitivity(
+    current_fee_bps: u64,
+    daily_volume_usd: f64,
+    tvl_usd: f64,
+    volume_elasticity: f64,
+) -> FeeSensitivity {
+    let apy_pct = |fee_bps: u64| {
+        if tvl_usd <= 0.0 || fee_bps == 0 {
+            return 0.0;
+        }
+        let daily_volume_usd = if current_fee_bps > 0 {
+            daily_volume_usd * (fee_bps as f64 / current_fee_bps as f64).powf(volume_elasticity)
+        } else {
+            daily_volume_usd
+        };
+        daily_volume_usd * fee_bps as f64 / 10_000.0 * 365.0 / tvl_usd * 100.0
+    };
+    let (fee_minus, fee_plus) = (current_fee_bps.saturating_sub(5), current_fee_bps + 5);
+    let current_apy_pct = apy_pct(current_fee_bps);
+    let fee_plus_5_bps_apy = apy_pct(fee_plus);
+    let fee_minus_5_bps_apy = apy_pct(fee_minus);
+    let elasticity = if current_apy_pct > 0.0 {
+        ((fee_plus_5_bps_apy - fee_minus_5_bps_apy) / current_apy_pct)
+            / ((fee_plus - fee_minus) as f64 / current_fee_bps as f64)
+    } else {
+        0.0
+    };
+    FeeSensitivity {
+        current_apy_pct,
+        fee_plus_5_bps_apy,
+        fee_minus_5_bps_apy,
+        elasticity,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn impermanent_loss() {
+        assert_close(compute_impermanent_loss(1.0), 0.0);
+        assert_close(compute_impermanent_loss(4.0), -0.2);
+        assert_close(compute_impermanent_loss(0.25), -0.2);
+        assert_close(compute_impermanent_loss(2.0), 2.0 * 2f64.sqrt() / 3.0 - 1.0);
+        assert_close(compute_impermanent_loss(0.0), -1.0);
+        assert_close(compute_impermanent_loss(-1.0), -1.0);
+    }
+
+    #[test]
+    fn log_returns_between_prices() {
+        let returns = log_returns(&[1.0, 2.0, 1.0, 1.0]);
+        assert_eq!(returns.len(), 3);
+        assert_close(returns[0], 2f64.ln());
+        assert_close(returns[1], -(2f64.ln()));
+        assert_close(returns[2], 0.0);
+        assert!(log_returns(&[1.0]).is_empty());
+        assert!(log_returns(&[]).is_empty());
+    }
+
+    #[test]
+    fn pearson_correlation_of_linear_series() {
+        let xs = [1.0, 2.0, 3.0, 4.0];
+        assert_close(pearson_correlation(&xs, &[2.0, 4.0, 6.0, 8.0]), 1.0);
+        assert_close(pearson_correlation(&xs, &[8.0, 6.0, 4.0, 2.0]), -1.0);
+        assert_close(pearson_correlation(&xs, &[1.0, -1.0, -1.0, 1.0]), 0.0);
+    }
+
+    #[test]
+    fn pearson_correlation_degenerate_series() {
+        assert_close(pearson_correlation(&[1.0], &[1.0]), 0.0);
+        assert_close(pearson_correlation(&[1.0, 2.0], &[3.0, 3.0]), 0.0);
+        // only the common prefix of the two series is used
+        assert_close(pearson_correlation(&[1.0, 2.0, 3.0], &[1.0, 2.0]), 1.0);
+    }
+
+    #[test]
+    fn exponential_moving_average_weights_recent_values() {
+        assert_close(exponential_moving_average(&[], 0.5), 0.0);
+        assert_close(exponential_moving_average(&[3.0], 0.5), 3.0);
+        // 1 -> 0.5 * 2 + 0.5 * 1 = 1.5 -> 0.5 * 4 + 0.5 * 1.5 = 2.75
+        assert_close(exponential_moving_average(&[1.0, 2.0, 4.0], 0.5), 2.75);
+        assert_close(exponential_moving_average(&[1.0, 2.0, 4.0], 1.0), 4.0);
+        assert_close(exponential_moving_average(&[1.0, 2.0, 4.0], 0.0), 1.0);
+    }
+
+    #[test]
+    fn median_of_odd_and_even_counts() {
+        assert_close(median(&[]), 0.0);
+        assert_close(median(&[2.0]), 2.0);
+        assert_close(median(&[3.0, 1.0, 2.0]), 2.0);
+        assert_close(median(&[4.0, 1.0, 3.0, 2.0]), 2.5);
+        assert_close(median(&[-1.0, 5.0, 100.0]), 5.0);
+    }
+
+    #[test]
+    fn fee_sensitivity_with_fixed_volume() {
+        let sensitivity = compute_fee_sensitivity(25, 1_000.0, 100_000.0, 0.0);
+        assert_close(sensitivity.current_apy_pct, 0.9125);
+        assert_close(sensitivity.fee_plus_5_bps_apy, 1.095);
+        assert_close(sensitivity.fee_minus_5_bps_apy, 0.73);
+        assert_close(sensitivity.elasticity, 1.0);
+    }
+
+    #[test]
+    fn fee_sensitivity_with_elastic_volume() {
+        // volume inversely proportional to the fee keeps the yield constant
+        let sensitivity = compute_fee_sensitivity(25, 1_000.0, 100_000.0, -1.0);
+        assert_close(sensitivity.fee_plus_5_bps_apy, sensitivity.current_apy_pct);
+        assert_close(sensitivity.fee_minus_5_bps_apy, sensitivity.current_apy_pct);
+        assert_close(sensitivity.elasticity, 0.0);
+    }
+
+    #[test]
+    fn fee_sensitivity_without_liquidity() {
+        let sensitivity = compute_fee_sensitivity(25, 1_000.0, 0.0, 0.0);
+        assert_close(sensitivity.current_apy_pct, 0.0);
+        assert_close(sensitivity.elasticity, 0.0);
+    }
+
+    #[test]
+    fn volume_fee_elasticity_from_periods() {
+        let elasticity = estimate_volume_fee_elasticity(&[(25, 1_000.0), (50, 500.0)]).unwrap();
+        assert_close(elasticity, -1.0);
+        assert_eq!(
+            estimate_volume_fee_elasticity(&[(25, 1_000.0), (25, 2_000.0)]),
+            None
+        );
+        assert_eq!(
+            estimate_volume_fee_elasticity(&[(25, 1_000.0), (50, 0.0)]),
+            None
+        );
+        assert_eq!(estimate_volume_fee_elasticity(&[]), None);
+    }
+
+    #[test]
+    fn covariance_of_samples() {
+        assert_close(covariance(&[1.0, 2.0, 3.0], &[2.0, 4.0, 6.0]), 2.0);
+        assert_close(covariance(&[1.0, 2.0, 3.0], &[3.0, 2.0, 1.0]), -1.0);
+        assert_close(covariance(&[1.0, 2.0, 3.0], &[5.0, 5.0, 5.0]), 0.0);
+        assert_close(covariance(&[1.0, 2.0, 3.0, 100.0], &[2.0, 4.0, 6.0]), 2.0);
+        assert_close(covariance(&[1.0], &[1.0]), 0.0);
+        assert_close(covariance(&[], &[]), 0.0);
+    }
+
+    #[test]
+    fn linear_regression_slope_of_samples() {
+        assert_close(
+            linear_regression_slope(&[1.0, 2.0, 3.0], &[3.0, 5.0, 7.0]),
+            2.0,
+        );
+        assert_close(
+            linear_regression_slope(&[0.0, 1.0, 2.0, 3.0], &[1.0, 0.0, 3.0, 2.0]),
+            0.6,
+        );
+        assert_close(
+            linear_regression_slope(&[2.0, 2.0, 2.0], &[1.0, 2.0, 3.0]),
+            0.0,
+        );
+        assert_close(linear_regression_slope(&[1.0], &[1.0]), 0.0);
+    }
+}
@@ -1,2 +1,5 @@
 mod amm_info;
 pub mod executor;
+pub mod history;
+pub mod math;
+pub mod routing;
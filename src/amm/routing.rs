@@ -0,0 +1,319 @@
+//! Mint level routing graph built from the standard pools listed by the api
+
+use crate::api_v3::response::ApiV3StandardPool;
+use solana_sdk::pubkey::Pubkey;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::time::Instant;
+
+#[derive(Clone, Debug)]
+pub struct RoutingEdge {
+    /// The mint on the other side of the pool
+    pub to: Pubkey,
+    /// The pool connecting both mints
+    pub pool_id: Pubkey,
+    /// The liquidity of the pool in usd
+    pub tvl: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct RoutingGraph {
+    edges: HashMap<Pubkey, Vec<RoutingEdge>>,
+    updated_at: Instant,
+}
+
+impl RoutingGraph {
+    pub fn from_pools<'a>(pools: impl IntoIterator<Item = &'a ApiV3StandardPool>) -> Self {
+        let mut edges: HashMap<Pubkey, Vec<RoutingEdge>> = HashMap::new();
+        for pool in pools {
+            let (a, b) = (pool.mint_a.address, pool.mint_b.address);
+            edges.entry(a).or_default().push(RoutingEdge {
+                to: b,
+                pool_id: pool.id,
+                tvl: pool.tvl,
+            });
+            edges.entry(b).or_default().push(RoutingEdge {
+                to: a,
+                pool_id: pool.id,
+                tvl: pool.tvl,
+            });
+        }
+        Self {
+            edges,
+            updated_at: Instant::now(),
+        }
+    }
+
+    /// The pools containing `mint`, together with the mint on the other side
+    pub fn neighbours(&self, mint: &Pubkey) -> &[RoutingEdge] {
+        self.edges.get(mint).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn last_updated(&self) -> Instant {
+        self.updated_at
+    }
+
+    /// The cycles of two or three hops starting and ending at `start`, each pool used at most once
+    /// per cycle. Every hop is the edge taken from the previous mint.
+    pub fn cycles(&self, start: Pubkey) -> Vec<Vec<&RoutingEdge>> {
+        let mut cycles = Vec::new();
+        for first in self.neighbours(&start) {
+            for second in self.neighbours(&first.to) {
+                if second.pool_id == first.pool_id {
+                    continue;
+                }
+                if second.to == start {
+                    cycles.push(vec![first, second]);
+                    continue;
+                }
+                for third in self.neighbours(&second.to) {
+                    if third.to == start
+                        && third.pool_id != first.pool_id
+                        && third.pool_id != second.pool_id
+                    {
+                        cycles.push(vec![first, second, third]);
+                    }
+                }
+            }
+        }
+        cycles
+    }
+
+    /// Finds the cheapest path of mints from `from` to `to` with Dijkstra's algorithm.
+    /// Each hop costs `1 + 1 / tvl`, so fewer hops are preferred and deeper pools win ties.
+    pub fn shortest_path(&self, from: Pubkey, to: Pubkey) -> Option<Vec<Pubkey>> {
+        let mut costs: HashMap<Pubkey, f64> = HashMap::from([(from, 0.0)]);
+        let mut previous: HashMap<Pubkey, Pubkey> = HashMap::new();
+        let mut queue = BinaryHeap::from([Visit {
+            cost: 0.0,
+            mint: from,
+        }]);
+
+        while let Some(Visit { cost, mint }) = queue.pop() {
+            if mint == to {
+                let mut path = vec![to];
+                let mut current = to;
+                while let Some(prev) = previous.get(&current) {
+                    path.push(*prev);
+                    current = *prev;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            if costs.get(&mint).is_some_and(|best| cost > *best) {
+                continue;
+            }
+            for edge in self.neighbours(&mint) {
+                let next_cost = cost + 1.0 + 1.0 / edge.tvl.max(1.0);
+                if costs.get(&edge.to).map_or(true, |best| next_cost < *best) {
+                    costs.insert(edge.to, next_cost);
+                    previous.insert(edge.to, mint);
+                    queue.push(Visit {
+                        cost: next_cost,
+                        mint: edge.to,
+                    });
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Min-heap entry for the path search
+struct Visit {
+    cost: f64,
+    mint: Pubkey,
+}
+
+impl PartialEq for Visit {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
+}
+impl Eq for Visit {}
+impl PartialOrd for Visit {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Visit {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RoutingGraph;
+    use crate::api_v3::response::ApiV3StandardPool;
+    use solana_sdk::pubkey::Pubkey;
+    use std::collections::HashSet;
+
+    fn token(mint: Pubkey) -> serde_json::Value {
+        serde_json::json!({
+            "chainId": 101,
+            "address": mint.to_string(),
+            "programId": spl_token::ID.to_string(),
+            "decimals": 6,
+            "extensions": {},
+        })
+    }
+
+    /// A standard pool as listed by the api, with only the fields the routing graph uses set
+    fn pool(mint_a: Pubkey, mint_b: Pubkey, id: Pubkey, tvl: f64) -> ApiV3StandardPool {
+        let period = serde_json::json!({
+            "volume": 0.0,
+            "volumeQuote": 0.0,
+            "volumeFee": 0.0,
+            "apr": 0.0,
+            "feeApr": 0.0,
+            "priceMin": 0.0,
+            "priceMax": 0.0,
+            "rewardApr": [],
+        });
+        serde_json::from_value(serde_json::json!({
+            "programId": Pubkey::new_unique().to_string(),
+            "id": id.to_string(),
+            "mintA": token(mint_a),
+            "mintB": token(mint_b),
+            "rewardDefaultInfos": [],
+            "price": 1.0,
+            "mintAmountA": 0.0,
+            "mintAmountB": 0.0,
+            "feeRate": 0.0025,
+            "openTime": "0",
+            "pooltype": [],
+            "tvl": tvl,
+            "day": period,
+            "week": period,
+            "month": period,
+            "farmUpcomingCount": 0,
+            "farmOngoingCount": 0,
+            "farmFinishedCount": 0,
+            "type": "Standard",
+            "lpPrice": 0.0,
+            "lpAmount": 0.0,
+            "lpMint": token(Pubkey::new_unique()),
+        }))
+        .unwrap()
+    }
+
+    /// Builds a graph through `RoutingGraph::from_pools` from `(mint_a, mint_b, pool_id, tvl)` tuples
+    fn graph(pools: &[(Pubkey, Pubkey, Pubkey, f64)]) -> RoutingGraph {
+        let pools = pools
+            .iter()
+            .map(|&(a, b, pool_id, tvl)| pool(a, b, pool_id, tvl))
+            .collect::<Vec<_>>();
+        RoutingGraph::from_pools(&pools)
+    }
+
+    #[test]
+    fn shortest_path_prefers_fewer_hops() {
+        let [a, b, c, d] = [(); 4].map(|_| Pubkey::new_unique());
+        let graph = graph(&[
+            (a, b, Pubkey::new_unique(), 1_000_000.0),
+            (b, c, Pubkey::new_unique(), 1_000_000.0),
+            (a, c, Pubkey::new_unique(), 10.0),
+            (c, d, Pubkey::new_unique(), 1_000.0),
+        ]);
+        assert_eq!(graph.shortest_path(a, c), Some(vec![a, c]));
+        assert_eq!(graph.shortest_path(a, d), Some(vec![a, c, d]));
+        assert_eq!(graph.shortest_path(d, b), Some(vec![d, c, b]));
+    }
+
+    #[test]
+    fn shortest_path_prefers_deeper_pools_on_ties() {
+        let [a, b, c, d] = [(); 4].map(|_| Pubkey::new_unique());
+        let graph = graph(&[
+            (a, b, Pubkey::new_unique(), 100.0),
+            (b, d, Pubkey::new_unique(), 100.0),
+            (a, c, Pubkey::new_unique(), 1_000_000.0),
+            (c, d, Pubkey::new_unique(), 1_000_000.0),
+        ]);
+        assert_eq!(graph.shortest_path(a, d), Some(vec![a, c, d]));
+    }
+
+    #[test]
+    fn shortest_path_without_route() {
+        let [a, b, c] = [(); 3].map(|_| Pubkey::new_unique());
+        let graph = graph(&[(a, b, Pubkey::new_unique(), 1_000.0)]);
+        assert_eq!(graph.shortest_path(a, c), None);
+        assert_eq!(graph.shortest_path(a, a), Some(vec![a]));
+    }
+
+    #[test]
+    fn cycles_use_each_pool_once() {
+        let [a, b, c] = [(); 3].map(|_| Pubkey::new_unique());
+        let [ab_1, ab_2, bc, ca] = [(); 4].map(|_| Pubkey::new_unique());
+        let graph = graph(&[
+            (a, b, ab_1, 1_000.0),
+            (a, b, ab_2, 1_000.0),
+            (b, c, bc, 1_000.0),
+            (c, a, ca, 1_000.0),
+        ]);
+
+        let cycles = graph
+            .cycles(a)
+            .into_iter()
+            .map(|cycle| cycle.iter().map(|edge| edge.pool_id).collect::<Vec<_>>())
+            .collect::<HashSet<_>>();
+        let expected = HashSet::from([
+            vec![ab_1, ab_2],
+            vec![ab_2, ab_1],
+            vec![ab_1, bc, ca],
+            vec![ab_2, bc, ca],
+            vec![ca, bc, ab_1],
+            vec![ca, bc, ab_2],
+        ]);
+        assert_eq!(cycles, expected);
+    }
+
+    #[test]
+    fn cycles_end_at_start() {
+        let [a, b, c] = [(); 3].map(|_| Pubkey::new_unique());
+        let graph = graph(&[
+            (a, b, Pubkey::new_unique(), 1_000.0),
+            (b, c, Pubkey::new_unique(), 1_000.0),
+        ]);
+        assert!(graph.cycles(a).is_empty());
+    }
+
+    #[test]
+    fn cycles_longer_than_three_hops_are_skipped() {
+        let [a, b, c, d] = [(); 4].map(|_| Pubkey::new_unique());
+        let square = [
+            (a, b, Pubkey::new_unique(), 1_000.0),
+            (b, c, Pubkey::new_unique(), 1_000.0),
+            (c, d, Pubkey::new_unique(), 1_000.0),
+            (d, a, Pubkey::new_unique(), 1_000.0),
+        ];
+        assert!(graph(&square).cycles(a).is_empty());
+
+        // the diagonal closes the triangles a -> b -> d -> a and a -> d -> b -> a
+        let mut pools = square.to_vec();
+        pools.push((b, d, Pubkey::new_unique(), 1_000.0));
+        let graph = graph(&pools);
+        let cycles = graph.cycles(a);
+        assert_eq!(cycles.len(), 2);
+        assert!(cycles.iter().all(|cycle| cycle.len() == 3));
+    }
+
+    #[test]
+    fn from_pools_links_both_mints() {
+        let [a, b, c] = [(); 3].map(|_| Pubkey::new_unique());
+        let [ab, bc] = [(); 2].map(|_| Pubkey::new_unique());
+        let graph = graph(&[(a, b, ab, 1_000.0), (b, c, bc, 2_000.0)]);
+
+        let edges = |mint| {
+            graph
+                .neighbours(&mint)
+                .iter()
+                .map(|edge| (edge.to, edge.pool_id, edge.tvl))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(edges(a), vec![(b, ab, 1_000.0)]);
+        assert_eq!(edges(b), vec![(a, ab, 1_000.0), (c, bc, 2_000.0)]);
+        assert_eq!(edges(c), vec![(b, bc, 2_000.0)]);
+        assert!(graph.neighbours(&Pubkey::new_unique()).is_empty());
+    }
+}
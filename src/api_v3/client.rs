@@ -2,6 +2,8 @@ use super::response::{ApiV3PoolsPage, ApiV3Token, ApiV3TokenList};
 use super::{handle_response_or_error, PoolFetchParams};
 use serde::de::DeserializeOwned;
 use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::str::FromStr;
 
 #[derive(Clone, Debug)]
 pub struct ApiV3Client {
@@ -52,6 +54,29 @@ impl ApiV3Client {
             .data)
     }
 
+    pub async fn get_token_price(
+        &self,
+        mints: Vec<String>,
+    ) -> Result<HashMap<Pubkey, f64>, anyhow::Error> {
+        let mints = mints.join(",");
+        let url = format!("{}/mint/price?mints={}", &self.base_url, mints);
+        let prices: HashMap<String, serde_json::Value> =
+            handle_response_or_error(reqwest::get(url).await?)
+                .await?
+                .data;
+        // prices are returned as strings, and are null for unknown mints
+        Ok(prices
+            .into_iter()
+            .filter_map(|(mint, price)| {
+                let price = match price {
+                    serde_json::Value::String(price) => price.parse().ok()?,
+                    price => price.as_f64()?,
+                };
+                Some((Pubkey::from_str(&mint).ok()?, price))
+            })
+            .collect())
+    }
+
     pub async fn get_pool_list<T: DeserializeOwned>(
         &self,
         params: &PoolFetchParams,
@@ -81,6 +106,17 @@ impl ApiV3Client {
             .data)
     }
 
+    pub async fn fetch_pools_by_lps<T: DeserializeOwned>(
+        &self,
+        lps: Vec<String>,
+    ) -> Result<Vec<T>, anyhow::Error> {
+        let lps = lps.join(",");
+        let url = format!("{}/pools/info/lps?lps={}", &self.base_url, lps);
+        Ok(handle_response_or_error(reqwest::get(url).await?)
+            .await?
+            .data)
+    }
+
     pub async fn fetch_pool_keys_by_ids<T: DeserializeOwned>(
         &self,
         ids: Vec<String>,
@@ -1,6 +1,6 @@
 mod client;
 pub mod response;
-mod serde_helpers;
+pub(crate) mod serde_helpers;
 
 use anyhow::Context;
 pub use client::ApiV3Client;
@@ -184,6 +184,15 @@ pub mod raydium_api_v3 {
             .fetch_pool_keys_by_ids::<ApiV3StandardPoolKeys>(ids)
             .await
             .unwrap();
+        let lps = pools
+            .pools
+            .iter()
+            .map(|p| p.pool.lp_mint.address.to_string())
+            .collect::<Vec<_>>();
+        let pools_by_lp = client
+            .fetch_pools_by_lps::<ApiV3StandardPool>(lps)
+            .await
+            .unwrap();
 
         for pool in pools.pools.iter().take(3) {
             let pools_by_mint = client
@@ -204,6 +213,10 @@ pub mod raydium_api_v3 {
             let pool_keys_by_id = pool_keys_by_id.iter().find(|p| p.id == pool.id);
             assert!(pool_keys_by_id.is_some());
             assert!(pool_keys_by_id.unwrap().id == pool.id);
+
+            let pool_by_lp = pools_by_lp.iter().find(|p| p.id == pool.id);
+            assert!(pool_by_lp.is_some());
+            assert!(pool_by_lp.unwrap().pool.lp_mint.address == pool.pool.lp_mint.address);
         }
     }
 
@@ -42,3 +42,20 @@ pub type ApiV3StandardPoolsPage = ApiV3PoolsPage<ApiV3StandardPool>;
 pub type ApiV3ClmmPool = ApiV3BasePool<_ApiV3ClmmPool>;
 pub type ApiV3ClmmPoolKeys = ApiV3BasePoolKeys<_ApiV3ClmmPoolKeys>;
 pub type ApiV3ClmmPoolsPage = ApiV3PoolsPage<ApiV3ClmmPool>;
+
+/// A standard pool together with metrics derived from the api response
+#[derive(Clone, Debug)]
+pub struct ApiV3StandardPoolExtended {
+    pub pool: ApiV3StandardPool,
+    /// The swap fees collected by the pool over the last 24 hours, in usd
+    pub fee_revenue_24h_usd: f64,
+}
+
+impl From<ApiV3StandardPool> for ApiV3StandardPoolExtended {
+    fn from(pool: ApiV3StandardPool) -> Self {
+        Self {
+            fee_revenue_24h_usd: pool.day.volume_fee,
+            pool,
+        }
+    }
+}
@@ -21,6 +21,7 @@ pub struct SwapInstructionsBuilder {
     pub compute_budget_instructions: Vec<Instruction>,
     pub setup_instructions: Vec<Instruction>,
     pub swap_instruction: Option<Instruction>,
+    pub post_swap_instructions: Vec<Instruction>,
     pub cleanup_instruction: Option<Instruction>,
     pub address_lookup_table_addresses: Vec<Pubkey>,
 }
@@ -31,6 +32,19 @@ pub struct UserAssociatedTokenAccounts {
 }
 
 impl SwapInstructionsBuilder {
+    /// Sets the compute unit limit and price directly, replacing any previously set budget
+    /// instructions. Use instead of `handle_compute_units_params` and `handle_priority_fee_params`
+    /// when the transaction has already been profiled.
+    pub fn with_compute_budget(mut self, limit: u32, price_micro_lamports: u64) -> Self {
+        self.compute_budget_instructions = vec![
+            solana_sdk::compute_budget::ComputeBudgetInstruction::set_compute_unit_limit(limit),
+            solana_sdk::compute_budget::ComputeBudgetInstruction::set_compute_unit_price(
+                price_micro_lamports,
+            ),
+        ];
+        self
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn handle_token_wrapping_and_accounts_creation(
         &mut self,
@@ -210,12 +224,14 @@ impl SwapInstructionsBuilder {
             compute_budget_instructions,
             setup_instructions,
             swap_instruction,
+            post_swap_instructions,
             cleanup_instruction,
             address_lookup_table_addresses: _,
         } = self;
         final_instructions.extend(compute_budget_instructions);
         final_instructions.extend(setup_instructions);
         final_instructions.push(swap_instruction.context("Swap instruction not set")?);
+        final_instructions.extend(post_swap_instructions);
         if let Some(cleanup_instruction) = cleanup_instruction {
             final_instructions.push(cleanup_instruction);
         }
@@ -1,3 +1,4 @@
+use serde::Serialize;
 use solana_sdk::pubkey::Pubkey;
 
 #[derive(Copy, Clone, Debug, Default)]
@@ -31,6 +32,35 @@ pub struct SwapConfigOverrides {
     pub as_legacy_transaction: Option<bool>,
 }
 
+/// A recurring window of utc hours during which swaps are allowed
+#[derive(Clone, Debug)]
+pub struct TradingWindow {
+    /// The first hour of the window, inclusive
+    pub start_utc_hour: u8,
+    /// The last hour of the window, exclusive. A window with `end_utc_hour <= start_utc_hour`
+    /// wraps past midnight
+    pub end_utc_hour: u8,
+    /// The days on which the window starts
+    pub days_of_week: Vec<chrono::Weekday>,
+}
+
+impl TradingWindow {
+    /// Whether the given utc time falls inside the window
+    pub fn contains(&self, now: chrono::DateTime<chrono::Utc>) -> bool {
+        use chrono::{Datelike, Timelike};
+        let hour = now.hour() as u8;
+        if self.start_utc_hour < self.end_utc_hour {
+            self.days_of_week.contains(&now.weekday())
+                && (self.start_utc_hour..self.end_utc_hour).contains(&hour)
+        } else if hour >= self.start_utc_hour {
+            self.days_of_week.contains(&now.weekday())
+        } else {
+            // the window started on the previous day and wraps past midnight
+            hour < self.end_utc_hour && self.days_of_week.contains(&now.weekday().pred())
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct SwapInput {
     pub input_token_mint: Pubkey,
@@ -51,3 +81,256 @@ impl SwapExecutionMode {
         matches!(self, SwapExecutionMode::ExactIn)
     }
 }
+
+#[derive(Clone, Debug)]
+pub enum SwapError {
+    TransactionTooLarge { size: usize, limit: usize },
+}
+
+impl std::fmt::Display for SwapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SwapError::TransactionTooLarge { size, limit } => f.write_fmt(format_args!(
+                "Transaction of {} bytes exceeds the {} byte limit",
+                size, limit
+            )),
+        }
+    }
+}
+impl std::error::Error for SwapError {}
+
+#[derive(Copy, Clone, Debug)]
+pub enum RaydiumAccountType {
+    AmmInfo,
+    TargetOrders,
+    WithdrawQueue,
+    LpMint,
+    OpenOrders,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize)]
+pub enum AmmPoolStatus {
+    Uninitialized,
+    Initialized,
+    Disabled,
+    WithdrawOnly,
+    LiquidityOnly,
+    OrderBookOnly,
+    SwapOnly,
+    WaitingTrade,
+    Invalid(u64),
+}
+
+impl From<u64> for AmmPoolStatus {
+    fn from(status: u64) -> Self {
+        match status {
+            0 => AmmPoolStatus::Uninitialized,
+            1 => AmmPoolStatus::Initialized,
+            2 => AmmPoolStatus::Disabled,
+            3 => AmmPoolStatus::WithdrawOnly,
+            4 => AmmPoolStatus::LiquidityOnly,
+            5 => AmmPoolStatus::OrderBookOnly,
+            6 => AmmPoolStatus::SwapOnly,
+            7 => AmmPoolStatus::WaitingTrade,
+            x => AmmPoolStatus::Invalid(x),
+        }
+    }
+}
+
+/// Maps the coefficient of variation of a pool's price to a recommended slippage
+#[derive(Clone, Debug)]
+pub struct VolatilityToSlippageMap {
+    /// `(max_coefficient_of_variation, slippage_bps)` tiers, sorted by ascending volatility
+    pub tiers: Vec<(f64, u16)>,
+    /// The slippage used when the volatility exceeds every tier
+    pub max_slippage_bps: u16,
+}
+
+impl Default for VolatilityToSlippageMap {
+    fn default() -> Self {
+        Self {
+            tiers: vec![(0.001, 50), (0.005, 100), (0.01, 200), (0.02, 300)],
+            max_slippage_bps: 500,
+        }
+    }
+}
+
+impl VolatilityToSlippageMap {
+    pub fn slippage_bps(&self, coefficient_of_variation: f64) -> u16 {
+        self.tiers
+            .iter()
+            .find(|(max_cv, _)| coefficient_of_variation <= *max_cv)
+            .map(|(_, slippage_bps)| *slippage_bps)
+            .unwrap_or(self.max_slippage_bps)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FlashloanRisk {
+    /// No recent transaction moved the pool price abnormally
+    Low,
+    /// A recent transaction moved the pool price by more than 5%
+    High,
+}
+
+/// Which pools the executor accepts when building swap transactions
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum PoolVerificationPolicy {
+    /// Only pools listed by the raydium api
+    StrictApiOnly,
+    /// Pools listed by the raydium api or owned by the raydium amm v4 program
+    #[default]
+    AllowKnownPrograms,
+    /// Any pool, no verification is done
+    AllowAll,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum EntrySignal {
+    /// The price is within one standard deviation of its historical mean
+    GoodEntry,
+    /// The price is more than one standard deviation above its historical mean
+    OverextendedUp,
+    /// The price is more than one standard deviation below its historical mean
+    OverextendedDown,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RouteType {
+    /// A single swap through a pool of the input and output mints
+    Direct,
+    /// Two swaps through an intermediate mint
+    TwoHop { intermediate_mint: Pubkey },
+}
+
+/// Network congestion derived from the mean transactions per second of recent performance samples
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum CongestionLevel {
+    /// More than 3000 tps
+    Low,
+    /// Between 1500 and 3000 tps
+    Medium,
+    /// Between 500 and 1500 tps
+    High,
+    /// Less than 500 tps
+    Extreme,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TokenFlagStatus {
+    /// The token is listed by the raydium api
+    Safe,
+    /// The token is on the configured scam list or the raydium api blacklist
+    Flagged { reason: String },
+    /// The token is on none of the checked lists
+    Unknown,
+}
+
+/// The short-term direction of the pool price relative to its moving average
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+pub enum Trend {
+    /// The price is more than 1% above its moving average
+    Bullish,
+    /// The price is more than 1% below its moving average
+    Bearish,
+    Neutral,
+}
+
+/// The liquidity action suggested by a rebalancing opportunity
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+pub enum LpAction {
+    /// The fees of the rebalancing trade outweigh the impermanent loss it causes
+    AddLiquidity,
+    /// The impermanent loss of the rebalancing trade outweighs its fees
+    RemoveLiquidity,
+    /// The divergence is too small for an arbitrage to pay the pool fee
+    Hold,
+}
+
+/// Whether a liquidity event added or removed liquidity
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+pub enum LiquidityEventType {
+    Add,
+    Remove,
+}
+
+/// The status of a bundle submitted to the jito block engine
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+pub enum BundleStatusEnum {
+    /// The block engine has not reported the bundle as landed yet
+    Pending,
+    /// The bundle landed and its transactions succeeded
+    Landed,
+    /// The bundle landed but one of its transactions failed
+    Failed,
+}
+
+/// How a swap trades off inclusion speed against execution price
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Urgency {
+    /// Optimize for inclusion speed
+    High,
+    /// Balance speed and price
+    Medium,
+    /// Optimize for the best price
+    Low,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TradingWindow, VolatilityToSlippageMap};
+    use chrono::{TimeZone, Utc, Weekday};
+
+    #[test]
+    fn slippage_for_volatility_tiers() {
+        let map = VolatilityToSlippageMap::default();
+        assert_eq!(map.slippage_bps(0.0), 50);
+        assert_eq!(map.slippage_bps(0.001), 50);
+        assert_eq!(map.slippage_bps(0.003), 100);
+        assert_eq!(map.slippage_bps(0.02), 300);
+        assert_eq!(map.slippage_bps(0.5), 500);
+    }
+
+    #[test]
+    fn slippage_without_tiers() {
+        let map = VolatilityToSlippageMap {
+            tiers: vec![],
+            max_slippage_bps: 75,
+        };
+        assert_eq!(map.slippage_bps(0.0), 75);
+    }
+
+    // 2024-01-01 is a Monday
+    fn monday_at(hour: u32) -> chrono::DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn trading_window_within_a_day() {
+        let window = TradingWindow {
+            start_utc_hour: 9,
+            end_utc_hour: 17,
+            days_of_week: vec![Weekday::Mon],
+        };
+        assert!(window.contains(monday_at(9)));
+        assert!(window.contains(monday_at(16)));
+        assert!(!window.contains(monday_at(17)));
+        assert!(!window.contains(monday_at(8)));
+        assert!(!window.contains(monday_at(9) + chrono::Duration::days(1)));
+    }
+
+    #[test]
+    fn trading_window_past_midnight() {
+        let window = TradingWindow {
+            start_utc_hour: 22,
+            end_utc_hour: 2,
+            days_of_week: vec![Weekday::Mon],
+        };
+        assert!(window.contains(monday_at(23)));
+        assert!(!window.contains(monday_at(1)));
+        let tuesday = monday_at(0) + chrono::Duration::days(1);
+        assert!(window.contains(tuesday + chrono::Duration::hours(1)));
+        assert!(!window.contains(tuesday + chrono::Duration::hours(2)));
+        assert!(!window.contains(tuesday + chrono::Duration::hours(23)));
+    }
+}
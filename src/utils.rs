@@ -4,6 +4,7 @@ use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_client::rpc_config::RpcAccountInfoConfig;
 use solana_sdk::account::Account;
 use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::program_pack::Pack;
 use solana_sdk::pubkey::Pubkey;
 
 pub async fn get_multiple_account_data(
@@ -34,3 +35,61 @@ pub async fn get_multiple_account_data(
     }
     Ok(accounts_vec)
 }
+
+/// Reads the token amount of an spl-token account, treating a missing account as empty
+pub fn token_account_amount(account: Option<&Account>) -> anyhow::Result<u64> {
+    match account {
+        Some(account) => Ok(spl_token::state::Account::unpack(&account.data)?.amount),
+        None => Ok(0),
+    }
+}
+
+/// Converts a raw token amount into its decimal representation
+pub fn to_ui_amount(amount: u64, decimals: u64) -> f64 {
+    amount as f64 / 10f64.powi(decimals as i32)
+}
+
+/// The similarity of two token symbols from 0 to 1, one minus their case-insensitive levenshtein
+/// distance divided by the length of the longer symbol. Two empty symbols are not similar.
+pub fn symbol_similarity(a: &str, b: &str) -> f64 {
+    let a = a.to_lowercase().chars().collect::<Vec<_>>();
+    let b = b.to_lowercase().chars().collect::<Vec<_>>();
+    let max_len = a.len().max(b.len());
+    if max_len == 0 {
+        return 0.0;
+    }
+    let mut distances = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.iter().enumerate() {
+        let mut previous_diagonal = distances[0];
+        distances[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous_diagonal + usize::from(ca != cb);
+            previous_diagonal = distances[j + 1];
+            distances[j + 1] = substitution.min(distances[j] + 1).min(distances[j + 1] + 1);
+        }
+    }
+    1.0 - distances[b.len()] as f64 / max_len as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn symbol_similarity_of_symbols() {
+        assert_close(symbol_similarity("USDC", "USDC"), 1.0);
+        assert_close(symbol_similarity("usdc", "USDC"), 1.0);
+        assert_close(symbol_similarity("USDC", "USDT"), 0.75);
+        assert_close(symbol_similarity("SOL", "WSOL"), 0.75);
+        assert_close(symbol_similarity("RAY", "BONK"), 0.0);
+        assert_close(symbol_similarity("", "SOL"), 0.0);
+        assert_close(symbol_similarity("", ""), 0.0);
+    }
+}